@@ -9,7 +9,7 @@ use std::io::Error as IOError;
 use std::path::Path;
 
 use lazy_static::lazy_static;
-use reqwest::{Client as ReqwestClient, RequestBuilder};
+use reqwest::{Body, Client as ReqwestClient, RequestBuilder};
 use reqwest::ClientBuilder;
 use reqwest::Error as ReqwestError;
 use reqwest::IntoUrl;
@@ -41,6 +41,7 @@ pub fn get_user_agent () -> &'static str {
      - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
 
 /// HTTP client
+#[derive(Clone, Debug)]
 pub struct Client {
     client: ReqwestClient,
 }
@@ -76,6 +77,50 @@ impl Client {
         self.run(request).await
     }
 
+    pub async fn post_with_headers<T, B>(&self, url: T, headers: HashMap<String, String>, body: B) -> Result<Response, Error>
+    where T: IntoUrl, B: Into<Body> {
+        let headers = parse_headers(headers);
+
+        let request = self.client
+            .post(url)
+            .headers(headers)
+            .body(body);
+
+        self.run(request).await
+    }
+
+    pub async fn put_with_headers<T, B>(&self, url: T, headers: HashMap<String, String>, body: B) -> Result<Response, Error>
+    where T: IntoUrl, B: Into<Body> {
+        let headers = parse_headers(headers);
+
+        let request = self.client
+            .put(url)
+            .headers(headers)
+            .body(body);
+
+        self.run(request).await
+    }
+
+    pub async fn delete_with_headers<T>(&self, url: T, headers: HashMap<String, String>) -> Result<Response, Error>
+    where T: IntoUrl {
+        let headers = parse_headers(headers);
+
+        let request = self.client
+            .delete(url)
+            .headers(headers);
+
+        self.run(request).await
+    }
+
+    /// Prepares a GET request, to be completed then sent with `run`.
+    ///
+    /// This allows libraries to add their own headers or query parameters,
+    /// while keeping the default headers of this client, like the User-Agent.
+    pub fn prepare_get<T>(&self, url: T) -> RequestBuilder
+    where T: IntoUrl {
+        self.client.get(url)
+    }
+
     pub async fn run(&self, request: RequestBuilder) -> Result<Response, Error> {
         request
             .send()
@@ -0,0 +1,168 @@
+//! # Utilities for database.
+//!
+//! This library provides helpers to interact with a PostgreSQL database,
+//! shared by the Nasqueron Datasources import tools.
+//! Functions expect to work with a connection pool from sqlx crate.
+//!
+//! Tables names can be qualified by their schema, e.g. `ods.postal_codes`,
+//! and are considered to be in the public schema otherwise.
+
+use std::future::Future;
+
+use sqlx::PgPool;
+use sqlx::postgres::PgPoolOptions;
+
+/// The schema of the tables when their name isn't qualified
+static DEFAULT_SCHEMA: &str = "public";
+
+/*   -------------------------------------------------------------
+     Identifiers
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// Encloses an identifier, like a column name, in double quotes.
+pub fn quote_identifier (identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Splits a table name into its schema and its name.
+pub fn split_table_name (table: &str) -> (&str, &str) {
+    match table.split_once('.') {
+        Some((schema, name)) => (schema, name),
+        None => (DEFAULT_SCHEMA, table),
+    }
+}
+
+/// Quotes a table name, and its schema if it's qualified.
+pub fn quote_table_name (table: &str) -> String {
+    match table.split_once('.') {
+        Some((schema, name)) => format!("{}.{}", quote_identifier(schema), quote_identifier(name)),
+        None => quote_identifier(table),
+    }
+}
+
+/*   -------------------------------------------------------------
+     Tables
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+pub struct TableInitializationArgs {
+    pub table_name: String,
+    pub create_table: bool,
+    pub overwrite_table: bool,
+}
+
+pub trait ToTableInitializationArgs {
+    fn to_table_initialization_args(&self) -> TableInitializationArgs;
+}
+
+pub async fn connect_to_db (database_url: &str) -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(3)
+        .connect(database_url)
+        .await
+        .expect("Can't connect to database.")
+}
+
+pub async fn is_table_exists (pool: &PgPool, table: &str) -> bool {
+    let query = r#"
+    SELECT EXISTS (
+               SELECT FROM
+                   pg_tables
+               WHERE
+                       schemaname = $1 AND
+                       tablename  = $2
+           );
+    "#;
+
+    let (schema, name) = split_table_name(table);
+    let result: (bool,) = sqlx::query_as(query)
+        .bind(schema)
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .expect("Can't check if table exists.");
+
+    result.0
+}
+
+pub async fn is_table_empty (pool: &PgPool, table: &str) -> bool {
+    let query = r#"
+    SELECT EXISTS (
+        SELECT 1 FROM %%table%%
+    );
+    "#.replace("%%table%%", &quote_table_name(table));
+
+    let result: (bool,) = sqlx::query_as(&query)
+        .fetch_one(pool)
+        .await
+        .expect("Can't check if table is empty.");
+
+    !result.0
+}
+
+pub async fn truncate_table (pool: &PgPool, table: &str) {
+    let query = format!("TRUNCATE TABLE {} RESTART IDENTITY;", quote_table_name(table));
+
+    sqlx::query(&query)
+        .execute(pool)
+        .await
+        .expect("Can't truncate table.");
+}
+
+pub async fn initialize_table<F, T> (
+    pool: &PgPool,
+    callback: F,
+    args: T
+) -> Result<(), String>
+    where F: Future, T: ToTableInitializationArgs
+{
+    let args = args.to_table_initialization_args();
+    if is_table_exists(pool, &args.table_name).await {
+        if is_table_empty(pool, &args.table_name).await {
+            return Ok(());
+        }
+
+        if args.overwrite_table {
+            truncate_table(pool, &args.table_name).await;
+            return Ok(());
+        }
+
+        return Err(format!(
+            "Table {} already exists and contains rows. To overwrite it, run the import tool with -t option.",
+            &args.table_name
+        ));
+    }
+
+    if args.create_table {
+        callback.await;
+        return Ok(());
+    }
+
+    Err(format!(
+        "Table {} doesn't exist. To create it, run the import tool with -c option.",
+        &args.table_name
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_identifier () {
+        assert_eq!(r#""code_postal""#, quote_identifier("code_postal"));
+        assert_eq!(r#""a""b""#, quote_identifier(r#"a"b"#));
+    }
+
+    #[test]
+    fn test_split_table_name () {
+        assert_eq!(("public", "postal_codes"), split_table_name("postal_codes"));
+        assert_eq!(("ods", "postal_codes"), split_table_name("ods.postal_codes"));
+    }
+
+    #[test]
+    fn test_quote_table_name () {
+        assert_eq!(r#""postal_codes""#, quote_table_name("postal_codes"));
+        assert_eq!(r#""ods"."postal_codes""#, quote_table_name("ods.postal_codes"));
+        assert_eq!(r#""postal_codes"";DROP TABLE users;--""#, quote_table_name(r#"postal_codes";DROP TABLE users;--"#));
+    }
+}
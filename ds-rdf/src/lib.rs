@@ -0,0 +1,88 @@
+//! # Utilities for RDF graphs.
+//!
+//! This library parses Turtle and N-Triples documents into `oxrdf::Graph`,
+//! and serializes graphs to Turtle, for the Nasqueron Datasources components.
+//!
+//! Parsers of other syntaxes can reuse [`resolve_iri`] and [`RdfParseError`].
+
+use std::fmt::{Display, Formatter};
+
+use oxiri::Iri;
+use oxrdf::NamedNode;
+
+pub mod turtle;
+
+/*   -------------------------------------------------------------
+     IRIs
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// Resolves an IRI, possibly relative, against the base IRI.
+pub fn resolve_iri (base_iri: Option<&str>, iri: &str) -> Result<NamedNode, RdfParseError> {
+    let resolved = match base_iri {
+        Some(base_iri) => Iri::parse(base_iri)
+            .and_then(|base_iri| base_iri.resolve(iri))
+            .map(Iri::into_inner),
+        None => Iri::parse(iri.to_string())
+            .map(Iri::into_inner),
+    };
+
+    resolved
+        .map(NamedNode::new_unchecked)
+        .map_err(|error| RdfParseError::new(format!("Invalid IRI <{}>: {}", iri, error)))
+}
+
+/*   -------------------------------------------------------------
+     Errors
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// An error in an RDF document
+#[derive(Clone, Debug, PartialEq)]
+pub struct RdfParseError {
+    pub message: String,
+
+    /// The line where the error occurred, when known
+    pub line: Option<usize>,
+}
+
+impl RdfParseError {
+    pub fn new<S> (message: S) -> Self where S: ToString {
+        Self {
+            message: message.to_string(),
+            line: None,
+        }
+    }
+
+    pub fn at_line (mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+impl Display for RdfParseError {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} at line {}", self.message, line),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RdfParseError {}
+
+/*   -------------------------------------------------------------
+     Tests
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_iri () {
+        assert_eq!(
+            "https://data.economie.gouv.fr/api/v2/catalog/datasets/controle_techn",
+            resolve_iri(Some("https://data.economie.gouv.fr/api/v2/catalog/"), "datasets/controle_techn").unwrap().as_str(),
+        );
+        assert!(resolve_iri(None, "datasets/controle_techn").is_err());
+    }
+}
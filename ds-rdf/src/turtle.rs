@@ -0,0 +1,952 @@
+//! Turtle parser and serializer.
+//!
+//! The parser supports the full Turtle grammar: @prefix, @base and their
+//! SPARQL variants, prefixed names, the `a` keyword, predicate and object lists,
+//! blank nodes, blank nodes property lists, collections, literals with
+//! language tags, datatypes, numbers and booleans, and RDF-star quoted triples.
+//! As N-Triples is a subset of Turtle, it also parses N-Triples documents.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use oxrdf::vocab::{rdf, xsd};
+use oxrdf::{BlankNode, Graph, Literal, NamedNode, Subject, Term, Triple};
+
+use crate::{resolve_iri, RdfParseError};
+
+/*  -------------------------------------------------------------
+    Parser
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+pub fn parse (document: &str, base_iri: Option<&str>) -> Result<Graph, RdfParseError> {
+    let mut parser = TurtleParser {
+        input: document,
+        position: 0,
+        base_iri: base_iri.map(str::to_string),
+        prefixes: HashMap::new(),
+        blank_nodes: HashMap::new(),
+        graph: Graph::new(),
+    };
+
+    parser.parse_document()
+        .map_err(|error| {
+            let line = parser.get_line();
+            error.at_line(line)
+        })?;
+
+    Ok(parser.graph)
+}
+
+struct TurtleParser<'a> {
+    input: &'a str,
+    position: usize,
+    base_iri: Option<String>,
+    prefixes: HashMap<String, String>,
+
+    /// The blank nodes labels, mapped to unique blank nodes
+    blank_nodes: HashMap<String, BlankNode>,
+
+    graph: Graph,
+}
+
+impl<'a> TurtleParser<'a> {
+    fn parse_document (&mut self) -> Result<(), RdfParseError> {
+        loop {
+            self.skip_whitespace();
+            if self.is_eof() {
+                return Ok(());
+            }
+
+            self.parse_statement()?;
+        }
+    }
+
+    fn parse_statement (&mut self) -> Result<(), RdfParseError> {
+        if self.consume("@prefix") {
+            self.parse_prefix()?;
+            return self.expect('.');
+        }
+
+        if self.consume("@base") {
+            self.parse_base()?;
+            return self.expect('.');
+        }
+
+        if self.consume_keyword("PREFIX") {
+            return self.parse_prefix();
+        }
+
+        if self.consume_keyword("BASE") {
+            return self.parse_base();
+        }
+
+        self.parse_triples()?;
+        self.expect('.')
+    }
+
+    fn parse_prefix (&mut self) -> Result<(), RdfParseError> {
+        self.skip_whitespace();
+        let prefix = self.read_prefix()?.to_string();
+        self.expect(':')?;
+        self.skip_whitespace();
+        let iri = self.parse_iri_ref()?;
+
+        self.prefixes.insert(prefix, iri.into_string());
+        Ok(())
+    }
+
+    fn parse_base (&mut self) -> Result<(), RdfParseError> {
+        self.skip_whitespace();
+        let iri = self.parse_iri_ref()?;
+
+        self.base_iri = Some(iri.into_string());
+        Ok(())
+    }
+
+    fn parse_triples (&mut self) -> Result<(), RdfParseError> {
+        self.skip_whitespace();
+
+        if self.peek() == Some('[') {
+            let subject = self.parse_blank_node_property_list()?.into();
+
+            // The predicate object list is optional after [ ... ]
+            self.skip_whitespace();
+            if self.peek() == Some('.') {
+                return Ok(());
+            }
+
+            return self.parse_predicate_object_list(&subject);
+        }
+
+        let subject = self.parse_subject()?;
+        self.parse_predicate_object_list(&subject)
+    }
+
+    fn parse_subject (&mut self) -> Result<Subject, RdfParseError> {
+        match self.peek() {
+            Some('<') if self.is_quoted_triple() => Ok(Subject::Triple(Box::new(self.parse_quoted_triple()?))),
+            Some('(') => self.parse_collection(),
+            Some('_') => Ok(self.parse_blank_node_label()?.into()),
+            _ => Ok(self.parse_iri()?.into()),
+        }
+    }
+
+    fn parse_predicate_object_list (&mut self, subject: &Subject) -> Result<(), RdfParseError> {
+        loop {
+            self.skip_whitespace();
+            let predicate = self.parse_verb()?;
+            self.parse_object_list(subject, &predicate)?;
+
+            self.skip_whitespace();
+            if !self.consume(";") {
+                return Ok(());
+            }
+
+            // Repeated or trailing semicolons are allowed.
+            loop {
+                self.skip_whitespace();
+                if !self.consume(";") {
+                    break;
+                }
+            }
+
+            match self.peek() {
+                Some('.') | Some(']') | None => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn parse_verb (&mut self) -> Result<NamedNode, RdfParseError> {
+        if self.peek() == Some('a') {
+            let next = self.input[self.position + 1..].chars().next();
+            if next.is_none_or(|c| c.is_whitespace() || c == '<' || c == '[' || c == '"') {
+                self.position += 1;
+                return Ok(rdf::TYPE.into_owned());
+            }
+        }
+
+        self.parse_iri()
+    }
+
+    fn parse_object_list (&mut self, subject: &Subject, predicate: &NamedNode) -> Result<(), RdfParseError> {
+        loop {
+            self.skip_whitespace();
+            let object = self.parse_object()?;
+            self.graph.insert(&Triple::new(subject.clone(), predicate.clone(), object));
+
+            self.skip_whitespace();
+            if !self.consume(",") {
+                return Ok(());
+            }
+        }
+    }
+
+    fn parse_object (&mut self) -> Result<Term, RdfParseError> {
+        match self.peek() {
+            Some('<') if self.is_quoted_triple() => Ok(Term::Triple(Box::new(self.parse_quoted_triple()?))),
+            Some('<') => Ok(self.parse_iri_ref()?.into()),
+            Some('_') => Ok(self.parse_blank_node_label()?.into()),
+            Some('[') => Ok(self.parse_blank_node_property_list()?.into()),
+            Some('(') => Ok(self.parse_collection()?.into()),
+            Some('"') | Some('\'') => Ok(self.parse_rdf_literal()?.into()),
+            Some(c) if c.is_ascii_digit() || c == '+' || c == '-' || c == '.' => Ok(self.parse_numeric_literal()?.into()),
+            Some(_) if self.consume_boolean("true") => Ok(Literal::new_typed_literal("true", xsd::BOOLEAN).into()),
+            Some(_) if self.consume_boolean("false") => Ok(Literal::new_typed_literal("false", xsd::BOOLEAN).into()),
+            Some(_) => Ok(self.parse_prefixed_name()?.into()),
+            None => Err(RdfParseError::new("Unexpected end of document, expected an object")),
+        }
+    }
+
+    /*  -------------------------------------------------------------
+        RDF-star quoted triples
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    fn is_quoted_triple (&self) -> bool {
+        self.input[self.position..].starts_with("<<")
+    }
+
+    fn parse_quoted_triple (&mut self) -> Result<Triple, RdfParseError> {
+        self.consume("<<");
+
+        self.skip_whitespace();
+        self.check_quoted_term()?;
+        let subject = self.parse_subject()?;
+
+        self.skip_whitespace();
+        let predicate = self.parse_verb()?;
+
+        self.skip_whitespace();
+        self.check_quoted_term()?;
+        let object = self.parse_object()?;
+
+        self.skip_whitespace();
+        if !self.consume(">>") {
+            return Err(RdfParseError::new("Expected \">>\" at the end of a quoted triple"));
+        }
+
+        Ok(Triple::new(subject, predicate, object))
+    }
+
+    /// Rejects the terms which would add triples to the graph, as a quoted triple only quotes one.
+    fn check_quoted_term (&self) -> Result<(), RdfParseError> {
+        match self.peek() {
+            Some('(') | Some('[') => Err(RdfParseError::new(
+                "Collections and blank nodes property lists can't be used in a quoted triple"
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /*  -------------------------------------------------------------
+        Blank nodes and collections
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    fn parse_blank_node_label (&mut self) -> Result<BlankNode, RdfParseError> {
+        if !self.consume("_:") {
+            return Err(RdfParseError::new("Expected a blank node label"));
+        }
+
+        let label = self.read_name(|c| is_pn_chars_u(c) || c.is_ascii_digit());
+        if label.is_empty() {
+            return Err(RdfParseError::new("Invalid blank node label"));
+        }
+
+        let node = self.blank_nodes
+            .entry(label.to_string())
+            .or_default()
+            .clone();
+
+        Ok(node)
+    }
+
+    fn parse_blank_node_property_list (&mut self) -> Result<BlankNode, RdfParseError> {
+        self.expect('[')?;
+        let node = BlankNode::default();
+
+        self.skip_whitespace();
+        if self.consume("]") {
+            return Ok(node);
+        }
+
+        self.parse_predicate_object_list(&node.clone().into())?;
+        self.expect(']')?;
+
+        Ok(node)
+    }
+
+    fn parse_collection (&mut self) -> Result<Subject, RdfParseError> {
+        self.expect('(')?;
+
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.consume(")") {
+                break;
+            }
+
+            items.push(self.parse_object()?);
+        }
+
+        let mut list: Subject = rdf::NIL.into_owned().into();
+        for item in items.into_iter().rev() {
+            let node = BlankNode::default();
+            self.graph.insert(&Triple::new(node.clone(), rdf::FIRST.into_owned(), item));
+            self.graph.insert(&Triple::new(node.clone(), rdf::REST.into_owned(), list));
+            list = node.into();
+        }
+
+        Ok(list)
+    }
+
+    /*  -------------------------------------------------------------
+        IRIs
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    fn parse_iri (&mut self) -> Result<NamedNode, RdfParseError> {
+        if self.peek() == Some('<') {
+            self.parse_iri_ref()
+        } else {
+            self.parse_prefixed_name()
+        }
+    }
+
+    fn parse_iri_ref (&mut self) -> Result<NamedNode, RdfParseError> {
+        self.expect('<')?;
+
+        let mut iri = String::new();
+        loop {
+            match self.next_char() {
+                Some('>') => break,
+                Some('\\') => iri.push(self.parse_unicode_escape()?),
+                Some(c) if c <= ' ' || "<\"{}|^`".contains(c) => {
+                    return Err(RdfParseError::new(format!("Invalid character in IRI: {:?}", c)));
+                }
+                Some(c) => iri.push(c),
+                None => return Err(RdfParseError::new("Unexpected end of document in IRI")),
+            }
+        }
+
+        resolve_iri(self.base_iri.as_deref(), &iri)
+    }
+
+    fn parse_prefixed_name (&mut self) -> Result<NamedNode, RdfParseError> {
+        let prefix = self.read_prefix()?.to_string();
+        if !self.consume(":") {
+            return Err(RdfParseError::new(format!("Expected a prefixed name, found {:?}", prefix)));
+        }
+
+        let namespace = match self.prefixes.get(&prefix) {
+            Some(namespace) => namespace.clone(),
+            None => return Err(RdfParseError::new(format!("Undefined prefix: {}", prefix))),
+        };
+
+        let local_name = self.parse_local_name()?;
+
+        Ok(NamedNode::new_unchecked(format!("{}{}", namespace, local_name)))
+    }
+
+    /// Parses the local name of a prefixed name, as the PN_LOCAL rule of the grammar.
+    fn parse_local_name (&mut self) -> Result<String, RdfParseError> {
+        let mut local_name = String::new();
+
+        // Dots can't end a local name, they end the statement instead.
+        let mut trailing_dots = 0;
+
+        loop {
+            let is_first = local_name.is_empty();
+
+            match self.peek() {
+                Some('\\') => {
+                    self.position += 1;
+                    match self.next_char() {
+                        Some(c) if LOCAL_NAME_ESCAPED_CHARS.contains(c) => local_name.push(c),
+                        c => return Err(RdfParseError::new(format!(
+                            "Invalid escape sequence in local name: \\{}", c.unwrap_or(' ')
+                        ))),
+                    }
+                    trailing_dots = 0;
+                }
+                Some('%') => {
+                    let digits = self.input.get(self.position + 1..self.position + 3)
+                        .filter(|digits| digits.chars().all(|c| c.is_ascii_hexdigit()))
+                        .ok_or_else(|| RdfParseError::new("Invalid percent encoding in local name"))?;
+                    local_name.push('%');
+                    local_name.push_str(digits);
+                    self.position += 3;
+                    trailing_dots = 0;
+                }
+                Some(c) if is_first && (is_pn_chars_u(c) || c == ':' || c.is_ascii_digit()) => {
+                    local_name.push(c);
+                    self.position += c.len_utf8();
+                }
+                Some(c) if !is_first && (is_pn_chars(c) || c == ':' || c == '.') => {
+                    local_name.push(c);
+                    self.position += c.len_utf8();
+                    trailing_dots = if c == '.' { trailing_dots + 1 } else { 0 };
+                }
+                _ => break,
+            }
+        }
+
+        local_name.truncate(local_name.len() - trailing_dots);
+        self.position -= trailing_dots;
+
+        Ok(local_name)
+    }
+
+    /*  -------------------------------------------------------------
+        Literals
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    fn parse_rdf_literal (&mut self) -> Result<Literal, RdfParseError> {
+        let value = self.parse_string()?;
+
+        if self.consume("@") {
+            let language = self.read_while(|c| c.is_ascii_alphanumeric() || c == '-').to_string();
+            return Literal::new_language_tagged_literal(value, &language)
+                .map_err(|error| RdfParseError::new(format!("Invalid language tag {}: {}", language, error)));
+        }
+
+        if self.consume("^^") {
+            let datatype = self.parse_iri()?;
+            if datatype.as_ref() == xsd::STRING {
+                return Ok(Literal::new_simple_literal(value));
+            }
+
+            return Ok(Literal::new_typed_literal(value, datatype));
+        }
+
+        Ok(Literal::new_simple_literal(value))
+    }
+
+    fn parse_string (&mut self) -> Result<String, RdfParseError> {
+        let quote = match self.next_char() {
+            Some(c) if c == '"' || c == '\'' => c,
+            _ => return Err(RdfParseError::new("Expected a string")),
+        };
+
+        let long_quote: String = std::iter::repeat_n(quote, 3).collect();
+        let is_long = self.input[self.position - 1..].starts_with(&long_quote);
+        if is_long {
+            self.position += 2;
+        }
+
+        let mut value = String::new();
+        loop {
+            if is_long && self.consume(&long_quote) {
+                // Up to two quotes can end the content: """a"""" is a"
+                while self.peek() == Some(quote) {
+                    value.push(quote);
+                    self.position += 1;
+                }
+                return Ok(value);
+            }
+
+            match self.next_char() {
+                Some(c) if c == quote && !is_long => return Ok(value),
+                Some('\\') => value.push(self.parse_string_escape()?),
+                Some('\n') | Some('\r') if !is_long => {
+                    return Err(RdfParseError::new("Unexpected end of line in string"));
+                }
+                Some(c) => value.push(c),
+                None => return Err(RdfParseError::new("Unexpected end of document in string")),
+            }
+        }
+    }
+
+    fn parse_string_escape (&mut self) -> Result<char, RdfParseError> {
+        match self.peek() {
+            Some('u') | Some('U') => self.parse_unicode_escape(),
+            _ => match self.next_char() {
+                Some('t') => Ok('\t'),
+                Some('b') => Ok('\u{8}'),
+                Some('n') => Ok('\n'),
+                Some('r') => Ok('\r'),
+                Some('f') => Ok('\u{c}'),
+                Some(c) if c == '"' || c == '\'' || c == '\\' => Ok(c),
+                c => Err(RdfParseError::new(format!("Invalid escape sequence: \\{}", c.unwrap_or(' ')))),
+            }
+        }
+    }
+
+    /// Parses \uXXXX and \UXXXXXXXX escapes, after the backslash.
+    fn parse_unicode_escape (&mut self) -> Result<char, RdfParseError> {
+        let length = match self.next_char() {
+            Some('u') => 4,
+            Some('U') => 8,
+            _ => return Err(RdfParseError::new("Invalid escape sequence")),
+        };
+
+        let digits = self.input.get(self.position..self.position + length)
+            .ok_or_else(|| RdfParseError::new("Unexpected end of document in escape sequence"))?;
+        self.position += length;
+
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| RdfParseError::new(format!("Invalid escape sequence: {}", digits)))
+    }
+
+    fn parse_numeric_literal (&mut self) -> Result<Literal, RdfParseError> {
+        let start = self.position;
+        if !self.consume("+") {
+            self.consume("-");
+        }
+        let integer_digits = self.read_while(|c| c.is_ascii_digit()).len();
+
+        let mut datatype = xsd::INTEGER;
+        let mut fraction_digits = 0;
+        if self.peek() == Some('.') && self.input[self.position + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+            self.position += 1;
+            fraction_digits = self.read_while(|c| c.is_ascii_digit()).len();
+            datatype = xsd::DECIMAL;
+        }
+
+        if self.peek() == Some('e') || self.peek() == Some('E') {
+            self.position += 1;
+            if !self.consume("+") {
+                self.consume("-");
+            }
+            if self.read_while(|c| c.is_ascii_digit()).is_empty() {
+                let value = &self.input[start..self.position];
+                return Err(RdfParseError::new(format!("Invalid number, exponent without digits: {}", value)));
+            }
+            datatype = xsd::DOUBLE;
+        }
+
+        let value = &self.input[start..self.position];
+        if integer_digits + fraction_digits == 0 {
+            return Err(RdfParseError::new(format!("Invalid number: {}", value)));
+        }
+
+        Ok(Literal::new_typed_literal(value, datatype))
+    }
+
+    /*  -------------------------------------------------------------
+        Input helpers
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    fn is_eof (&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    fn peek (&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next_char (&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+
+        Some(c)
+    }
+
+    fn consume (&mut self, expected: &str) -> bool {
+        if self.input[self.position..].starts_with(expected) {
+            self.position += expected.len();
+            return true;
+        }
+
+        false
+    }
+
+    /// Consumes a SPARQL-style keyword, case-insensitive, followed by a whitespace.
+    fn consume_keyword (&mut self, keyword: &str) -> bool {
+        let candidate = match self.input.get(self.position..self.position + keyword.len()) {
+            Some(candidate) => candidate,
+            None => return false,
+        };
+
+        let is_followed_by_whitespace = self.input[self.position + keyword.len()..]
+            .starts_with(char::is_whitespace);
+        if candidate.eq_ignore_ascii_case(keyword) && is_followed_by_whitespace {
+            self.position += keyword.len();
+            return true;
+        }
+
+        false
+    }
+
+    fn consume_boolean (&mut self, value: &str) -> bool {
+        let is_boolean = self.input[self.position..].starts_with(value)
+            && !self.input[self.position + value.len()..].starts_with(|c: char| is_pn_chars(c) || c == ':');
+
+        if is_boolean {
+            self.position += value.len();
+        }
+
+        is_boolean
+    }
+
+    fn expect (&mut self, expected: char) -> Result<(), RdfParseError> {
+        self.skip_whitespace();
+
+        match self.next_char() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(RdfParseError::new(format!("Expected {:?}, found {:?}", expected, c))),
+            None => Err(RdfParseError::new(format!("Expected {:?}, found end of document", expected))),
+        }
+    }
+
+    fn read_while<F> (&mut self, predicate: F) -> &'a str where F: Fn(char) -> bool {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if !predicate(c) {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+
+        &self.input[start..self.position]
+    }
+
+    /// Reads a prefix, as the PN_PREFIX rule of the grammar, possibly empty.
+    fn read_prefix (&mut self) -> Result<&'a str, RdfParseError> {
+        let prefix = self.read_name(is_pn_chars_base);
+
+        match self.peek() {
+            Some(c) if c != ':' && (is_pn_chars(c) || c == '.') => {
+                Err(RdfParseError::new(format!("Invalid character in prefix: {:?}", c)))
+            }
+            _ => Ok(prefix),
+        }
+    }
+
+    /// Reads a name starting by a character allowed by `is_first`,
+    /// followed by PN_CHARS characters or dots, but not ending by a dot.
+    fn read_name<F> (&mut self, is_first: F) -> &'a str where F: Fn(char) -> bool {
+        let start = self.position;
+
+        match self.peek() {
+            Some(c) if is_first(c) => self.position += c.len_utf8(),
+            _ => return "",
+        }
+        self.read_while(|c| is_pn_chars(c) || c == '.');
+
+        // A name can't end with a dot, which ends the statement instead.
+        let name = self.input[start..self.position].trim_end_matches('.');
+        self.position = start + name.len();
+
+        name
+    }
+
+    /// Skips whitespaces and comments.
+    fn skip_whitespace (&mut self) {
+        loop {
+            self.read_while(char::is_whitespace);
+
+            if self.peek() != Some('#') {
+                return;
+            }
+            self.read_while(|c| c != '\n');
+        }
+    }
+
+    fn get_line (&self) -> usize {
+        self.input[..self.position.min(self.input.len())].matches('\n').count() + 1
+    }
+}
+
+/*  -------------------------------------------------------------
+    Serializer
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Serializes a graph to Turtle, grouping triples by subject and predicate.
+///
+/// Terms are written with full IRIs, as no prefix is declared.
+pub fn serialize (graph: &Graph) -> String {
+    let mut subjects: BTreeMap<String, BTreeMap<String, Vec<String>>> = BTreeMap::new();
+    for triple in graph.iter() {
+        subjects
+            .entry(triple.subject.to_string())
+            .or_default()
+            .entry(triple.predicate.to_string())
+            .or_default()
+            .push(triple.object.to_string());
+    }
+
+    let mut output = String::new();
+    for (subject, predicates) in subjects {
+        let predicates: Vec<_> = predicates
+            .iter()
+            .map(|(predicate, objects)| format!("{} {}", predicate, objects.join(" , ")))
+            .collect();
+
+        writeln!(output, "{} {} .", subject, predicates.join(" ;\n    "))
+            .expect("Writing to a String can't fail");
+    }
+
+    output
+}
+
+/*  -------------------------------------------------------------
+    Characters classes of the Turtle grammar
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// The characters which can be escaped by a backslash in a local name
+static LOCAL_NAME_ESCAPED_CHARS: &str = "_~.-!$&'()*+,;=/?#@%";
+
+fn is_pn_chars_base (c: char) -> bool {
+    matches!(c,
+        'A'..='Z' | 'a'..='z'
+        | '\u{00C0}'..='\u{00D6}' | '\u{00D8}'..='\u{00F6}' | '\u{00F8}'..='\u{02FF}'
+        | '\u{0370}'..='\u{037D}' | '\u{037F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+fn is_pn_chars_u (c: char) -> bool {
+    is_pn_chars_base(c) || c == '_'
+}
+
+fn is_pn_chars (c: char) -> bool {
+    is_pn_chars_u(c)
+        || c == '-'
+        || c.is_ascii_digit()
+        || matches!(c, '\u{00B7}' | '\u{0300}'..='\u{036F}' | '\u{203F}'..='\u{2040}')
+}
+
+#[cfg(test)]
+mod tests {
+    use oxrdf::{LiteralRef, NamedNodeRef, TermRef};
+
+    use super::*;
+
+    static DOCUMENT: &str = r#"@prefix dcat: <http://www.w3.org/ns/dcat#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+@base <https://data.economie.gouv.fr/api/v2/catalog/> .
+
+# A dataset with two distributions
+<datasets/controle_techn> a dcat:Dataset ;
+    dct:title "Contrôle technique \"VL\""@fr ;
+    dct:description """Tarifs des
+contrôles techniques""" ;
+    dct:identifier 'controle_techn' ;
+    dcat:keyword "auto", "prix" ;
+    dct:issued "2019-03-01"^^<http://www.w3.org/2001/XMLSchema#date> ;
+    dct:publisher [ a <http://xmlns.com/foaf/0.1/Agent> ; dct:title "DGE" ] ;
+    dcat:distribution <datasets/controle_techn-csv>, _:json ;
+    <http://example.org/size> 42 ;
+    <http://example.org/ratio> 0.5 ;
+    <http://example.org/open> true ;
+    <http://example.org/order> ( "csv" "json" ) .
+
+_:json dcat:mediaType "application/json" .
+"#;
+
+    fn dct (local_name: &str) -> NamedNode {
+        NamedNode::new_unchecked(format!("http://purl.org/dc/terms/{}", local_name))
+    }
+
+    #[test]
+    fn test_parse () {
+        let graph = parse(DOCUMENT, None).unwrap();
+        let dataset = NamedNodeRef::new_unchecked("https://data.economie.gouv.fr/api/v2/catalog/datasets/controle_techn");
+
+        assert_eq!(21, graph.len());
+        assert_eq!(
+            Some(TermRef::NamedNode(NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#Dataset"))),
+            graph.object_for_subject_predicate(dataset, rdf::TYPE),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_language_tagged_literal_unchecked("Contrôle technique \"VL\"", "fr").into()),
+            graph.object_for_subject_predicate(dataset, &dct("title")),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_simple_literal("Tarifs des\ncontrôles techniques").into()),
+            graph.object_for_subject_predicate(dataset, &dct("description")),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_simple_literal("controle_techn").into()),
+            graph.object_for_subject_predicate(dataset, &dct("identifier")),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_typed_literal("0.5", xsd::DECIMAL).into()),
+            graph.object_for_subject_predicate(dataset, NamedNodeRef::new_unchecked("http://example.org/ratio")),
+        );
+        assert_eq!(
+            2,
+            graph.objects_for_subject_predicate(dataset, NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#distribution")).count(),
+        );
+    }
+
+    #[test]
+    fn test_parse_with_sparql_directives () {
+        let document = "PREFIX ex: <http://example.org/>\nex:a ex:b ex:c.\n";
+
+        let graph = parse(document, None).unwrap();
+        assert!(graph.contains(&Triple::new(
+            NamedNode::new_unchecked("http://example.org/a"),
+            NamedNode::new_unchecked("http://example.org/b"),
+            NamedNode::new_unchecked("http://example.org/c"),
+        )));
+    }
+
+    #[test]
+    fn test_parse_when_invalid () {
+        let document = "@prefix ex: <http://example.org/> .\n\nex:a ex:b unknown:c .\n";
+
+        let error = parse(document, None).unwrap_err();
+        assert_eq!("Undefined prefix: unknown", error.message);
+        assert_eq!(Some(3), error.line);
+    }
+
+    fn parse_object_of (object: &str) -> Result<Term, RdfParseError> {
+        let document = format!("@prefix ex: <http://example.org/> .\nex:s ex:p {} .\n", object);
+
+        let graph = parse(&document, None)?;
+        assert_eq!(1, graph.len());
+
+        Ok(graph.iter().next().unwrap().object.into_owned())
+    }
+
+    #[test]
+    fn test_parse_valid_terms () {
+        let named_nodes = [
+            ("ex:a", "http://example.org/a"),
+            ("ex:1a", "http://example.org/1a"),
+            ("ex:a.b", "http://example.org/a.b"),
+            ("ex:a-b", "http://example.org/a-b"),
+            ("ex:_a", "http://example.org/_a"),
+            ("ex:a:b", "http://example.org/a:b"),
+            ("ex:a\\~b", "http://example.org/a~b"),
+            ("ex:\\.a", "http://example.org/.a"),
+            ("ex:a%20b", "http://example.org/a%20b"),
+            ("ex:été", "http://example.org/été"),
+            ("ex:", "http://example.org/"),
+        ];
+        for (object, expected) in named_nodes {
+            assert_eq!(Ok(NamedNode::new_unchecked(expected).into()), parse_object_of(object), "{}", object);
+        }
+
+        let literals = [
+            ("1", "1", xsd::INTEGER),
+            ("-1", "-1", xsd::INTEGER),
+            ("+1", "+1", xsd::INTEGER),
+            ("1.5", "1.5", xsd::DECIMAL),
+            (".5", ".5", xsd::DECIMAL),
+            ("1e3", "1e3", xsd::DOUBLE),
+            ("1.5E-3", "1.5E-3", xsd::DOUBLE),
+            (".5e+3", ".5e+3", xsd::DOUBLE),
+        ];
+        for (object, value, datatype) in literals {
+            assert_eq!(Ok(Literal::new_typed_literal(value, datatype).into()), parse_object_of(object), "{}", object);
+        }
+
+        assert!(matches!(parse_object_of("_:b1"), Ok(Term::BlankNode(_))));
+        assert!(matches!(parse_object_of("_:1b"), Ok(Term::BlankNode(_))));
+        assert!(matches!(parse_object_of("_:a.b"), Ok(Term::BlankNode(_))));
+    }
+
+    #[test]
+    fn test_parse_invalid_terms () {
+        let objects = [
+            // Local names
+            "ex:-a",
+            "ex:.a",
+            "ex:~a",
+            "ex:a\\b",
+            "ex:a%2",
+            "ex:a%zz",
+            // Numbers
+            "1e",
+            "1.5e+",
+            "+-1",
+            "-",
+            // Blank nodes
+            "_:-a",
+            "_:.a",
+            "_:",
+            // IRIs
+            "<http://example.org/a b>",
+            "<http://example.org/{a}>",
+        ];
+
+        for object in objects {
+            assert!(parse_object_of(object).is_err(), "{} should be rejected", object);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_prefix () {
+        let documents = [
+            "@prefix -ex: <http://example.org/> .\n",
+            "@prefix 1ex: <http://example.org/> .\n",
+            "@prefix ex.: <http://example.org/> .\n",
+        ];
+
+        for document in documents {
+            assert!(parse(document, None).is_err(), "{} should be rejected", document);
+        }
+    }
+
+    #[test]
+    fn test_parse_ntriples () {
+        let document = r#"# Rue de la Paix
+<http://www.wikidata.org/entity/Q1630316> <http://www.w3.org/2000/01/rdf-schema#label> "Rue de la \"Paix\""@fr . # label
+<http://www.wikidata.org/entity/Q1630316> <http://www.wikidata.org/prop/direct/P3182> "751566969Y".
+_:b1 <http://example.org/p> "caf\u00E9\ttab"^^<http://www.w3.org/2001/XMLSchema#string> .
+"#;
+
+        let graph = parse(document, None).unwrap();
+        assert_eq!(3, graph.len());
+
+        let literal = Literal::new_simple_literal("café\ttab");
+        assert_eq!(1, graph.iter().filter(|triple| triple.object == literal.as_ref().into()).count());
+    }
+
+    #[test]
+    fn test_parse_quoted_triples () {
+        let document = r#"@prefix ex: <http://example.org/> .
+<< _:b1 ex:p _:b2 >> ex:source ex:s .
+ex:s ex:quotes << ex:a ex:b "c"@fr >> .
+"#;
+
+        let graph = parse(document, None).unwrap();
+        assert_eq!(2, graph.len());
+        assert!(graph.iter().any(|triple| matches!(triple.subject, oxrdf::SubjectRef::Triple(_))));
+        assert!(graph.iter().any(|triple| matches!(triple.object, TermRef::Triple(_))));
+
+        let document = "@prefix ex: <http://example.org/> .\nex:s ex:p << ex:a ex:b ( ex:c ) >> .\n";
+        assert!(parse(document, None).is_err());
+    }
+
+    #[test]
+    fn test_parse_maps_blank_nodes_per_document () {
+        let document = "_:b1 <http://example.org/p> _:b1 .\n";
+
+        let first = parse(document, None).unwrap();
+        let second = parse(document, None).unwrap();
+
+        let first = first.iter().next().unwrap().into_owned();
+        let second = second.iter().next().unwrap().into_owned();
+        assert_eq!(Term::from(first.subject.clone()), first.object);
+        assert_ne!(first.subject, second.subject);
+    }
+
+    #[test]
+    fn test_serialize () {
+        let item = NamedNode::new_unchecked("http://www.wikidata.org/entity/Q1630316");
+
+        let mut graph = Graph::new();
+        graph.insert(&Triple::new(
+            item.clone(),
+            NamedNode::new_unchecked("http://www.w3.org/2000/01/rdf-schema#label"),
+            Literal::new_language_tagged_literal("Rue de la \"Paix\"", "fr").unwrap(),
+        ));
+        graph.insert(&Triple::new(
+            item,
+            NamedNode::new_unchecked("http://www.wikidata.org/prop/direct/P3182"),
+            Literal::new_simple_literal("751566969Y"),
+        ));
+
+        let expected = r#"<http://www.wikidata.org/entity/Q1630316> <http://www.w3.org/2000/01/rdf-schema#label> "Rue de la \"Paix\""@fr ;
+    <http://www.wikidata.org/prop/direct/P3182> "751566969Y" .
+"#;
+        let document = serialize(&graph);
+        assert_eq!(expected, document);
+        assert_eq!(graph, parse(&document, None).unwrap());
+    }
+}
@@ -7,6 +7,7 @@ use std::path::PathBuf;
 use std::process::exit;
 
 use chrono::Utc;
+use ds_http_client::Client;
 use tokio::fs::remove_file;
 use opendatasoft_explore_api::requests::ExploreApiEndPoint;
 use tokio::process::Command;
@@ -22,7 +23,8 @@ static ENDPOINT: &'static str = "https://data.economie.gouv.fr/api/v2";
 static DATASET_ID: &'static str = "fichier-fantoir-des-voies-et-lieux-dits";
 
 pub async fn fetch (overwrite: bool) {
-    let fantoir_file = get_last_file_information().await;
+    let http_client = build_http_client();
+    let fantoir_file = get_last_file_information(&http_client).await;
 
     let file_exists = fantoir_file.exists_locally();
     if file_exists && !overwrite {
@@ -36,7 +38,7 @@ pub async fn fetch (overwrite: bool) {
     }
 
     let target_path = get_fantoir_zip_path();
-    if let Err(error) = build_http_client().download(&fantoir_file.url, &target_path).await {
+    if let Err(error) = http_client.download(&fantoir_file.url, &target_path).await {
         eprintln!("Can't download FANTOIR file: {:?}", error);
         exit(16);
     }
@@ -121,9 +123,13 @@ fn find_extracted_file(stdout: Vec<u8>) -> Option<String> {
     None
 }
 
-pub async fn get_last_file_information () -> FantoirFile {
-    let endpoint = ExploreApiEndPoint::new(ENDPOINT);
-    let result = endpoint.get_dataset_attachments(DATASET_ID).await;
+pub async fn get_last_file_information (http_client: &Client) -> FantoirFile {
+    let endpoint = ExploreApiEndPoint::from_http_client(ENDPOINT, http_client.clone());
+    let result = endpoint.get_dataset_attachments(DATASET_ID).await
+        .unwrap_or_else(|error| {
+            eprintln!("Can't query FANTOIR dataset attachments: {}", error);
+            exit(8);
+        });
 
     result
         .attachments
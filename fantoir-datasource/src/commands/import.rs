@@ -1,16 +1,28 @@
 //! Import command for the fantoir-datasource tool.
 //!
 //! Import from FANTOIR file generated by the DGFIP
+//!
+//! Rows are streamed to PostgreSQL through `COPY ... FROM STDIN` in CSV format,
+//! then the secondary indexes are built. The truncation of the table, the indexes drop,
+//! the copy and the indexes build run in one transaction, so a failed import leaves
+//! the table as it was.
 
 use std::process::exit;
+use std::time::Instant;
 
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use tokio::fs::File;
 use tokio::io::{AsyncBufReadExt, BufReader};
 
 use crate::ImportArgs;
 use crate::db::*;
-use crate::fantoir::FantoirEntry;
+use crate::fantoir::{FantoirEntry, IMPORT_COLUMNS};
+
+/// The number of rows to send to PostgreSQL by COPY data message
+static BATCH_SIZE: usize = 10_000;
+
+/// The secondary indexes, built after the rows are loaded
+static SECONDARY_INDEXES_SCHEMA: &str = include_str!("../schema/fantoir_indexes.sql");
 
 impl ToTableInitializationArgs for &ImportArgs {
     fn to_table_initialization_args (&self) -> TableInitializationArgs {
@@ -23,34 +35,94 @@ impl ToTableInitializationArgs for &ImportArgs {
 }
 
 async fn create_table(pool: &PgPool, table: &str) {
-    let queries = include_str!("../schema/fantoir.sql")
-        .replace("/*table*/fantoir", table)
-        .replace("/*index*/index_fantoir_", format!("index_{}_", table).as_ref());
+    let queries = replace_markers(include_str!("../schema/fantoir.sql"), table);
 
     run_multiple_queries(pool, &queries).await;
 }
 
+async fn drop_secondary_indexes(connection: &mut PgConnection, table: &str) {
+    for index in get_indexes_names(SECONDARY_INDEXES_SCHEMA) {
+        let query = format!("DROP INDEX IF EXISTS {}", get_index_name(table, index, true));
+
+        sqlx::query(&query)
+            .execute(&mut *connection)
+            .await
+            .expect("Can't drop index.");
+    }
+}
+
+async fn truncate_table_on_connection(connection: &mut PgConnection, table: &str) {
+    let query = format!("TRUNCATE TABLE {} RESTART IDENTITY;", quote_table_name(table));
+
+    sqlx::query(&query)
+        .execute(connection)
+        .await
+        .expect("Can't truncate table.");
+}
+
+async fn create_secondary_indexes(connection: &mut PgConnection, table: &str) {
+    let queries = replace_markers(SECONDARY_INDEXES_SCHEMA, table);
+
+    run_multiple_queries_on_connection(connection, &queries).await;
+}
+
 pub async fn import(args: &ImportArgs, database_url: &str) {
     let fd = File::open(&args.fantoir_file).await.expect("Can't open file.");
     let pool  = connect_to_db(database_url).await;
 
-    // Create/truncate table as needed and as allowed by options
-    let callback = async {
-        create_table(&pool, &args.fantoir_table).await;
-    };
-    if let Err(error) = initialize_table(&pool, callback, args).await {
-        eprintln!("{}", &error);
-        exit(1);
+    // A table to overwrite is truncated in the import transaction,
+    // so its rows are kept if the import fails.
+    let table = &args.fantoir_table;
+    let must_truncate = args.overwrite_table
+        && is_table_exists(&pool, table).await
+        && !is_table_empty(&pool, table).await;
+
+    // Create table as needed and as allowed by options
+    if !must_truncate {
+        let callback = async {
+            create_table(&pool, table).await;
+        };
+        if let Err(error) = initialize_table(&pool, callback, args).await {
+            eprintln!("{}", &error);
+            exit(1);
+        }
+    }
+
+    // If the import fails, the transaction is rolled back when dropped,
+    // restoring the rows and the indexes.
+    let mut transaction = pool.begin().await.expect("Can't start transaction.");
+
+    if must_truncate {
+        truncate_table_on_connection(&mut transaction, table).await;
     }
 
+    // Indexes are faster to build at once than to update at each row
+    drop_secondary_indexes(&mut transaction, &args.fantoir_table).await;
+
+    let start = Instant::now();
+    let query = format!(
+        "COPY {} ({}) FROM STDIN WITH (FORMAT csv)",
+        quote_table_name(&args.fantoir_table), IMPORT_COLUMNS
+    );
+    let mut copy = transaction.copy_in_raw(&query).await.expect("Can't start COPY.");
+
     // Currently, async closures are unstable, see https://github.com/rust-lang/rust/issues/62290
     // They are also largely unimplemented. As such, this code doesn't follow HOF pattern.
+    let mut batch = String::new();
+    let mut batch_len = 0;
     let mut buffer = BufReader::new(fd).lines();
-    while let Ok(line) = buffer.next_line().await {
-        if line.is_none() {
-            break;
-        }
-        let line = line.unwrap();
+    loop {
+        let line = match buffer.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(error) => {
+                eprintln!("Can't read FANTOIR file: {}", error);
+
+                copy.abort("Can't read FANTOIR file.").await.expect("Can't abort COPY.");
+                transaction.rollback().await.expect("Can't roll back import.");
+                exit(2);
+            }
+        };
 
         if line.len() < 90 {
             // This record is the header or describes a department or a commune
@@ -62,8 +134,41 @@ pub async fn import(args: &ImportArgs, database_url: &str) {
             break;
         }
 
-        FantoirEntry::parse_line(&line)
-            .insert_to_db(&pool, &args.fantoir_table)
-            .await
+        batch.push_str(&FantoirEntry::parse_line(&line).to_csv_row());
+        batch_len += 1;
+
+        if batch_len == BATCH_SIZE {
+            copy.send(batch.as_bytes()).await.expect("Can't send rows to database.");
+            batch.clear();
+            batch_len = 0;
+        }
+    }
+
+    if batch_len > 0 {
+        copy.send(batch.as_bytes()).await.expect("Can't send rows to database.");
+    }
+    let count = copy.finish().await.expect("Can't complete COPY.");
+    let load_duration = start.elapsed();
+
+    let start = Instant::now();
+    create_secondary_indexes(&mut transaction, &args.fantoir_table).await;
+    let index_duration = start.elapsed();
+
+    transaction.commit().await.expect("Can't commit import.");
+
+    let seconds = load_duration.as_secs_f64();
+    println!(
+        "{} voies imported into {} in {:.1} s ({:.0} rows/s), indexes built in {:.1} s",
+        count, &args.fantoir_table, seconds, count as f64 / seconds.max(0.001), index_duration.as_secs_f64()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_secondary_indexes_names() {
+        assert_eq!(vec!["voie_trigram"], get_indexes_names(SECONDARY_INDEXES_SCHEMA));
     }
 }
@@ -25,8 +25,6 @@ async fn get_queries_groups (pool: &PgPool, fantoir_table: &str) -> Vec<String>
 
     queries_groups
         .into_iter()
-        .map(|queries| queries
-            .replace("/*table*/fantoir", fantoir_table)
-        )
+        .map(|queries| replace_markers(queries, fantoir_table))
         .collect()
 }
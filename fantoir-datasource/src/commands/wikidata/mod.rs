@@ -5,9 +5,11 @@ mod report;
 
 use std::collections::HashMap;
 use std::process::exit;
+use std::time::Duration;
 
 use oxrdf::Term;
 use sparql_client::{is_term_empty, parse_literal, parse_term_uri};
+use sparql_client::cache::QueryCache;
 use sparql_client::Client as SparqlClient;
 use sqlx::PgPool;
 
@@ -40,10 +42,13 @@ pub async fn import (args: &WikidataArgs, database_url: &str) {
     }
 
     // Query Wikidata and get (Wikidata/FANTOIR code, list of P31 (instance of) values) hashmap
-    let client = SparqlClient::from_http_client(
+    let mut client = SparqlClient::from_http_client(
         WIKIDATA_SPARQL_ENDPOINT,
         build_http_client()
     );
+    if let Some(cache) = build_query_cache(args) {
+        client = client.with_cache(cache);
+    }
     let mut what_map = HashMap::new();
 
     client.query(include_str!("../../queries/wikidata.sparql"))
@@ -112,6 +117,17 @@ impl ToTableInitializationArgs for &WikidataArgs {
     }
 }
 
+fn build_query_cache (args: &WikidataArgs) -> Option<QueryCache> {
+    let mut cache = QueryCache::new(args.cache_dir.as_ref()?)
+        .with_force_refresh(args.force_refresh);
+
+    if let Some(ttl) = args.cache_ttl {
+        cache = cache.with_ttl(Duration::from_secs(ttl));
+    }
+
+    Some(cache)
+}
+
 /*   -------------------------------------------------------------
      Wikidata entry structures
 
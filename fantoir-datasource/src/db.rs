@@ -2,113 +2,81 @@
 //!
 //! This module provides helpers to interact with a PostgreSQL database.
 //! Functions expect to work with an executor from sqlx crate.
-
-use std::future::Future;
+//!
+//! The table helpers are shared with other import tools through ds-postgres crate.
 
 use async_scoped::TokioScope;
-use sqlx::PgPool;
-use sqlx::postgres::PgPoolOptions;
+use sqlx::{PgConnection, PgPool};
+
+pub use ds_postgres::*;
 
 static QUERIES_SEPARATOR: &str = "\n\n\n";
 
-pub struct TableInitializationArgs {
-    pub table_name: String,
-    pub create_table: bool,
-    pub overwrite_table: bool,
-}
+/// The marker of the table name in the SQL schemas
+static TABLE_MARKER: &str = "/*table*/fantoir";
 
-pub trait ToTableInitializationArgs {
-    fn to_table_initialization_args(&self) -> TableInitializationArgs;
-}
+/// The marker of the indexes names in the SQL schemas, replaced by the table prefix
+static INDEX_MARKER: &str = "/*index*/index_fantoir_";
 
-pub async fn connect_to_db (database_url: &str) -> PgPool {
-    PgPoolOptions::new()
-        .max_connections(3)
-        .connect(database_url)
-        .await
-        .expect("Can't connect to database.")
-}
-
-pub async fn is_table_exists (pool: &PgPool, table: &str) -> bool {
-    let query = r#"
-    SELECT EXISTS (
-               SELECT FROM
-                   pg_tables
-               WHERE
-                       schemaname = 'public' AND
-                       tablename  = $1
-           );
-    "#;
-
-    let result: (bool,) = sqlx::query_as(query)
-        .bind(table)
-        .fetch_one(pool)
-        .await
-        .expect("Can't check if table exists.");
-
-    result.0
-}
+/*   -------------------------------------------------------------
+     SQL schemas markers
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
 
-pub async fn is_table_empty (pool: &PgPool, table: &str) -> bool {
-    let query = r#"
-    SELECT EXISTS (
-        SELECT 1 FROM %%table%%
-    );
-    "#.replace("%%table%%", table);
+/// Replaces the table and indexes markers of SQL queries by the quoted names.
+///
+/// In a string literal, e.g. '/*table*/fantoir', the table name is written as a string.
+pub fn replace_markers (queries: &str, table: &str) -> String {
+    let mut parts = queries.split(INDEX_MARKER);
+    let mut rendered = parts.next().unwrap_or_default().to_string();
 
-    let result: (bool,) = sqlx::query_as(&query)
-        .fetch_one(pool)
-        .await
-        .expect("Can't check if table is empty.");
+    for part in parts {
+        let end = get_index_name_length(part);
+        rendered.push_str(&get_index_name(table, &part[..end], false));
+        rendered.push_str(&part[end..]);
+    }
 
-    !result.0
+    rendered
+        .replace(&format!("'{}'", TABLE_MARKER), &quote_literal(table))
+        .replace(TABLE_MARKER, &quote_table_name(table))
 }
 
-pub async fn truncate_table (pool: &PgPool, table: &str) {
-    let query = format!("TRUNCATE TABLE {} RESTART IDENTITY;", table);
-
-    sqlx::query(&query)
-        .bind(table)
-        .execute(pool)
-        .await
-        .expect("Can't truncate table.");
+/// Gets the names of the indexes of a SQL schema, without table prefix.
+pub fn get_indexes_names (queries: &str) -> Vec<&str> {
+    queries
+        .split(INDEX_MARKER)
+        .skip(1)
+        .map(|part| &part[..get_index_name_length(part)])
+        .collect()
 }
 
-pub async fn initialize_table<F, T> (
-    pool: &PgPool,
-    callback: F,
-    args: T
-) -> Result<(), String>
-    where F: Future, T: ToTableInitializationArgs
-{
-    let args = args.to_table_initialization_args();
-    if is_table_exists(pool, &args.table_name).await {
-        if is_table_empty(&pool, &args.table_name).await {
-            return Ok(());
-        }
-
-        if args.overwrite_table {
-            truncate_table(&pool, &args.table_name).await;
-            return Ok(());
-        }
-
-        return Err(format!(
-            "Table {} already exists and contains rows. To overwrite it, run the import tool with -t option.",
-            &args.table_name
-        ));
+/// Gets the quoted name of an index of a table.
+///
+/// * `qualified` - Qualifies the name by the schema of the table, if any, e.g. for DROP INDEX
+pub fn get_index_name (table: &str, index: &str, qualified: bool) -> String {
+    let (schema, name) = split_table_name(table);
+    let index_name = quote_identifier(&format!("index_{}_{}", name, index));
+
+    if qualified && table.contains('.') {
+        format!("{}.{}", quote_identifier(schema), index_name)
+    } else {
+        index_name
     }
+}
 
-    if args.create_table {
-        callback.await;
-        return Ok(());
-    }
+fn get_index_name_length (text: &str) -> usize {
+    text
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(text.len())
+}
 
-    Err(format!(
-        "Table {} doesn't exist. To create it, run the import tool with -c option.",
-        &args.table_name
-    ))
+fn quote_literal (value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
 }
 
+/*   -------------------------------------------------------------
+     Queries
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
 pub async fn run_multiple_queries(pool: &PgPool, queries: &str) {
     for query in queries.split(QUERIES_SEPARATOR) {
         sqlx::query(&query)
@@ -118,6 +86,16 @@ pub async fn run_multiple_queries(pool: &PgPool, queries: &str) {
     }
 }
 
+/// Runs the queries on a connection, e.g. in a transaction.
+pub async fn run_multiple_queries_on_connection (connection: &mut PgConnection, queries: &str) {
+    for query in queries.split(QUERIES_SEPARATOR) {
+        sqlx::query(query)
+            .execute(&mut *connection)
+            .await
+            .expect("Can't run SQL query.");
+    }
+}
+
 pub fn run_multiple_queries_groups (pool: &PgPool, queries_groups: &Vec<String>) {
     let n = queries_groups.len();
     TokioScope::scope_and_block(|scope| {
@@ -128,3 +106,35 @@ pub fn run_multiple_queries_groups (pool: &PgPool, queries_groups: &Vec<String>)
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_markers() {
+        let queries = "CREATE TABLE /*table*/fantoir (id bigserial constraint /*index*/index_fantoir_pk primary key);\n\n\n\
+                       INSERT INTO fantoir_config VALUES ('fantoir_table', '/*table*/fantoir');";
+
+        assert_eq!(
+            "CREATE TABLE \"ods\".\"Fantoir\" (id bigserial constraint \"index_Fantoir_pk\" primary key);\n\n\n\
+             INSERT INTO fantoir_config VALUES ('fantoir_table', 'ods.Fantoir');",
+            replace_markers(queries, "ods.Fantoir"),
+        );
+    }
+
+    #[test]
+    fn test_get_indexes_names() {
+        let queries = "CREATE INDEX /*index*/index_fantoir_voie_trigram ON /*table*/fantoir (libelle_voie);\n\n\n\
+                       CREATE INDEX /*index*/index_fantoir_commune\n    ON /*table*/fantoir (code_insee);";
+
+        assert_eq!(vec!["voie_trigram", "commune"], get_indexes_names(queries));
+    }
+
+    #[test]
+    fn test_get_index_name() {
+        assert_eq!(r#""index_fantoir_pk""#, get_index_name("fantoir", "pk", true));
+        assert_eq!(r#""index_Fantoir_pk""#, get_index_name("ods.Fantoir", "pk", false));
+        assert_eq!(r#""ods"."index_Fantoir_pk""#, get_index_name("ods.Fantoir", "pk", true));
+    }
+}
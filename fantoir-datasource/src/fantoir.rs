@@ -6,7 +6,13 @@
 use chrono::NaiveDate;
 use lazy_static::lazy_static;
 use regex::Regex;
-use sqlx::PgPool;
+
+/// The columns filled by an import, in the order of FantoirEntry::to_csv_row values
+pub static IMPORT_COLUMNS: &str = "code_fantoir,
+    departement, code_commune, code_insee, type_commune, is_pseudo_recensee,
+    identifiant_communal_voie, cle_rivoli, code_nature_voie, libelle_voie, type_voie, is_public,
+    is_large, population_a_part, population_fictive,
+    is_cancelled, cancel_date, creation_date, code_majic, last_alpha_word";
 
 lazy_static! {
     static ref DEPARTMENTS_WITH_CODE_DIRECTION: Vec<&'static str> = vec!["13", "59", "75", "92", "97"];
@@ -97,59 +103,46 @@ impl FantoirEntry {
         }
     }
 
-    pub async fn insert_to_db(&self, pool: &PgPool, table: &str) {
-        let mut query = format!("INSERT INTO {}", table);
-        query.push_str(
-            r#"
-            (code_fantoir,
-             departement, code_commune, code_insee, type_commune, is_pseudo_recensee,
-             identifiant_communal_voie, cle_rivoli, code_nature_voie, libelle_voie, type_voie, is_public,
-             is_large, population_a_part, population_fictive,
-             is_cancelled, cancel_date, creation_date, code_majic, last_alpha_word
-            )
-        VALUES
-            ($1,
-             $2, $3, $4, $5, $6,
-             $7, $8, $9, $10, $11, $12,
-             $13, $14, $15,
-             $16, $17, $18, $19, $20
-            )"#
-        );
-
-        sqlx::query(&query)
+    /// Formats the entry as a CSV row for `COPY ... FROM STDIN`, in IMPORT_COLUMNS order.
+    ///
+    /// Empty optional values are written unquoted, so PostgreSQL reads them as NULL.
+    pub fn to_csv_row(&self) -> String {
+        let values = [
             /* Identifiers */
-            .bind(&self.code_fantoir)
+            csv_string(&self.code_fantoir),
 
             /* Part 1 - commune */
-            .bind(&self.departement)
-            .bind(&self.code_commune)
-            .bind(&self.code_insee)
-            .bind(&self.type_commune)
-            .bind(&self.is_pseudo_recensee)
+            csv_string(&self.departement),
+            self.code_commune.to_string(),
+            csv_string(&self.code_insee),
+            csv_optional_string(&self.type_commune),
+            csv_bool(self.is_pseudo_recensee),
 
             /* Part 2 - Voie */
-            .bind(&self.identifiant_communal_voie)
-            .bind(&self.cle_rivoli)
-            .bind(&self.code_nature_voie)
-            .bind(&self.libelle_voie)
-            .bind(&self.type_voie)
-            .bind(&self.is_public)
+            csv_string(&self.identifiant_communal_voie),
+            csv_string(&self.cle_rivoli),
+            csv_optional_string(&self.code_nature_voie),
+            csv_string(&self.libelle_voie),
+            self.type_voie.to_string(),
+            csv_bool(self.is_public),
 
             /* Part 3 - Population */
-            .bind(&self.is_large)
-            .bind(&self.population_a_part)
-            .bind(&self.population_fictive)
+            csv_bool(self.is_large),
+            self.population_a_part.to_string(),
+            self.population_fictive.to_string(),
 
             /* Part 4 - Metadata */
-            .bind(&self.is_cancelled)
-            .bind(&self.cancel_date)
-            .bind(&self.creation_date)
-            .bind(&self.code_majic)
-            .bind(&self.last_alpha_word)
-
-            .execute(pool)
-            .await
-            .expect("Can't insert entry to database");
+            csv_bool(self.is_cancelled),
+            csv_optional_date(&self.cancel_date),
+            csv_optional_date(&self.creation_date),
+            self.code_majic.to_string(),
+            csv_string(&self.last_alpha_word),
+        ];
+
+        let mut row = values.join(",");
+        row.push('\n');
+
+        row
     }
 }
 
@@ -174,6 +167,28 @@ fn parse_optional_string (expression: &str) -> Option<String> {
     }
 }
 
+fn csv_string (value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn csv_optional_string (value: &Option<String>) -> String {
+    match value {
+        Some(value) => csv_string(value),
+        None => String::new(),
+    }
+}
+
+fn csv_optional_date (value: &Option<NaiveDate>) -> String {
+    match value {
+        Some(date) => date.format("%Y-%m-%d").to_string(),
+        None => String::new(),
+    }
+}
+
+fn csv_bool (value: bool) -> String {
+    String::from(if value { "t" } else { "f" })
+}
+
 /// A fixed FANTOIR code result
 #[derive(Debug, Eq, PartialEq)]
 pub enum FixedFantoirCode {
@@ -305,6 +320,37 @@ mod tests {
         assert_eq!(true, parse_optional_string("    ").is_none());
     }
 
+    #[test]
+    fn test_to_csv_row() {
+        let entry = FantoirEntry {
+            code_fantoir: String::from("8501190012X"),
+            departement: String::from("85"),
+            code_commune: 11,
+            code_insee: String::from("85011"),
+            type_commune: Some(String::from("R")),
+            is_pseudo_recensee: false,
+            identifiant_communal_voie: String::from("0012"),
+            cle_rivoli: String::from("X"),
+            code_nature_voie: None,
+            libelle_voie: String::from("RUE DE L\"EGLISE"),
+            type_voie: 1,
+            is_public: true,
+            is_large: false,
+            population_a_part: 0,
+            population_fictive: 0,
+            is_cancelled: false,
+            cancel_date: None,
+            creation_date: NaiveDate::from_ymd_opt(1987, 1, 1),
+            code_majic: 42,
+            last_alpha_word: String::from("EGLISE"),
+        };
+
+        assert_eq!(
+            "\"8501190012X\",\"85\",11,\"85011\",\"R\",f,\"0012\",\"X\",,\"RUE DE L\"\"EGLISE\",1,t,f,0,0,f,,1987-01-01,42,\"EGLISE\"\n",
+            entry.to_csv_row()
+        );
+    }
+
     #[test]
     pub fn test_fix_fantoir_code () {
         assert_fixed_fantoir_code("755112P144L", fix_fantoir_code("755112P144L"));
@@ -76,6 +76,18 @@ pub struct WikidataArgs {
     /// Generate a Wikidata maintenance report instead to print errors to stderr
     #[arg(long)]
     maintenance_report: bool,
+
+    /// Cache Wikidata query results in the specified directory
+    #[arg(long)]
+    cache_dir: Option<String>,
+
+    /// Maximum age of cached results, in seconds
+    #[arg(long, requires = "cache_dir")]
+    cache_ttl: Option<u64>,
+
+    /// Query Wikidata even if cached results are still fresh
+    #[arg(long, requires = "cache_dir")]
+    force_refresh: bool,
 }
 
 #[derive(Debug, Args)]
@@ -5,6 +5,7 @@
 --
 -- If you provide several instructions, separate those with TWO blank lines.
 -- Indexes have to match every WHERE clause used against the database.
+-- Secondary indexes are in fantoir_indexes.sql, built after the import.
 --
 -- This schema is compiled as part of the program, as such you need to rebuild
 -- (`cargo build`) the project after any schema modification.
@@ -47,8 +48,3 @@ CREATE TABLE IF NOT EXISTS /*table*/fantoir (
     UNIQUE (code_fantoir),
     UNIQUE (code_insee, identifiant_communal_voie)
 );
-
-
-CREATE INDEX CONCURRENTLY /*index*/index_fantoir_voie_trigram
-    ON /*table*/fantoir
-        USING gin (libelle_voie gin_trgm_ops);
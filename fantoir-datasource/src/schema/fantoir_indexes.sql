@@ -0,0 +1,11 @@
+-- Secondary indexes of the fantoir table.
+--
+-- They're dropped before an import and built once the rows are loaded,
+-- as building an index at once is faster than updating it at each row.
+--
+-- If you provide several instructions, separate those with TWO blank lines.
+-- Indexes are dropped by the names following the index marker, keep it.
+
+CREATE INDEX IF NOT EXISTS /*index*/index_fantoir_voie_trigram
+    ON /*table*/fantoir
+        USING gin (libelle_voie gin_trgm_ops);
@@ -0,0 +1,127 @@
+//! Import command for the opendatasoft-datasource tool.
+//!
+//! Import the records of a dataset, read from its jsonl export.
+
+use std::process::exit;
+
+use futures_util::{pin_mut, StreamExt};
+use sqlx::PgPool;
+
+use opendatasoft_explore_api::drift::{DriftPolicy, SchemaSnapshot};
+use opendatasoft_explore_api::query::RecordsQuery;
+use opendatasoft_explore_api::schema::DatasetField;
+
+use crate::ImportArgs;
+use crate::commands::get_dataset;
+use crate::db::*;
+use crate::schema::{get_copy_query, get_create_table_query, prepare_record};
+
+/// The number of records to send at once to the COPY query
+static BATCH_SIZE: usize = 1000;
+
+impl ToTableInitializationArgs for &ImportArgs {
+    fn to_table_initialization_args (&self) -> TableInitializationArgs {
+        TableInitializationArgs {
+            table_name: self.table.clone(),
+            create_table: self.create_table,
+            overwrite_table: self.overwrite_table,
+        }
+    }
+}
+
+async fn create_table(pool: &PgPool, table: &str, fields: &[DatasetField]) {
+    sqlx::query(&get_create_table_query(table, fields))
+        .execute(pool)
+        .await
+        .expect("Can't create table.");
+}
+
+pub async fn import(args: &ImportArgs, database_url: &str) {
+    let endpoint = args.source.to_endpoint();
+    let dataset = get_dataset(&endpoint, &args.source.dataset_id).await;
+
+    let snapshot = SchemaSnapshot::from_dataset(&dataset);
+    if let Some(path) = &args.schema_snapshot {
+        check_schema_drift(path, &snapshot, args.get_drift_policy()).await;
+    }
+
+    let pool = connect_to_db(database_url).await;
+
+    // Create/truncate table as needed and as allowed by options
+    let callback = async {
+        create_table(&pool, &args.table, &dataset.fields).await;
+    };
+    if let Err(error) = initialize_table(&pool, callback, args).await {
+        eprintln!("{}", &error);
+        exit(1);
+    }
+
+    let records = endpoint.export_records_stream(&args.source.dataset_id, &RecordsQuery::new());
+    pin_mut!(records);
+
+    // Records are bulk loaded in one COPY, so a failed import doesn't insert any row.
+    let mut copy = start_copy(&pool, &get_copy_query(&args.table, &dataset.fields)).await;
+    let mut rows = String::new();
+    let mut batch_count = 0;
+    while let Some(record) = records.next().await {
+        let record = match record {
+            Ok(record) => record,
+            Err(error) => {
+                eprintln!("Can't read dataset export: {}", error);
+                exit(16);
+            }
+        };
+
+        rows.push_str(&prepare_record(&dataset.fields, &record.record.fields));
+        batch_count += 1;
+        if batch_count == BATCH_SIZE {
+            send_copy_rows(&mut copy, &mut rows).await;
+            batch_count = 0;
+        }
+    }
+    send_copy_rows(&mut copy, &mut rows).await;
+    let count = finish_copy(copy).await;
+
+    println!("{} records imported into {}", count, &args.table);
+
+    if let Some(path) = &args.schema_snapshot {
+        if let Err(error) = snapshot.write(path).await {
+            eprintln!("Can't write schema snapshot: {}", error);
+            exit(4);
+        }
+    }
+}
+
+impl ImportArgs {
+    fn get_drift_policy (&self) -> DriftPolicy {
+        if self.fail_on_breaking_changes {
+            DriftPolicy::FailOnBreakingChanges
+        } else {
+            DriftPolicy::Report
+        }
+    }
+}
+
+/// Reports the changes since the schema snapshot, or exits if the policy doesn't allow them.
+async fn check_schema_drift(path: &str, current: &SchemaSnapshot, policy: DriftPolicy) {
+    let previous = match SchemaSnapshot::read(path).await {
+        Ok(Some(previous)) => previous,
+        Ok(None) => return,
+        Err(error) => {
+            eprintln!("Can't read schema snapshot: {}", error);
+            exit(4);
+        }
+    };
+
+    match previous.compare(current).and_then(|diff| diff.check(policy)) {
+        Ok(diff) => {
+            if !diff.is_empty() {
+                eprintln!("Schema changed since {}:\n{}", previous.taken_at, diff);
+            }
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            exit(2);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Commands for the opendatasoft-datasource tool.
+
+use std::process::exit;
+
+use opendatasoft_explore_api::auth::ApiKeyLocation;
+use opendatasoft_explore_api::requests::ExploreApiEndPoint;
+use opendatasoft_explore_api::schema::DatasetProperties;
+
+use crate::EndPointArgs;
+
+pub(crate) mod import;
+pub(crate) mod schema;
+
+/// The number of times to retry a request on transient errors
+static REQUEST_RETRIES: u32 = 3;
+
+impl EndPointArgs {
+    pub fn to_endpoint (&self) -> ExploreApiEndPoint {
+        let endpoint = ExploreApiEndPoint::new(&self.endpoint)
+            .with_retries(REQUEST_RETRIES);
+
+        let api_key = match &self.api_key {
+            None => return endpoint,
+            Some(api_key) => api_key,
+        };
+
+        match endpoint.with_api_key(api_key, ApiKeyLocation::Header) {
+            Ok(endpoint) => endpoint,
+            Err(error) => {
+                eprintln!("Can't use API key: {}", error);
+                exit(32);
+            }
+        }
+    }
+}
+
+/// Gets the dataset properties, or exits if the API can't provide them.
+pub async fn get_dataset (endpoint: &ExploreApiEndPoint, dataset_id: &str) -> DatasetProperties {
+    match endpoint.get_dataset_information(dataset_id).await {
+        Ok(dataset) => dataset.dataset,
+        Err(error) => {
+            eprintln!("Can't get dataset information: {}", error);
+            exit(8);
+        }
+    }
+}
@@ -0,0 +1,14 @@
+//! Schema command for the opendatasoft-datasource tool.
+//!
+//! Print the table the import command would create.
+
+use crate::SchemaArgs;
+use crate::commands::get_dataset;
+use crate::schema::get_create_table_query;
+
+pub async fn print_schema (args: &SchemaArgs) {
+    let endpoint = args.source.to_endpoint();
+    let dataset = get_dataset(&endpoint, &args.source.dataset_id).await;
+
+    println!("{}", get_create_table_query(&args.table, &dataset.fields));
+}
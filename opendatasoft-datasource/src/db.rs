@@ -0,0 +1,43 @@
+//! # Utilities for database.
+//!
+//! This module provides helpers to interact with a PostgreSQL database.
+//! Functions expect to work with an executor from sqlx crate.
+//!
+//! The table lifecycle is shared with fantoir-datasource through ds-postgres crate.
+
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgCopyIn, PgPoolCopyExt};
+use sqlx::{PgPool, Postgres};
+
+pub use ds_postgres::*;
+
+pub type CopyIn = PgCopyIn<PoolConnection<Postgres>>;
+
+/// Starts a bulk load with a `COPY ... FROM STDIN` query.
+///
+/// If the copy isn't finished, e.g. when the import fails, no row is inserted.
+pub async fn start_copy (pool: &PgPool, query: &str) -> CopyIn {
+    pool.copy_in_raw(query)
+        .await
+        .expect("Can't start to copy rows.")
+}
+
+/// Sends rows, in the format expected by the COPY query, then clears them.
+pub async fn send_copy_rows (copy: &mut CopyIn, rows: &mut String) {
+    if rows.is_empty() {
+        return;
+    }
+
+    copy.send(rows.as_bytes())
+        .await
+        .expect("Can't copy rows.");
+
+    rows.clear();
+}
+
+/// Finishes the bulk load, and returns the number of inserted rows.
+pub async fn finish_copy (copy: CopyIn) -> u64 {
+    copy.finish()
+        .await
+        .expect("Can't insert rows.")
+}
@@ -0,0 +1,87 @@
+use std::env;
+
+use clap::{Args, Parser};
+
+mod commands;
+mod db;
+mod schema;
+
+#[derive(Debug, Parser)]
+#[command(name = "opendatasoft-datasource")]
+#[clap(author="Nasqueron project", version, about="Import an Opendatasoft dataset into PostgreSQL", long_about=None)]
+enum OpendatasoftCommand {
+    /// Import the records of a dataset into a table
+    #[command(arg_required_else_help = true)]
+    Import(ImportArgs),
+
+    /// Print the CREATE TABLE query matching the dataset fields
+    #[command(arg_required_else_help = true)]
+    Schema(SchemaArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct EndPointArgs {
+    /// The Explore API v2 end-point URL, e.g. https://datanova.laposte.fr/api/v2
+    #[arg(long, short = 'e')]
+    endpoint: String,
+
+    /// The API key to authenticate requests
+    #[arg(long)]
+    api_key: Option<String>,
+
+    /// The identifier of the dataset
+    dataset_id: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+    #[command(flatten)]
+    source: EndPointArgs,
+
+    /// Create table if it doesn't exist
+    #[arg(short = 'c')]
+    create_table: bool,
+
+    /// Truncate table if it already exists, allowing the overwrite mode.
+    /// If not specified, the script will fail if table exists.
+    #[arg(short = 't')]
+    overwrite_table: bool,
+
+    /// The JSON file storing the dataset fields at the previous import,
+    /// to report schema changes. It's updated after a successful import.
+    #[arg(long)]
+    schema_snapshot: Option<String>,
+
+    /// Fail if fields have been removed, renamed or retyped since the snapshot
+    #[arg(long, requires = "schema_snapshot")]
+    fail_on_breaking_changes: bool,
+
+    /// The name of the table to populate
+    table: String,
+}
+
+#[derive(Debug, Args)]
+pub struct SchemaArgs {
+    #[command(flatten)]
+    source: EndPointArgs,
+
+    /// The name of the table to create
+    table: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let command = OpendatasoftCommand::parse(); // Will exit if argument is missing or --help/--version provided.
+
+    match command {
+        OpendatasoftCommand::Import(args) => {
+            let database_url = env::var("DATABASE_URL")
+                .expect("The environment variable DATABASE_URL need to be set to your PostgreSQL database.");
+
+            commands::import::import(&args, &database_url).await;
+        },
+        OpendatasoftCommand::Schema(args) => {
+            commands::schema::print_schema(&args).await;
+        },
+    };
+}
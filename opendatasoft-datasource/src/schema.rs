@@ -0,0 +1,179 @@
+//! # Table schema for a dataset.
+//!
+//! This module maps the fields of an Opendatasoft dataset to PostgreSQL columns,
+//! and prepares the records so PostgreSQL can read them as rows of the table.
+
+use ds_postgres::{quote_identifier, quote_table_name};
+use opendatasoft_explore_api::fields::{FieldType, GeoPoint2D};
+use opendatasoft_explore_api::schema::DatasetField;
+use serde_json::Value as JsonValue;
+
+/*  -------------------------------------------------------------
+    Columns
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Gets the PostgreSQL column type to store a field.
+///
+/// Geographic shapes and files are stored as their JSON representation,
+/// so the table doesn't need PostGIS.
+pub fn get_column_type (field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Text => "TEXT",
+        FieldType::Int => "BIGINT",
+        FieldType::Double => "DOUBLE PRECISION",
+        FieldType::Date => "DATE",
+        FieldType::DateTime => "TIMESTAMP WITH TIME ZONE",
+        FieldType::GeoPoint2D => "POINT",
+        FieldType::GeoShape => "JSONB",
+        FieldType::File => "JSONB",
+        FieldType::Other(_) => "TEXT",
+    }
+}
+
+pub fn get_create_table_query (table: &str, fields: &[DatasetField]) -> String {
+    let columns: Vec<_> = fields
+        .iter()
+        .map(|field| format!(
+            "    {} {}",
+            quote_identifier(&field.name),
+            get_column_type(&field.get_field_type()),
+        ))
+        .collect();
+
+    format!("CREATE TABLE {} (\n{}\n);", quote_table_name(table), columns.join(",\n"))
+}
+
+/// Gets the query to bulk load records, as CSV rows with the dataset fields as columns.
+pub fn get_copy_query (table: &str, fields: &[DatasetField]) -> String {
+    let columns: Vec<_> = fields
+        .iter()
+        .map(|field| quote_identifier(&field.name))
+        .collect();
+
+    format!(
+        "COPY {} ({}) FROM STDIN WITH (FORMAT csv)",
+        quote_table_name(table),
+        columns.join(", "),
+    )
+}
+
+/*  -------------------------------------------------------------
+    Records
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Prepares the fields of a record as a CSV row, to be read by the COPY query.
+///
+/// Only the dataset fields are kept, in the same order as the columns.
+/// Missing and null values are written as unquoted empty values, read as NULL.
+/// Geographic points are converted to the PostgreSQL point representation, "(lon,lat)".
+/// Objects and arrays are written as JSON.
+pub fn prepare_record (fields: &[DatasetField], record: &JsonValue) -> String {
+    let values: Vec<_> = fields
+        .iter()
+        .map(|field| {
+            let value = match record.get(&field.name) {
+                None | Some(JsonValue::Null) => return String::new(),
+                Some(value) => value,
+            };
+
+            let value = match field.get_field_type() {
+                FieldType::GeoPoint2D => match serde_json::from_value::<GeoPoint2D>(value.clone()) {
+                    Ok(point) => format!("({},{})", point.lon, point.lat),
+                    Err(_) => return String::new(),
+                },
+                _ => match value {
+                    JsonValue::String(value) => value.clone(),
+                    value => value.to_string(),
+                },
+            };
+
+            quote_csv_value(&value)
+        })
+        .collect();
+
+    let mut row = values.join(",");
+    row.push('\n');
+
+    row
+}
+
+/// Encloses a value in double quotes, so an empty string isn't read as NULL.
+fn quote_csv_value (value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn build_field (name: &str, field_type: &str) -> DatasetField {
+        DatasetField {
+            name: name.to_string(),
+            label: name.to_string(),
+            field_type: field_type.to_string(),
+            annotations: JsonValue::Null,
+            description: None,
+        }
+    }
+
+    fn get_fields () -> Vec<DatasetField> {
+        vec![
+            build_field("code_postal", "text"),
+            build_field("prix_visite", "int"),
+            build_field("coordonnees_gps", "geo_point_2d"),
+        ]
+    }
+
+    #[test]
+    fn test_get_create_table_query () {
+        let expected = r#"CREATE TABLE "controle_techn" (
+    "code_postal" TEXT,
+    "prix_visite" BIGINT,
+    "coordonnees_gps" POINT
+);"#;
+
+        assert_eq!(expected, get_create_table_query("controle_techn", &get_fields()));
+    }
+
+    #[test]
+    fn test_get_create_table_query_with_schema () {
+        let query = get_create_table_query("ods.controle_techn", &get_fields());
+
+        assert!(query.starts_with(r#"CREATE TABLE "ods"."controle_techn" ("#));
+    }
+
+    #[test]
+    fn test_get_copy_query () {
+        assert_eq!(
+            r#"COPY "controle_techn" ("code_postal", "prix_visite", "coordonnees_gps") FROM STDIN WITH (FORMAT csv)"#,
+            get_copy_query("controle_techn", &get_fields())
+        );
+    }
+
+    #[test]
+    fn test_prepare_record () {
+        let record = json!({
+            "code_postal": "85300",
+            "prix_visite": null,
+            "coordonnees_gps": {"lon": -1.867611, "lat": 46.85617},
+            "unknown_field": 42,
+        });
+
+        assert_eq!("\"85300\",,\"(-1.867611,46.85617)\"\n", prepare_record(&get_fields(), &record));
+    }
+
+    #[test]
+    fn test_prepare_record_escapes_values () {
+        let record = json!({
+            "code_postal": "Le \"Grand\" garage, Nord",
+            "prix_visite": 80,
+        });
+
+        assert_eq!("\"Le \"\"Grand\"\" garage, Nord\",\"80\",\n", prepare_record(&get_fields(), &record));
+
+        let record = json!({"code_postal": ""});
+        assert_eq!("\"\",,\n", prepare_record(&get_fields(), &record));
+    }
+}
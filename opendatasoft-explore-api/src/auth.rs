@@ -0,0 +1,144 @@
+//! Authentication to query restricted datasets.
+//!
+//! Opendatasoft portals accept an API key, sent either as an
+//! `Authorization: Apikey <key>` header, or as an `apikey` URL parameter.
+
+use std::fmt::{Debug, Formatter};
+
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use reqwest::{RequestBuilder, Url};
+
+use crate::error::Error;
+
+/// The name of the URL parameter to send the API key
+pub(crate) static API_KEY_PARAMETER: &str = "apikey";
+
+/// The way the API key is sent to the server
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ApiKeyLocation {
+    /// Sends the key in an `Authorization: Apikey <key>` header.
+    ///
+    /// This is the recommended way, as the key doesn't appear in the URL.
+    #[default]
+    Header,
+
+    /// Sends the key in the `apikey` URL parameter.
+    QueryParameter,
+}
+
+/// An Opendatasoft API key
+///
+/// The key is never printed by `Debug`.
+#[derive(Clone)]
+pub struct ApiKey {
+    key: String,
+    location: ApiKeyLocation,
+
+    /// The `Authorization` header value, built once the key is validated
+    header_value: HeaderValue,
+}
+
+impl ApiKey {
+    /// Fails with `Error::InvalidHeader` if the key can't be sent in a header,
+    /// e.g. when it's read from a file with a trailing newline.
+    pub fn new (key: &str, location: ApiKeyLocation) -> Result<Self, Error> {
+        let mut header_value = HeaderValue::from_str(&format!("Apikey {}", key))
+            .map_err(|_| Error::InvalidHeader(AUTHORIZATION.to_string()))?;
+        header_value.set_sensitive(true);
+
+        Ok(Self {
+            key: key.to_string(),
+            location,
+            header_value,
+        })
+    }
+
+    pub fn location (&self) -> ApiKeyLocation {
+        self.location
+    }
+
+    /// Adds the API key to a request, as header or URL parameter.
+    pub(crate) fn authenticate (&self, request: RequestBuilder) -> RequestBuilder {
+        match self.location {
+            ApiKeyLocation::Header => request.header(AUTHORIZATION, self.header_value.clone()),
+            ApiKeyLocation::QueryParameter => request.query(&[(API_KEY_PARAMETER, &self.key)]),
+        }
+    }
+
+    /// Adds the API key to a blocking request, as header or URL parameter.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn authenticate_blocking (&self, request: reqwest::blocking::RequestBuilder) -> reqwest::blocking::RequestBuilder {
+        match self.location {
+            ApiKeyLocation::Header => request.header(AUTHORIZATION, self.header_value.clone()),
+            ApiKeyLocation::QueryParameter => request.query(&[(API_KEY_PARAMETER, &self.key)]),
+        }
+    }
+}
+
+impl Debug for ApiKey {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("key", &"[REDACTED]")
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+/// Replaces the value of the `apikey` parameter of an URL, if any.
+pub(crate) fn redact_api_key (url: &mut Url) {
+    if !url.query_pairs().any(|(key, _)| key == API_KEY_PARAMETER) {
+        return;
+    }
+
+    let pairs: Vec<(String, String)> = url.query_pairs()
+        .map(|(key, value)| {
+            if key == API_KEY_PARAMETER {
+                (key.into_owned(), "REDACTED".to_string())
+            } else {
+                (key.into_owned(), value.into_owned())
+            }
+        })
+        .collect();
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_doesnt_print_key () {
+        let api_key = ApiKey::new("s3cr3t", ApiKeyLocation::Header).unwrap();
+
+        assert!(!format!("{:?}", api_key).contains("s3cr3t"));
+    }
+
+    #[test]
+    fn test_new_with_invalid_key () {
+        for location in [ApiKeyLocation::Header, ApiKeyLocation::QueryParameter] {
+            assert!(matches!(
+                ApiKey::new("s3cr3t\n", location),
+                Err(Error::InvalidHeader(name)) if name == "authorization"
+            ));
+        }
+    }
+
+    #[test]
+    fn test_redact_api_key () {
+        let mut url = Url::parse("https://foo/bar?limit=10&apikey=s3cr3t").unwrap();
+        redact_api_key(&mut url);
+
+        assert_eq!("https://foo/bar?limit=10&apikey=REDACTED", url.as_str());
+    }
+
+    #[test]
+    fn test_redact_api_key_without_key () {
+        let mut url = Url::parse("https://foo/bar?limit=10").unwrap();
+        redact_api_key(&mut url);
+
+        assert_eq!("https://foo/bar?limit=10", url.as_str());
+    }
+}
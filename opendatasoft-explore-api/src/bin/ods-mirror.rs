@@ -0,0 +1,80 @@
+use std::process::exit;
+use std::str::FromStr;
+
+use clap::Parser;
+
+use opendatasoft_explore_api::auth::ApiKeyLocation;
+use opendatasoft_explore_api::export::ExportFormat;
+use opendatasoft_explore_api::mirror::CatalogMirror;
+use opendatasoft_explore_api::requests::ExploreApiEndPoint;
+
+#[derive(Debug, Parser)]
+#[command(name = "ods-mirror")]
+#[clap(author="Nasqueron project", version, about="Mirror the catalog metadata of an Opendatasoft portal", long_about=None)]
+pub struct OdsMirrorArgs {
+    /// The Explore API v2 end-point URL, e.g. https://data.economie.gouv.fr/api/v2
+    #[arg(long, short = 'e')]
+    endpoint: String,
+
+    /// Export this dataset in addition to its metadata. Can be repeated.
+    #[arg(long = "export", short = 'x')]
+    exports: Vec<String>,
+
+    /// The format of the datasets exports
+    #[arg(long, short = 'f', default_value = "csv", value_parser = ExportFormat::from_str)]
+    format: ExportFormat,
+
+    /// The API key to authenticate requests
+    #[arg(long)]
+    api_key: Option<String>,
+
+    /// The number of times to retry a request on transient errors
+    #[arg(long, default_value_t = 3)]
+    retries: u32,
+
+    /// Refresh every dataset, even the unchanged ones
+    #[arg(long)]
+    force_refresh: bool,
+
+    /// The directory to write the mirror to
+    directory: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = OdsMirrorArgs::parse(); // Will exit if argument is missing or --help/--version provided.
+
+    let mut endpoint = ExploreApiEndPoint::new(&args.endpoint)
+        .with_retries(args.retries);
+    if let Some(api_key) = &args.api_key {
+        endpoint = match endpoint.with_api_key(api_key, ApiKeyLocation::Header) {
+            Ok(endpoint) => endpoint,
+            Err(error) => {
+                eprintln!("Can't use API key: {}", error);
+                exit(2);
+            }
+        };
+    }
+
+    let report = CatalogMirror::new(&endpoint, &args.directory)
+        .with_exports(args.exports, args.format)
+        .with_force_refresh(args.force_refresh)
+        .run()
+        .await;
+
+    match report {
+        Ok(report) => {
+            println!("{} datasets in catalog", report.datasets_count);
+            println!("{} datasets refreshed, {} exported, {} removed",
+                     report.refreshed.len(), report.exported.len(), report.removed.len());
+
+            for dataset_id in &report.skipped {
+                eprintln!("Skipped dataset with unsafe identifier: {}", dataset_id);
+            }
+        }
+        Err(error) => {
+            eprintln!("Can't mirror catalog: {}", error);
+            exit(4);
+        }
+    }
+}
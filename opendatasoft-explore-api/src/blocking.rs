@@ -0,0 +1,388 @@
+//! Blocking requests for the Opendatasoft Explore API v2.
+//!
+//! Available with the `blocking` feature, the `ExploreApiEndPoint` of this module
+//! offers the requests of [`requests::ExploreApiEndPoint`](crate::requests::ExploreApiEndPoint)
+//! without async runtime, through the reqwest blocking client.
+//! Responses use the same schema types.
+//!
+//! ```no_run
+//! use opendatasoft_explore_api::blocking::ExploreApiEndPoint;
+//!
+//! let endpoint = ExploreApiEndPoint::new("https://data.economie.gouv.fr/api/v2");
+//! let dataset = endpoint.get_dataset_information("fichier-fantoir-des-voies-et-lieux-dits")
+//!     .expect("Can't query dataset");
+//!
+//! println!("{:?}", dataset.title());
+//! ```
+//!
+//! As streams need an async runtime, `datasets_stream` and `records_stream` aren't
+//! available: paginate with the limit and offset parameters of `RecordsQuery` instead.
+//!
+//! The blocking client can't be used from an async context, e.g. a `#[tokio::main]` function.
+
+use std::fmt::{Debug, Formatter};
+use std::fs::{self, File};
+use std::path::Path;
+
+use oxrdf::Graph;
+use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+use serde::de::DeserializeOwned;
+
+use crate::auth::{ApiKey, ApiKeyLocation};
+use crate::error::Error;
+use crate::export::{ExportFormat, ExportOptions};
+use crate::query::RecordsQuery;
+use crate::rdf::{parse_graph, RdfParseError, RdfSyntax};
+use crate::routes::*;
+use crate::schema::*;
+use crate::transport::{get_retry_delay, get_temporary_path, insert_sensitive_header, is_error_status, Credentials, USER_AGENT};
+
+pub use reqwest::blocking::Response as ApiHttpResponse;
+
+/// The Explore API end-point, with blocking requests
+pub struct ExploreApiEndPoint {
+    /// The Opendatasoft Explore API v2 server to use
+    pub url: String,
+
+    client: Client,
+
+    /// The API key to query restricted datasets
+    api_key: Option<ApiKey>,
+
+    /// Extra headers sent with every request
+    headers: HeaderMap,
+
+    /// The number of times to retry a request after a network or server error
+    retries: u32,
+}
+
+impl ExploreApiEndPoint {
+    /// Get a new instance of the API end-point, with an HTTP client ready to run queries.
+    ///
+    /// * `url` - The Explore API url, for example DOMAIN/api/v2/
+    pub fn new (url: &str) -> Self {
+        let client = Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .expect("Can't build HTTP client");
+
+        Self::from_http_client(url, client)
+    }
+
+    /// Get a new instance of the API end-point, using the specified blocking HTTP client.
+    ///
+    /// * `url` - The Explore API url, for example DOMAIN/api/v2/
+    /// * `http_client` - The reqwest blocking client to use for every request
+    pub fn from_http_client (url: &str, http_client: Client) -> Self {
+        Self {
+            url: url.to_string(),
+            client: http_client,
+            api_key: None,
+            headers: HeaderMap::new(),
+            retries: 0,
+        }
+    }
+
+    /// Authenticates requests with an API key, to query restricted datasets.
+    ///
+    /// * `api_key` - The API key, as generated in the portal account settings
+    /// * `location` - Sends the key as `Authorization` header or `apikey` URL parameter
+    ///
+    /// Fails with `Error::InvalidHeader` if the key contains invalid characters, e.g. a newline.
+    pub fn with_api_key (mut self, api_key: &str, location: ApiKeyLocation) -> Result<Self, Error> {
+        self.api_key = Some(ApiKey::new(api_key, location)?);
+        Ok(self)
+    }
+
+    /// Retries requests failing with a network or server error.
+    ///
+    /// Attempts are spaced by an exponential backoff, from 500 ms up to 1 minute.
+    pub fn with_retries (mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Adds a header to send with every request.
+    ///
+    /// Headers values are considered as sensitive, and so aren't printed by `Debug`.
+    ///
+    /// Fails with `Error::InvalidHeader` if the name or the value can't be sent in a request.
+    pub fn with_header (mut self, name: &str, value: &str) -> Result<Self, Error> {
+        insert_sensitive_header(&mut self.headers, name, value)?;
+        Ok(self)
+    }
+
+    /*  -------------------------------------------------------------
+        Part 1 - catalog
+
+        API to enumerate datasets
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    /// Query catalog datasets
+    ///
+    /// * `query` - Optional parameters to filter, sort or paginate datasets
+    pub fn get_datasets(&self, query: &RecordsQuery) -> Result<DatasetsCollection, Error> {
+        self.fetch(get_datasets_url(&self.url, query)?)
+    }
+
+    /// Export a catalog in the specified format
+    ///
+    /// The raw HTTP response is returned, so it can be read as `std::io::Read`.
+    pub fn export_datasets_catalog(&self, format: ExportFormat) -> Result<ApiHttpResponse, Error> {
+        self.fetch_resource(get_catalog_export_url(&self.url, format))
+    }
+
+    /// Export a catalog as an RDF graph
+    ///
+    /// * `format` - The RDF format of the export, other formats are rejected
+    pub fn export_datasets_catalog_graph(&self, format: ExportFormat) -> Result<Graph, Error> {
+        let syntax = RdfSyntax::from_export_format(format)
+            .ok_or_else(|| Error::Rdf(RdfParseError::new(
+                format!("{} export isn't an RDF format", format.catalog_name())
+            )))?;
+
+        let response = self.export_datasets_catalog(format)?;
+        let base_iri = response.url().to_string();
+
+        let document = response.text()
+            .map_err(Error::from_reqwest)?;
+
+        parse_graph(syntax, &document, Some(&base_iri))
+            .map_err(Error::Rdf)
+    }
+
+    /// List facet values
+    ///
+    /// * `query` - Optional parameters, for example to refine or exclude facets values
+    pub fn get_facets(&self, query: &RecordsQuery) -> Result<FacetsCollection, Error> {
+        self.fetch(get_facets_url(&self.url, query)?)
+    }
+
+    /*  -------------------------------------------------------------
+        Part 2 - datasets
+
+        API to work on records
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    /// Query datasets records
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `query` - Optional parameters to select, filter, sort, paginate or aggregate records
+    pub fn get_dataset_records(&self, dataset_id: &str, query: &RecordsQuery) -> Result<Results, Error> {
+        self.fetch(get_dataset_records_url(&self.url, dataset_id, query)?)
+    }
+
+    /// Query datasets records, deserializing their fields into a user struct
+    ///
+    /// Aggregations results are ignored.
+    pub fn get_dataset_records_as<T>(&self, dataset_id: &str, query: &RecordsQuery) -> Result<TypedResults<T>, Error>
+    where T: DeserializeOwned
+    {
+        self.get_dataset_records(dataset_id, query)?
+            .records_as()
+    }
+
+    /// Export a dataset in the desired format.
+    ///
+    /// The raw HTTP response is returned, so it can be read as `std::io::Read`.
+    pub fn export_dataset(&self, dataset_id: &str, format: ExportFormat, query: &RecordsQuery) -> Result<ApiHttpResponse, Error> {
+        self.fetch_resource(get_export_url(&self.url, dataset_id, format, query, &ExportOptions::default())?)
+    }
+
+    /// Export a dataset to a file.
+    ///
+    /// Returns the number of bytes written.
+    pub fn export_dataset_to_file<P>(
+        &self,
+        dataset_id: &str,
+        format: ExportFormat,
+        query: &RecordsQuery,
+        options: &ExportOptions,
+        target_path: P,
+    ) -> Result<usize, Error> where P: AsRef<Path> {
+        self.download(get_export_url(&self.url, dataset_id, format, query, options)?, target_path, Credentials::Included)
+    }
+
+    /// Show dataset information
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    pub fn get_dataset_information(&self, dataset_id: &str) -> Result<Dataset, Error> {
+        self.fetch(get_dataset_url(&self.url, dataset_id))
+    }
+
+    /// List dataset facets
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    pub fn get_dataset_facets(&self, dataset_id: &str) -> Result<FacetsCollection, Error> {
+        self.fetch(get_dataset_facets_url(&self.url, dataset_id))
+    }
+
+    /// List dataset attachments
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    pub fn get_dataset_attachments(&self, dataset_id: &str) -> Result<AttachmentCollection, Error> {
+        self.fetch(get_dataset_attachments_url(&self.url, dataset_id))
+    }
+
+    /// Read a dataset record
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `record_id` - Record identified, for example an UUID
+    pub fn get_dataset_record(&self, dataset_id: &str, record_id: &str) -> Result<Record, Error> {
+        self.fetch(get_dataset_record_url(&self.url, dataset_id, record_id))
+    }
+
+    /*  -------------------------------------------------------------
+        Part 3 - files
+
+        API to download attachments and files fields
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    /// Download a dataset attachment to a file
+    ///
+    /// The API key and the headers are only sent when the attachment is on the same
+    /// origin as the API end-point. Returns the number of bytes written.
+    pub fn download_attachment<P>(&self, attachment: &Attachment, target_path: P) -> Result<usize, Error>
+    where P: AsRef<Path> {
+        let credentials = Credentials::for_url(&self.url, &attachment.href);
+
+        self.download(attachment.href.clone(), target_path, credentials)
+    }
+
+    /// Get a file from a file field, like an image
+    pub fn get_file(&self, dataset_id: &str, file_id: &str) -> Result<ApiHttpResponse, Error> {
+        self.fetch_resource(get_file_url(&self.url, dataset_id, file_id))
+    }
+
+    /// Download a file from a file field, like an image, to a file
+    ///
+    /// Returns the number of bytes written.
+    pub fn download_file<P>(&self, dataset_id: &str, file_id: &str, target_path: P) -> Result<usize, Error>
+    where P: AsRef<Path> {
+        self.download(get_file_url(&self.url, dataset_id, file_id), target_path, Credentials::Included)
+    }
+
+    /*  -------------------------------------------------------------
+        Helper methods
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    /// Sends a GET request, and checks the response status.
+    ///
+    /// Failed attempts are retried according to `get_retry_delay`, as for the async end-point.
+    fn fetch_resource (&self, url: String) -> Result<ApiHttpResponse, Error> {
+        self.fetch_resource_with(url, Credentials::Included)
+    }
+
+    fn fetch_resource_with (&self, url: String, credentials: Credentials) -> Result<ApiHttpResponse, Error> {
+        let mut attempt = 0;
+
+        let response = loop {
+            let result = self.send(&url, credentials);
+
+            let outcome = result.as_ref().map(|response| response.status());
+            match get_retry_delay(outcome, attempt, self.retries) {
+                Some(delay) => std::thread::sleep(delay),
+                None => break result.map_err(Error::from_reqwest)?,
+            }
+            attempt += 1;
+        };
+
+        let status = response.status();
+        if is_error_status(status) {
+            let body = response
+                .text()
+                .map_err(Error::from_reqwest)?;
+
+            return Err(Error::from_response_body(status, &body));
+        }
+
+        Ok(response)
+    }
+
+    fn send (&self, url: &str, credentials: Credentials) -> Result<ApiHttpResponse, reqwest::Error> {
+        let mut request = self.client.get(url);
+
+        if credentials == Credentials::Included {
+            request = request.headers(self.headers.clone());
+            if let Some(api_key) = &self.api_key {
+                request = api_key.authenticate_blocking(request);
+            }
+        }
+
+        request.send()
+    }
+
+    fn fetch<T> (&self, url: String) -> Result<T, Error> where T: DeserializeOwned {
+        let body = self.fetch_resource(url)?
+            .text()
+            .map_err(Error::from_reqwest)?;
+
+        serde_json::from_str(&body)
+            .map_err(Error::Json)
+    }
+
+    /// Writes a resource to a temporary file, renamed to the target once complete.
+    fn download<P> (&self, url: String, target_path: P, credentials: Credentials) -> Result<usize, Error>
+    where P: AsRef<Path> {
+        let response = self.fetch_resource_with(url, credentials)?;
+
+        let temporary_path = get_temporary_path(target_path.as_ref());
+        let result = write_response(response, &temporary_path)
+            .and_then(|bytes_written| {
+                fs::rename(&temporary_path, target_path)
+                    .map(|_| bytes_written)
+                    .map_err(Error::IO)
+            });
+
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary_path);
+        }
+
+        result
+    }
+}
+
+fn write_response (mut response: ApiHttpResponse, path: &Path) -> Result<usize, Error> {
+    let mut file = File::create(path)
+        .map_err(Error::IO)?;
+
+    let bytes_written = response.copy_to(&mut file)
+        .map_err(Error::from_reqwest)?;
+
+    Ok(bytes_written as usize)
+}
+
+impl Debug for ExploreApiEndPoint {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let headers: Vec<_> = self.headers.keys().collect();
+
+        f.debug_struct("ExploreApiEndPoint")
+            .field("url", &self.url)
+            .field("api_key", &self.api_key)
+            .field("headers", &headers)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_doesnt_print_secrets () {
+        let endpoint = ExploreApiEndPoint::new("https://foo")
+            .with_api_key("s3cr3t", ApiKeyLocation::Header)
+            .unwrap()
+            .with_header("X-Token", "t0k3n")
+            .unwrap();
+
+        let output = format!("{:?}", endpoint);
+        assert!(output.contains("x-token"));
+        assert!(!output.contains("s3cr3t"));
+        assert!(!output.contains("t0k3n"));
+    }
+
+    // Requests integration tests are located in tests/ folder.
+}
@@ -0,0 +1,379 @@
+//! Detect schema drift, when a portal adds, removes, renames or retypes dataset fields.
+//!
+//! The fields of a dataset are stored as a [`SchemaSnapshot`] when data is imported,
+//! then compared to the current fields at next import. Breaking changes, i.e. removed,
+//! renamed or retyped fields, can be configured to fail the pipeline.
+//!
+//! ```no_run
+//! use opendatasoft_explore_api::drift::{DriftPolicy, SchemaSnapshot};
+//! use opendatasoft_explore_api::requests::ExploreApiEndPoint;
+//!
+//! async fn check_schema (endpoint: &ExploreApiEndPoint) {
+//!     let dataset = endpoint.get_dataset_information("controle_techn").await
+//!         .expect("Can't query dataset");
+//!     let current = SchemaSnapshot::from_dataset(&dataset.dataset);
+//!
+//!     if let Some(previous) = SchemaSnapshot::read("controle_techn.schema.json").await.unwrap() {
+//!         let diff = previous.compare(&current)
+//!             .and_then(|diff| diff.check(DriftPolicy::FailOnBreakingChanges))
+//!             .expect("Breaking schema changes");
+//!
+//!         println!("{}", diff);
+//!     }
+//!
+//!     current.write("controle_techn.schema.json").await
+//!         .expect("Can't save schema snapshot");
+//! }
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::schema::{DatasetField, DatasetProperties};
+
+/*  -------------------------------------------------------------
+    Schema snapshot
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// The fields of a dataset at a given time
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaSnapshot {
+    pub dataset_id: String,
+    pub taken_at: DateTime<Utc>,
+    pub fields: Vec<DatasetField>,
+}
+
+impl SchemaSnapshot {
+    pub fn from_dataset (dataset: &DatasetProperties) -> Self {
+        Self {
+            dataset_id: dataset.dataset_id.clone(),
+            taken_at: Utc::now(),
+            fields: dataset.fields.clone(),
+        }
+    }
+
+    /// Reads a snapshot from a JSON file, or returns None if the file doesn't exist.
+    pub async fn read<P> (path: P) -> Result<Option<Self>, Error> where P: AsRef<Path> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = tokio::fs::read_to_string(path).await
+            .map_err(Error::IO)?;
+
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(Error::Json)
+    }
+
+    pub async fn write<P> (&self, path: P) -> Result<(), Error> where P: AsRef<Path> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(Error::Json)?;
+
+        tokio::fs::write(path, content).await
+            .map_err(Error::IO)
+    }
+
+    /// Compares this snapshot, taken previously, to a current one of the same dataset.
+    ///
+    /// Returns an `Error::SchemaSnapshotMismatch` if the snapshots are of different datasets.
+    pub fn compare (&self, current: &SchemaSnapshot) -> Result<SchemaDiff, Error> {
+        if self.dataset_id != current.dataset_id {
+            return Err(Error::SchemaSnapshotMismatch {
+                previous: self.dataset_id.clone(),
+                current: current.dataset_id.clone(),
+            });
+        }
+
+        Ok(compare_schemas(&self.fields, &current.fields))
+    }
+}
+
+/*  -------------------------------------------------------------
+    Schema diff
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A field which seems to have been renamed: it has been removed,
+/// and a field with the same type and label or description has been added.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldRename {
+    pub previous_name: String,
+    pub current_name: String,
+    pub field_type: String,
+}
+
+/// A field with a new type
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldTypeChange {
+    pub name: String,
+    pub previous_type: String,
+    pub current_type: String,
+}
+
+/// The differences between two schemas of a dataset
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub added: Vec<DatasetField>,
+    pub removed: Vec<DatasetField>,
+
+    /// The renamed fields candidates, not counted in added or removed fields
+    pub renamed: Vec<FieldRename>,
+
+    pub type_changed: Vec<FieldTypeChange>,
+
+    /// The fields with new annotations, e.g. facet or sortable, with the same type
+    pub annotations_changed: Vec<String>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty (&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.type_changed.is_empty()
+            && self.annotations_changed.is_empty()
+    }
+
+    /// Determines if the changes can break an import: fields removed, renamed or retyped.
+    ///
+    /// New fields and annotations changes aren't considered as breaking.
+    pub fn has_breaking_changes (&self) -> bool {
+        !self.removed.is_empty()
+            || !self.renamed.is_empty()
+            || !self.type_changed.is_empty()
+    }
+
+    /// Applies a drift policy, returning an `Error::SchemaDrift`
+    /// if the policy doesn't allow these changes.
+    pub fn check (self, policy: DriftPolicy) -> Result<Self, Error> {
+        match policy {
+            DriftPolicy::FailOnBreakingChanges if self.has_breaking_changes() => Err(Error::SchemaDrift(self)),
+            _ => Ok(self),
+        }
+    }
+}
+
+impl Display for SchemaDiff {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No schema change");
+        }
+
+        let mut lines = Vec::new();
+        for field in &self.added {
+            lines.push(format!("+ {} ({})", field.name, field.field_type));
+        }
+        for field in &self.removed {
+            lines.push(format!("- {} ({})", field.name, field.field_type));
+        }
+        for rename in &self.renamed {
+            lines.push(format!("~ {} renamed to {}?", rename.previous_name, rename.current_name));
+        }
+        for change in &self.type_changed {
+            lines.push(format!("~ {}: {} -> {}", change.name, change.previous_type, change.current_type));
+        }
+        for name in &self.annotations_changed {
+            lines.push(format!("~ {}: annotations changed", name));
+        }
+
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// What to do when a schema changed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DriftPolicy {
+    /// Reports every change, without failing
+    #[default]
+    Report,
+
+    /// Fails when fields are removed, renamed or retyped
+    FailOnBreakingChanges,
+}
+
+/// Compares two lists of fields.
+pub fn compare_schemas (previous: &[DatasetField], current: &[DatasetField]) -> SchemaDiff {
+    let previous_names: HashSet<_> = previous.iter().map(|field| &field.name).collect();
+    let current_names: HashSet<_> = current.iter().map(|field| &field.name).collect();
+
+    let mut added: Vec<_> = current
+        .iter()
+        .filter(|field| !previous_names.contains(&field.name))
+        .cloned()
+        .collect();
+    let mut removed: Vec<_> = previous
+        .iter()
+        .filter(|field| !current_names.contains(&field.name))
+        .cloned()
+        .collect();
+
+    let mut renamed = Vec::new();
+    removed.retain(|removed_field| {
+        match added.iter().position(|added_field| is_rename_candidate(removed_field, added_field)) {
+            Some(position) => {
+                let added_field = added.remove(position);
+                renamed.push(FieldRename {
+                    previous_name: removed_field.name.clone(),
+                    current_name: added_field.name,
+                    field_type: added_field.field_type,
+                });
+
+                false
+            }
+            None => true,
+        }
+    });
+
+    let mut type_changed = Vec::new();
+    let mut annotations_changed = Vec::new();
+    for field in current {
+        let previous_field = match previous.iter().find(|candidate| candidate.name == field.name) {
+            Some(previous_field) => previous_field,
+            None => continue,
+        };
+
+        if previous_field.field_type != field.field_type {
+            type_changed.push(FieldTypeChange {
+                name: field.name.clone(),
+                previous_type: previous_field.field_type.clone(),
+                current_type: field.field_type.clone(),
+            });
+        } else if previous_field.annotations != field.annotations {
+            annotations_changed.push(field.name.clone());
+        }
+    }
+
+    SchemaDiff {
+        added,
+        removed,
+        renamed,
+        type_changed,
+        annotations_changed,
+    }
+}
+
+fn is_rename_candidate (removed: &DatasetField, added: &DatasetField) -> bool {
+    if removed.field_type != added.field_type {
+        return false;
+    }
+
+    let same_label = !removed.label.is_empty() && removed.label == added.label;
+    let same_description = removed.description.is_some() && removed.description == added.description;
+
+    same_label || same_description
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn build_field (name: &str, label: &str, field_type: &str) -> DatasetField {
+        DatasetField {
+            name: name.to_string(),
+            label: label.to_string(),
+            field_type: field_type.to_string(),
+            annotations: json!({}),
+            description: None,
+        }
+    }
+
+    fn build_fields () -> Vec<DatasetField> {
+        vec![
+            build_field("code_postal", "Code postal", "text"),
+            build_field("cct_denomination", "Dénomination", "text"),
+            build_field("prix_visite", "Prix de la visite", "int"),
+            build_field("cct_tel", "Téléphone", "text"),
+        ]
+    }
+
+    #[test]
+    fn test_compare_schemas_when_unchanged () {
+        let diff = compare_schemas(&build_fields(), &build_fields());
+
+        assert!(diff.is_empty());
+        assert!(!diff.has_breaking_changes());
+    }
+
+    #[test]
+    fn test_compare_schemas () {
+        let mut current = build_fields();
+        current[1].name = "denomination".to_string(); // renamed
+        current[2].field_type = "double".to_string(); // retyped
+        current[3].annotations = json!({"facet": []});
+        current.push(build_field("cct_siret", "SIRET", "text"));
+
+        let diff = compare_schemas(&build_fields(), &current);
+
+        assert_eq!(vec!["cct_siret".to_string()], diff.added.iter().map(|field| field.name.clone()).collect::<Vec<_>>());
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            vec![FieldRename {
+                previous_name: "cct_denomination".to_string(),
+                current_name: "denomination".to_string(),
+                field_type: "text".to_string(),
+            }],
+            diff.renamed
+        );
+        assert_eq!(
+            vec![FieldTypeChange {
+                name: "prix_visite".to_string(),
+                previous_type: "int".to_string(),
+                current_type: "double".to_string(),
+            }],
+            diff.type_changed
+        );
+        assert_eq!(vec!["cct_tel".to_string()], diff.annotations_changed);
+        assert!(diff.has_breaking_changes());
+    }
+
+    #[test]
+    fn test_compare_schemas_when_removed () {
+        let mut current = build_fields();
+        current.remove(3);
+        current.push(build_field("cct_url", "Site web", "text"));
+
+        let diff = compare_schemas(&build_fields(), &current);
+
+        assert_eq!("cct_tel", diff.removed[0].name);
+        assert_eq!("cct_url", diff.added[0].name);
+        assert!(diff.renamed.is_empty());
+    }
+
+    #[test]
+    fn test_check () {
+        let mut current = build_fields();
+        current.push(build_field("cct_siret", "SIRET", "text"));
+        let diff = compare_schemas(&build_fields(), &current);
+        assert!(diff.check(DriftPolicy::FailOnBreakingChanges).is_ok());
+
+        current.remove(0);
+        let diff = compare_schemas(&build_fields(), &current);
+        assert!(diff.clone().check(DriftPolicy::Report).is_ok());
+        assert!(matches!(diff.check(DriftPolicy::FailOnBreakingChanges), Err(Error::SchemaDrift(_))));
+    }
+
+    #[test]
+    fn test_compare_snapshots () {
+        let previous = SchemaSnapshot {
+            dataset_id: "controle_techn".to_string(),
+            taken_at: Utc::now(),
+            fields: build_fields(),
+        };
+        assert!(previous.compare(&previous).unwrap().is_empty());
+
+        let mut current = previous.clone();
+        current.dataset_id = "fantoir".to_string();
+        assert!(matches!(
+            previous.compare(&current),
+            Err(Error::SchemaSnapshotMismatch { previous, current }) if previous == "controle_techn" && current == "fantoir"
+        ));
+    }
+}
@@ -0,0 +1,161 @@
+//! Errors raised when querying the Explore API.
+
+use std::fmt::{Display, Formatter};
+use std::io::Error as IOError;
+
+use reqwest::Error as ReqwestError;
+use reqwest::StatusCode;
+use serde_derive::Deserialize;
+use serde_json::Error as JsonError;
+
+use crate::auth::redact_api_key;
+use crate::drift::SchemaDiff;
+use crate::rdf::RdfParseError;
+
+/// Explore API client error
+#[derive(Debug)]
+pub enum Error {
+    /// Represents an underlying error from Reqwest HTTP client when processing a request.
+    Reqwest(ReqwestError),
+
+    /// Represents an HTTP error status, when the response body isn't an API error document.
+    HttpStatus(StatusCode),
+
+    /// Represents an error document returned by the API, e.g. for an unknown dataset
+    /// or an invalid ODSQL query.
+    Api {
+        status: StatusCode,
+        error_code: String,
+        message: String,
+    },
+
+    /// Represents an error when an API response can't be deserialized.
+    Json(JsonError),
+
+    /// Represents an IO error when doing file operations.
+    IO(IOError),
+
+    /// Represents a catalog export which can't be parsed as RDF.
+    Rdf(RdfParseError),
+
+    /// Represents a header, by its name, which can't be sent in an HTTP request,
+    /// as its name or value contains invalid characters.
+    InvalidHeader(String),
+
+    /// Represents an ODSQL expression which can't be rendered, e.g. a list without values.
+    InvalidExpression(String),
+
+    /// Represents an URL, built from the end-point URL, which can't be parsed.
+    InvalidUrl(String),
+
+    /// Represents breaking changes in a dataset schema, when the drift policy doesn't allow them.
+    SchemaDrift(SchemaDiff),
+
+    /// Represents schema snapshots of two different datasets, which can't be compared.
+    SchemaSnapshotMismatch {
+        previous: String,
+        current: String,
+    },
+}
+
+impl Error {
+    /// Wraps a Reqwest error, redacting the API key from its URL if any.
+    pub(crate) fn from_reqwest (mut error: ReqwestError) -> Self {
+        if let Some(url) = error.url_mut() {
+            redact_api_key(url);
+        }
+
+        Error::Reqwest(error)
+    }
+
+    /// Builds an error from the status and the body of a failed HTTP response.
+    pub(crate) fn from_response_body (status: StatusCode, body: &str) -> Self {
+        match serde_json::from_str::<ApiErrorDocument>(body) {
+            Ok(document) => Error::Api {
+                status,
+                error_code: document.error_code,
+                message: document.message,
+            },
+            Err(_) => Error::HttpStatus(status),
+        }
+    }
+
+    /// Gets the HTTP status code of the response, if the request failed with one.
+    pub fn status (&self) -> Option<StatusCode> {
+        match self {
+            Error::Reqwest(error) => error.status(),
+            Error::HttpStatus(status) => Some(*status),
+            Error::Api { status, .. } => Some(*status),
+            Error::Json(_) | Error::IO(_) | Error::Rdf(_) | Error::InvalidHeader(_) | Error::InvalidExpression(_) | Error::InvalidUrl(_) | Error::SchemaDrift(_) | Error::SchemaSnapshotMismatch { .. } => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Reqwest(error) => write!(f, "HTTP client error: {}", error),
+            Error::HttpStatus(status) => write!(f, "HTTP error: {}", status),
+            Error::Api { status, error_code, message } => {
+                write!(f, "API error {} ({}): {}", error_code, status, message)
+            }
+            Error::Json(error) => write!(f, "Can't parse API response: {}", error),
+            Error::IO(error) => write!(f, "IO error: {}", error),
+            Error::Rdf(error) => write!(f, "Can't parse RDF export: {}", error),
+            Error::InvalidHeader(name) => write!(f, "Invalid header name or value: {}", name),
+            Error::InvalidExpression(message) => write!(f, "Invalid ODSQL expression: {}", message),
+            Error::InvalidUrl(url) => write!(f, "Invalid URL: {}", url),
+            Error::SchemaDrift(diff) => write!(f, "Breaking schema changes:\n{}", diff),
+            Error::SchemaSnapshotMismatch { previous, current } => {
+                write!(f, "Can't compare schema snapshot of {} to {}", previous, current)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source (&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Reqwest(error) => Some(error),
+            Error::Json(error) => Some(error),
+            Error::IO(error) => Some(error),
+            Error::Rdf(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The error document returned by the API, e.g.
+/// `{"error_code": "ODSQLError", "message": "..."}`
+#[derive(Deserialize)]
+struct ApiErrorDocument {
+    error_code: String,
+    message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_response_body () {
+        let body = r#"{"message": "Unknown field: foo", "error_code": "ODSQLError"}"#;
+
+        match Error::from_response_body(StatusCode::BAD_REQUEST, body) {
+            Error::Api { status, error_code, message } => {
+                assert_eq!(StatusCode::BAD_REQUEST, status);
+                assert_eq!("ODSQLError", error_code);
+                assert_eq!("Unknown field: foo", message);
+            }
+            error => panic!("Expected an API error, got {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_from_response_body_when_not_an_error_document () {
+        let error = Error::from_response_body(StatusCode::BAD_GATEWAY, "<html>Bad Gateway</html>");
+
+        assert!(matches!(error, Error::HttpStatus(StatusCode::BAD_GATEWAY)));
+        assert_eq!(Some(StatusCode::BAD_GATEWAY), error.status());
+    }
+}
@@ -0,0 +1,253 @@
+//! Export formats and options for datasets and catalog exports.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/*  -------------------------------------------------------------
+    Export format
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Formats available to export datasets or the catalog
+///
+/// Some formats only make sense for some exports: for example, geographic
+/// formats for datasets with geo fields, or DCAT and RSS for the catalog.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    Json,
+    Jsonl,
+    JsonLd,
+    Csv,
+    Xls,
+    Xlsx,
+    Parquet,
+    GeoJson,
+    Shapefile,
+    Kml,
+    Gpx,
+    RdfXml,
+    Turtle,
+    N3,
+    Dcat,
+    DataJson,
+    Rss,
+}
+
+static ALL_FORMATS: [ExportFormat; 17] = [
+    ExportFormat::Json,
+    ExportFormat::Jsonl,
+    ExportFormat::JsonLd,
+    ExportFormat::Csv,
+    ExportFormat::Xls,
+    ExportFormat::Xlsx,
+    ExportFormat::Parquet,
+    ExportFormat::GeoJson,
+    ExportFormat::Shapefile,
+    ExportFormat::Kml,
+    ExportFormat::Gpx,
+    ExportFormat::RdfXml,
+    ExportFormat::Turtle,
+    ExportFormat::N3,
+    ExportFormat::Dcat,
+    ExportFormat::DataJson,
+    ExportFormat::Rss,
+];
+
+impl ExportFormat {
+    /// Gets the format name, as used in the datasets exports URLs
+    pub fn name (&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::JsonLd => "jsonld",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xls => "xls",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Shapefile => "shp",
+            ExportFormat::Kml => "kml",
+            ExportFormat::Gpx => "gpx",
+            ExportFormat::RdfXml => "rdfxml",
+            ExportFormat::Turtle => "turtle",
+            ExportFormat::N3 => "n3",
+            ExportFormat::Dcat => "dcat",
+            ExportFormat::DataJson => "data.json",
+            ExportFormat::Rss => "rss",
+        }
+    }
+
+    /// Gets the format name, as used in the catalog exports URLs
+    ///
+    /// The catalog uses "rdf" and "ttl" where datasets use "rdfxml" and "turtle".
+    pub fn catalog_name (&self) -> &'static str {
+        match self {
+            ExportFormat::RdfXml => "rdf",
+            ExportFormat::Turtle => "ttl",
+            _ => self.name(),
+        }
+    }
+
+    pub fn file_extension (&self) -> &'static str {
+        match self {
+            ExportFormat::Json | ExportFormat::DataJson => "json",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::JsonLd => "jsonld",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Xls => "xls",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::Shapefile => "zip",
+            ExportFormat::Kml => "kml",
+            ExportFormat::Gpx => "gpx",
+            ExportFormat::RdfXml | ExportFormat::Dcat => "rdf",
+            ExportFormat::Turtle => "ttl",
+            ExportFormat::N3 => "n3",
+            ExportFormat::Rss => "xml",
+        }
+    }
+
+    pub fn mime_type (&self) -> &'static str {
+        match self {
+            ExportFormat::Json | ExportFormat::DataJson => "application/json",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::JsonLd => "application/ld+json",
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Xls => "application/vnd.ms-excel",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::GeoJson => "application/geo+json",
+            ExportFormat::Shapefile => "application/zip",
+            ExportFormat::Kml => "application/vnd.google-earth.kml+xml",
+            ExportFormat::Gpx => "application/gpx+xml",
+            ExportFormat::RdfXml | ExportFormat::Dcat => "application/rdf+xml",
+            ExportFormat::Turtle => "text/turtle",
+            ExportFormat::N3 => "text/n3",
+            ExportFormat::Rss => "application/rss+xml",
+        }
+    }
+}
+
+impl Display for ExportFormat {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    /// Parses a format name, accepting both datasets and catalog names.
+    fn from_str (s: &str) -> Result<Self, Self::Err> {
+        let format = s.to_lowercase();
+
+        ALL_FORMATS
+            .iter()
+            .find(|candidate| candidate.name() == format || candidate.catalog_name() == format)
+            .copied()
+            .ok_or_else(|| format!("Unknown export format: {}", s))
+    }
+}
+
+/*  -------------------------------------------------------------
+    Export options
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Optional parameters specific to exports
+///
+/// Records selection and filters are given by `RecordsQuery`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExportOptions {
+    /// The CSV fields delimiter, ";" by default
+    pub delimiter: Option<char>,
+
+    /// The separator for multivalued fields in CSV
+    pub list_separator: Option<char>,
+
+    /// Uses fields labels instead of fields names as CSV or XLSX headers
+    pub use_labels: Option<bool>,
+
+    /// Prepends a byte order mark to CSV exports
+    pub with_bom: Option<bool>,
+}
+
+impl ExportOptions {
+    pub fn new () -> Self {
+        Self::default()
+    }
+
+    pub fn delimiter (mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    pub fn list_separator (mut self, list_separator: char) -> Self {
+        self.list_separator = Some(list_separator);
+        self
+    }
+
+    pub fn use_labels (mut self, use_labels: bool) -> Self {
+        self.use_labels = Some(use_labels);
+        self
+    }
+
+    pub fn with_bom (mut self, with_bom: bool) -> Self {
+        self.with_bom = Some(with_bom);
+        self
+    }
+
+    /// Gets the parameters to append to the request URL
+    pub fn to_query_pairs (&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+
+        if let Some(delimiter) = self.delimiter {
+            pairs.push(("delimiter", delimiter.to_string()));
+        }
+        if let Some(list_separator) = self.list_separator {
+            pairs.push(("list_separator", list_separator.to_string()));
+        }
+        if let Some(use_labels) = self.use_labels {
+            pairs.push(("use_labels", use_labels.to_string()));
+        }
+        if let Some(with_bom) = self.with_bom {
+            pairs.push(("with_bom", with_bom.to_string()));
+        }
+
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str () {
+        assert_eq!(Ok(ExportFormat::Shapefile), ExportFormat::from_str("shp"));
+        assert_eq!(Ok(ExportFormat::RdfXml), ExportFormat::from_str("rdf"));
+        assert_eq!(Ok(ExportFormat::DataJson), ExportFormat::from_str("data.json"));
+        assert!(ExportFormat::from_str("docx").is_err());
+    }
+
+    #[test]
+    fn test_names_roundtrip () {
+        for format in ALL_FORMATS {
+            assert_eq!(Ok(format), ExportFormat::from_str(format.name()));
+            assert_eq!(Ok(format), ExportFormat::from_str(format.catalog_name()));
+        }
+    }
+
+    #[test]
+    fn test_options_to_query_pairs () {
+        let options = ExportOptions::new()
+            .delimiter(',')
+            .use_labels(true);
+
+        let expected = vec![
+            ("delimiter", ",".to_string()),
+            ("use_labels", "true".to_string()),
+        ];
+
+        assert_eq!(expected, options.to_query_pairs());
+    }
+}
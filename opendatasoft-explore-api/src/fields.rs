@@ -0,0 +1,190 @@
+//! Opendatasoft field types.
+//!
+//! Records fields can be deserialized into user structs, with `Record::fields_as`
+//! or `get_dataset_records_as`. Values types follow `DatasetField::field_type`:
+//!
+//! | Opendatasoft type | Rust type                               |
+//! |-------------------|-----------------------------------------|
+//! | text              | `String`                                |
+//! | int               | `i64`                                   |
+//! | double            | `f64`                                   |
+//! | date              | `chrono::NaiveDate`                     |
+//! | datetime          | `chrono::DateTime<FixedOffset>` or `<Utc>` |
+//! | geo_point_2d      | [`GeoPoint2D`]                          |
+//! | geo_shape         | [`GeoShape`]                            |
+//! | file              | [`FileField`]                           |
+//!
+//! Fields can be null, so use `Option` for fields not always filled.
+//!
+//! ```
+//! use chrono::NaiveDate;
+//! use opendatasoft_explore_api::fields::GeoPoint2D;
+//! use serde_derive::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! struct TestingCenter {
+//!     cct_denomination: String,
+//!     code_postal: String,
+//!     prix_visite: Option<u32>,
+//!     date_application_visite: NaiveDate,
+//!     latitude: GeoPoint2D,
+//! }
+//! ```
+
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+/*  -------------------------------------------------------------
+    Field type
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// The type of a dataset field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    Text,
+    Int,
+    Double,
+    Date,
+    DateTime,
+    GeoPoint2D,
+    GeoShape,
+    File,
+
+    /// A type not covered by this library, e.g. "boolean"
+    Other(String),
+}
+
+impl From<&str> for FieldType {
+    fn from (field_type: &str) -> Self {
+        match field_type {
+            "text" => FieldType::Text,
+            "int" => FieldType::Int,
+            "double" => FieldType::Double,
+            "date" => FieldType::Date,
+            "datetime" => FieldType::DateTime,
+            "geo_point_2d" => FieldType::GeoPoint2D,
+            "geo_shape" => FieldType::GeoShape,
+            "file" => FieldType::File,
+            other => FieldType::Other(other.to_string()),
+        }
+    }
+}
+
+/*  -------------------------------------------------------------
+    Geographic fields
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A geo_point_2d field value
+#[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct GeoPoint2D {
+    pub lon: f64,
+    pub lat: f64,
+}
+
+/// A geo_shape field value
+///
+/// The API gives the shape as a GeoJSON Feature, with the shape as geometry,
+/// or directly as a GeoJSON geometry.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeoShape {
+    #[serde(rename = "type")]
+    pub shape_type: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geometry: Option<JsonValue>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coordinates: Option<JsonValue>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<JsonValue>,
+}
+
+impl GeoShape {
+    /// Gets the GeoJSON geometry object, whatever the shape is a Feature or a geometry.
+    pub fn get_geometry (&self) -> JsonValue {
+        match &self.geometry {
+            Some(geometry) => geometry.clone(),
+            None => serde_json::json!({
+                "type": self.shape_type,
+                "coordinates": self.coordinates,
+            }),
+        }
+    }
+}
+
+/*  -------------------------------------------------------------
+    File fields
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A file field value, e.g. an image
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct FileField {
+    pub id: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mimetype: Option<String>,
+
+    /// The URL to download the file, when the API provides it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_field_type_from_str () {
+        assert_eq!(FieldType::GeoPoint2D, FieldType::from("geo_point_2d"));
+        assert_eq!(FieldType::Other("boolean".to_string()), FieldType::from("boolean"));
+    }
+
+    #[test]
+    fn test_geo_shape_get_geometry () {
+        let geometry = json!({"type": "Point", "coordinates": [-1.867611, 46.85617]});
+
+        let feature: GeoShape = serde_json::from_value(json!({
+            "type": "Feature",
+            "geometry": geometry,
+            "properties": {},
+        })).unwrap();
+        assert_eq!(geometry, feature.get_geometry());
+
+        let shape: GeoShape = serde_json::from_value(geometry.clone()).unwrap();
+        assert_eq!(geometry, shape.get_geometry());
+    }
+
+    #[test]
+    fn test_deserialize_file_field () {
+        let file: FileField = serde_json::from_value(json!({
+            "thumbnail": true,
+            "filename": "logo.png",
+            "format": "PNG",
+            "width": 200,
+            "id": "1c1a3d1e0e0b4d6b9f0f0c9a7f6c3b2a",
+            "height": 100,
+            "mimetype": "image/png",
+        })).unwrap();
+
+        assert_eq!(Some("logo.png".to_string()), file.filename);
+        assert_eq!(None, file.url);
+    }
+}
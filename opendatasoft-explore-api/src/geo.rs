@@ -0,0 +1,257 @@
+//! Geographic helpers, to read records geometries and build GeoJSON documents.
+//!
+//! The geo_point_2d and geo_shape fields are converted into geo-types
+//! geometries, so they can be used with the geo ecosystem crates.
+//!
+//! The [`FeatureCollectionBuilder`] turns records into a GeoJSON
+//! FeatureCollection, with the geometry of a field and selected properties:
+//!
+//! ```
+//! use opendatasoft_explore_api::geo::FeatureCollectionBuilder;
+//! use opendatasoft_explore_api::schema::Results;
+//!
+//! fn print_geojson (results: &Results) {
+//!     let collection = FeatureCollectionBuilder::new("latitude")
+//!         .with_properties(&["cct_denomination", "prix_visite"])
+//!         .from_results(results);
+//!
+//!     println!("{}", collection);
+//! }
+//! ```
+
+use futures_util::{Stream, StreamExt};
+use geo_types::{Geometry, Point};
+use geojson::feature::Id;
+use geojson::{Feature, FeatureCollection, JsonObject};
+use serde_json::Value as JsonValue;
+
+use crate::error::Error;
+use crate::fields::{GeoPoint2D, GeoShape};
+use crate::schema::{Record, RecordProperties, Results};
+
+/*  -------------------------------------------------------------
+    Geometries
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+impl From<GeoPoint2D> for Point<f64> {
+    fn from (point: GeoPoint2D) -> Self {
+        Point::new(point.lon, point.lat)
+    }
+}
+
+impl From<Point<f64>> for GeoPoint2D {
+    fn from (point: Point<f64>) -> Self {
+        Self {
+            lon: point.x(),
+            lat: point.y(),
+        }
+    }
+}
+
+impl GeoShape {
+    /// Converts the shape into a geo-types geometry.
+    ///
+    /// Returns None if the GeoJSON geometry is invalid.
+    pub fn to_geometry (&self) -> Option<Geometry<f64>> {
+        let geometry = geojson::Geometry::from_json_value(self.get_geometry()).ok()?;
+
+        Geometry::try_from(geometry).ok()
+    }
+}
+
+/// Parses a geo_point_2d or a geo_shape field value as a geometry.
+pub fn parse_geometry (value: &JsonValue) -> Option<Geometry<f64>> {
+    if value.get("type").is_some() {
+        let shape: GeoShape = serde_json::from_value(value.clone()).ok()?;
+        return shape.to_geometry();
+    }
+
+    let point: GeoPoint2D = serde_json::from_value(value.clone()).ok()?;
+    Some(Geometry::Point(point.into()))
+}
+
+impl RecordProperties {
+    /// Gets the geometry of a geo_point_2d or geo_shape field.
+    pub fn get_geometry (&self, field: &str) -> Option<Geometry<f64>> {
+        self.fields
+            .get(field)
+            .and_then(parse_geometry)
+    }
+}
+
+impl Record {
+    /// Gets the geometry of a geo_point_2d or geo_shape field.
+    pub fn get_geometry (&self, field: &str) -> Option<Geometry<f64>> {
+        self.record.get_geometry(field)
+    }
+}
+
+/*  -------------------------------------------------------------
+    GeoJSON
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Builds GeoJSON features from records
+///
+/// Records without a valid geometry in the geometry field are skipped.
+#[derive(Clone, Debug)]
+pub struct FeatureCollectionBuilder {
+    geometry_field: String,
+
+    /// The fields to copy as feature properties, or None for every field
+    properties: Option<Vec<String>>,
+}
+
+impl FeatureCollectionBuilder {
+    /// * `geometry_field` - The geo_point_2d or geo_shape field to use as feature geometry
+    pub fn new (geometry_field: &str) -> Self {
+        Self {
+            geometry_field: geometry_field.to_string(),
+            properties: None,
+        }
+    }
+
+    /// Restricts the feature properties to these fields.
+    ///
+    /// By default, every field but the geometry one is copied.
+    pub fn with_properties (mut self, fields: &[&str]) -> Self {
+        self.properties = Some(fields.iter().map(|field| field.to_string()).collect());
+        self
+    }
+
+    /// Builds a GeoJSON feature from a record, if it has a geometry.
+    ///
+    /// The record id is used as feature id, when the record has one.
+    pub fn to_feature (&self, record: &Record) -> Option<Feature> {
+        let geometry = record.get_geometry(&self.geometry_field)?;
+
+        let id = match record.record.id.as_str() {
+            "" => None,
+            id => Some(Id::String(id.to_string())),
+        };
+
+        Some(Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::from(&geometry)),
+            id,
+            properties: Some(self.get_properties(&record.record.fields)),
+            foreign_members: None,
+        })
+    }
+
+    fn get_properties (&self, fields: &JsonValue) -> JsonObject {
+        let fields = match fields.as_object() {
+            Some(fields) => fields,
+            None => return JsonObject::new(),
+        };
+
+        match &self.properties {
+            Some(properties) => properties
+                .iter()
+                .filter_map(|name| fields.get(name).map(|value| (name.clone(), value.clone())))
+                .collect(),
+
+            None => fields
+                .iter()
+                .filter(|(name, _)| **name != self.geometry_field)
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    pub fn from_records<'a, I> (&self, records: I) -> FeatureCollection where I: IntoIterator<Item = &'a Record> {
+        let features = records
+            .into_iter()
+            .filter_map(|record| self.to_feature(record))
+            .collect();
+
+        build_feature_collection(features)
+    }
+
+    /// Builds a FeatureCollection from a page of results.
+    ///
+    /// Aggregations results are ignored.
+    pub fn from_results (&self, results: &Results) -> FeatureCollection {
+        self.from_records(results.get_records())
+    }
+
+    /// Builds a FeatureCollection from a records stream, e.g. `ExploreApiEndPoint::records_stream`.
+    ///
+    /// Stops at the first error of the stream.
+    pub async fn from_stream<S> (&self, records: S) -> Result<FeatureCollection, Error>
+    where S: Stream<Item = Result<Record, Error>> {
+        let mut features = Vec::new();
+
+        let mut records = Box::pin(records);
+        while let Some(record) = records.next().await {
+            if let Some(feature) = self.to_feature(&record?) {
+                features.push(feature);
+            }
+        }
+
+        Ok(build_feature_collection(features))
+    }
+}
+
+fn build_feature_collection (features: Vec<Feature>) -> FeatureCollection {
+    FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_geometry () {
+        let point = parse_geometry(&json!({"lon": -1.867611, "lat": 46.85617}));
+        assert_eq!(Some(Geometry::Point(Point::new(-1.867611, 46.85617))), point);
+
+        let shape = parse_geometry(&json!({
+            "type": "Feature",
+            "geometry": {"type": "LineString", "coordinates": [[2.35, 48.85], [2.36, 48.86]]},
+            "properties": {},
+        }));
+        assert!(matches!(shape, Some(Geometry::LineString(_))));
+
+        let shape = parse_geometry(&json!({"type": "Point", "coordinates": [2.35, 48.85]}));
+        assert_eq!(Some(Geometry::Point(Point::new(2.35, 48.85))), shape);
+
+        assert_eq!(None, parse_geometry(&json!("85300")));
+    }
+
+    #[test]
+    fn test_to_feature () {
+        let record = Record {
+            record: RecordProperties {
+                id: "eb04cba1".to_string(),
+                fields: json!({
+                    "cct_denomination": "AUTO BILAN CHALLANDAIS",
+                    "prix_visite": 94,
+                    "latitude": {"lon": -1.867611, "lat": 46.85617},
+                }),
+                ..RecordProperties::default()
+            },
+            links: Vec::new(),
+        };
+
+        let feature = FeatureCollectionBuilder::new("latitude")
+            .to_feature(&record)
+            .unwrap();
+        assert_eq!(Some(Id::String("eb04cba1".to_string())), feature.id);
+        assert_eq!(Some(&json!(94)), feature.property("prix_visite"));
+        assert!(!feature.contains_property("latitude"));
+
+        let feature = FeatureCollectionBuilder::new("latitude")
+            .with_properties(&["cct_denomination"])
+            .to_feature(&record)
+            .unwrap();
+        assert_eq!(1, feature.properties.unwrap().len());
+
+        assert!(FeatureCollectionBuilder::new("geo_shape").to_feature(&record).is_none());
+    }
+}
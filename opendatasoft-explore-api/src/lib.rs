@@ -1,4 +1,4 @@
-//! `opendatasoft_explore_api` is an Opendatasoft Explore API v2 client library.
+//! `opendatasoft_explore_api` is an Opendatasoft Explore API v2 and v2.1 client library.
 //!
 //! It allows to query open data portals from public administrations and companies
 //! to get information about datasets metadata and records.
@@ -19,8 +19,10 @@
 //! async fn main() {
 //!     let endpoint = ExploreApiEndPoint::new(API_URL);
 //!
-//!     let dataset = endpoint.get_dataset_information(DATASET_ID).await;
-//!     println!("{:?}", dataset);
+//!     match endpoint.get_dataset_information(DATASET_ID).await {
+//!         Ok(dataset) => println!("{:?}", dataset),
+//!         Err(error) => eprintln!("Can't query dataset: {}", error),
+//!     }
 //! }
 //! ```
 //!
@@ -36,19 +38,88 @@
 //!
 //! Serde converts JSON responses into the structures defined in schema module.
 //!
+//! Requests return a `Result`, with the errors defined in error module.
+//! When the portal replies with an error document, its error code and message
+//! are available through `Error::Api`.
+//!
 //! # Library organization
 //!
-//! The crate offers is organization in two modules:
+//! The crate offers is organization in fifteen modules:
 //!
-//! * In requests module, the [`ExploreApiEndPoint`](./requests/struct.ExploreApiEndPoint.html)
+//! * In requests module, the [`ExploreApiEndPoint`](./requests/type.ExploreApiEndPoint.html)
 //!   allows to prepare an HTTP client and define the end-point API URL;
 //!
-//! * In schema module, the structs represent datatypes used by the API responses.
+//! * In schema module, the structs represent datatypes used by the API responses;
+//!
+//! * In metas module, the [`DatasetMetas`](./metas/struct.DatasetMetas.html) struct
+//!   types the default, dcat, dcat_ap, inspire and semantic metadata templates;
+//!
+//! * In fields module, helper types like [`GeoPoint2D`](./fields/struct.GeoPoint2D.html)
+//!   allow to deserialize records into your own structs, with `Record::fields_as`;
+//!
+//! * In geo module, with the `geo` feature, records geo_point_2d and geo_shape fields
+//!   are read as geo-types geometries, and the
+//!   [`FeatureCollectionBuilder`](./geo/struct.FeatureCollectionBuilder.html)
+//!   converts records into GeoJSON;
+//!
+//! * In query module, the [`RecordsQuery`](./query/struct.RecordsQuery.html) builder
+//!   allows to pass optional parameters like select, where, order_by, limit or refine.
+//!
+//! * In export module, the [`ExportFormat`](./export/enum.ExportFormat.html) enum
+//!   and the `ExportOptions` builder describe datasets and catalog exports;
+//!
+//! * In rdf module, [`parse_graph`](./rdf/fn.parse_graph.html) reads the RDF/XML and Turtle
+//!   catalog exports as oxrdf graphs, and the dcat submodule lists their datasets;
+//!
+//! * In odsql module, builders render where and select clauses as correctly escaped ODSQL;
+//!
+//! * In error module, the [`Error`](./error/enum.Error.html) enum represents
+//!   transport, HTTP status, API and deserialization errors.
+//!
+//! * In auth module, the [`ApiKey`](./auth/struct.ApiKey.html) allows to query
+//!   restricted datasets, through `ExploreApiEndPoint::with_api_key`.
+//!
+//! * In watcher module, the [`DatasetWatcher`](./watcher/struct.DatasetWatcher.html)
+//!   detects new attachments, data updates or schema changes since a previous run;
+//!
+//! * In drift module, [`compare_schemas`](./drift/fn.compare_schemas.html) compares dataset
+//!   fields to a stored snapshot, to detect added, removed, renamed or retyped fields;
+//!
+//! * In mirror module, the [`CatalogMirror`](./mirror/struct.CatalogMirror.html)
+//!   snapshots the catalog metadata into JSON files, as the ods-mirror command does;
+//!
+//! * In v21 module, the [`ExploreApiV21EndPoint`](./v21/requests/type.ExploreApiV21EndPoint.html)
+//!   offers the same requests for the API v2.1, with its own schema.
+//!
+//! With the `blocking` feature, the blocking module offers an `ExploreApiEndPoint`
+//! with the same requests as synchronous methods, for tools without async runtime.
 //!
 //! The requests are documented in `ExploreApiEndPoint`. From there, you'll always have a link
 //! to the schema used, as the return type of the method.
 
+pub mod auth;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod drift;
+pub mod error;
+pub mod export;
+pub mod fields;
+#[cfg(feature = "geo")]
+pub mod geo;
+pub mod metas;
+pub mod mirror;
+pub mod odsql;
+pub mod query;
+pub mod rdf;
 pub mod schema;
 pub mod requests;
+pub mod v21;
+pub mod watcher;
+
+mod pagination;
+mod routes;
+mod transport;
+
+pub use error::Error;
 
 pub use reqwest::Response as ApiHttpResponse;
@@ -0,0 +1,413 @@
+//! Dataset metadata, as organized in Opendatasoft metadata templates.
+//!
+//! The `default` template is filled for every dataset. Portals can enable
+//! the standard `dcat`, `dcat_ap`, `inspire` and `semantic` templates,
+//! and define their own, kept in [`DatasetMetas::other`].
+//!
+//! In each template, the well-known keys are typed, while the other keys
+//! are kept as JSON values in an `extra` map. Values with an unexpected shape,
+//! e.g. a number where a string is expected, are left out of the typed view
+//! and kept in `extra` too, so the metadata is serialized back as received.
+//! [`DatasetMetas::get`] returns any value as sent by the API.
+//!
+//! ```
+//! use opendatasoft_explore_api::schema::DatasetProperties;
+//!
+//! fn print_dataset (dataset: &DatasetProperties) {
+//!     println!("{} ({})", dataset.title().unwrap_or(&dataset.dataset_id), dataset.license().unwrap_or("no license"));
+//!
+//!     if let Some(inspire) = &dataset.metas.inspire {
+//!         println!("INSPIRE theme: {:?}", inspire.theme);
+//!     }
+//! }
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::de::{DeserializeOwned, Deserializer};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Map as JsonMap;
+use serde_json::Value as JsonValue;
+
+/*  -------------------------------------------------------------
+    Metadata templates
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// The metadata of a dataset, by template
+#[derive(Clone, Debug, PartialEq, Default, Serialize)]
+pub struct DatasetMetas {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<DefaultMetas>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dcat: Option<DcatMetas>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dcat_ap: Option<DcatApMetas>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inspire: Option<InspireMetas>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semantic: Option<SemanticMetas>,
+
+    /// The portal custom templates, e.g. "custom",
+    /// and the templates which aren't an object
+    #[serde(flatten)]
+    pub other: JsonMap<String, JsonValue>,
+
+    /// The templates as returned by the API, to get values by name
+    #[serde(skip)]
+    raw: JsonMap<String, JsonValue>,
+}
+
+impl<'de> serde::Deserialize<'de> for DatasetMetas {
+    fn deserialize<D> (deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let raw: JsonMap<String, JsonValue> = serde::Deserialize::deserialize(deserializer)?;
+
+        let mut other = raw.clone();
+        Ok(Self {
+            default: take_template(&mut other, "default"),
+            dcat: take_template(&mut other, "dcat"),
+            dcat_ap: take_template(&mut other, "dcat_ap"),
+            inspire: take_template(&mut other, "inspire"),
+            semantic: take_template(&mut other, "semantic"),
+            other,
+            raw,
+        })
+    }
+}
+
+/// A metadata template, with the values not read as typed fields in an extra map
+trait Template: DeserializeOwned + serde::Serialize {
+    fn extra_mut (&mut self) -> &mut JsonMap<String, JsonValue>;
+}
+
+/// Removes a template from the metadata to read it as a typed view.
+///
+/// A template which can't be read, e.g. as it isn't an object, is left in the metadata.
+/// The values left out of the typed fields, e.g. null or with an unexpected shape,
+/// are kept in the extra map of the template.
+fn take_template<T> (metas: &mut JsonMap<String, JsonValue>, template: &str) -> Option<T>
+where T: Template {
+    let value = metas.remove(template)?;
+
+    let mut typed_template: T = match serde_json::from_value(value.clone()) {
+        Ok(typed_template) => typed_template,
+        Err(_) => {
+            metas.insert(template.to_string(), value);
+            return None;
+        }
+    };
+
+    let read_values = match serde_json::to_value(&typed_template) {
+        Ok(JsonValue::Object(read_values)) => read_values,
+        _ => JsonMap::new(),
+    };
+    if let JsonValue::Object(values) = value {
+        for (key, value) in values {
+            if !read_values.contains_key(&key) {
+                typed_template.extra_mut().insert(key, value);
+            }
+        }
+    }
+
+    Some(typed_template)
+}
+
+/// Deserializes an optional value, as None when it doesn't have the expected shape.
+fn lenient<'de, D, T> (deserializer: D) -> Result<Option<T>, D::Error>
+where D: Deserializer<'de>, T: DeserializeOwned {
+    let value: JsonValue = serde::Deserialize::deserialize(deserializer)?;
+
+    Ok(serde_json::from_value(value).ok())
+}
+
+/// The default template, available for every dataset
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct DefaultMetas {
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// The description, as HTML
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<Vec<String>>,
+
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub license_url: Option<String>,
+
+    /// The language of the data, as ISO 639-1 code
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub metadata_languages: Option<Vec<String>>,
+
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    /// The last modification, of data or metadata according to the
+    /// modified_updates_on_data_change and modified_updates_on_metadata_change settings
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub modified: Option<DateTime<Utc>>,
+
+    /// The last time data has been processed, null for datasets without records
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub data_processed: Option<DateTime<Utc>>,
+
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub metadata_processed: Option<DateTime<Utc>>,
+
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub territory: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub references: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub attributions: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub records_count: Option<u64>,
+
+    /// Federated datasets come from another portal, see source_domain
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub federated: Option<bool>,
+
+    #[serde(flatten)]
+    pub extra: JsonMap<String, JsonValue>,
+}
+
+/// The DCAT template, following the W3C Data Catalog Vocabulary
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct DcatMetas {
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub issued: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contributor: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_name: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_email: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub accrualperiodicity: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub spatial: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub temporal: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub granularity: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub dataquality: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub conforms_to: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: JsonMap<String, JsonValue>,
+}
+
+/// The DCAT-AP template, for the European application profile
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct DcatApMetas {
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub availability: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_url: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub has_version: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub is_version_of: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub spatial_resolution_in_meters: Option<f64>,
+
+    #[serde(flatten)]
+    pub extra: JsonMap<String, JsonValue>,
+}
+
+/// The INSPIRE template, for geographic datasets of the European directive
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct InspireMetas {
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    #[serde(rename = "type", default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub dataset_type: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub file_identifier: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub hierarchy_level: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub hierarchy_level_name: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub spatial_resolution: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub topologic_consistency: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_individual_name: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_position: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_address: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_email: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub contact_organisation: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<String>,
+
+    #[serde(flatten)]
+    pub extra: JsonMap<String, JsonValue>,
+}
+
+/// The semantic template, describing the RDF mapping of the records
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct SemanticMetas {
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub rml_mapping: Option<String>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub classes: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "lenient", skip_serializing_if = "Option::is_none")]
+    pub properties: Option<Vec<String>>,
+
+    #[serde(flatten)]
+    pub extra: JsonMap<String, JsonValue>,
+}
+
+macro_rules! impl_template {
+    ($($template:ty),*) => {
+        $(
+            impl Template for $template {
+                fn extra_mut (&mut self) -> &mut JsonMap<String, JsonValue> {
+                    &mut self.extra
+                }
+            }
+        )*
+    };
+}
+
+impl_template!(DefaultMetas, DcatMetas, DcatApMetas, InspireMetas, SemanticMetas);
+
+impl DatasetMetas {
+    /// Gets a metadata value by template and name, as returned by the API,
+    /// whatever it's typed or not.
+    pub fn get (&self, template: &str, name: &str) -> Option<JsonValue> {
+        match self.raw.get(template)?.get(name)? {
+            JsonValue::Null => None,
+            value => Some(value.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_metas () {
+        let metas: DatasetMetas = serde_json::from_value(json!({
+            "default": {
+                "title": "Fichier FANTOIR des voies et lieux-dits",
+                "keyword": ["cadastre", "fantoir"],
+                "modified": "2022-11-04T09:03:03.268000+00:00",
+                "data_processed": null,
+                "records_count": 0,
+                "modified_updates_on_data_change": false,
+            },
+            "inspire": {
+                "theme": "Addresses",
+                "type": "dataset",
+            },
+            "custom": {
+                "editeur": ["DGFIP"],
+            },
+        })).unwrap();
+
+        let default_metas = metas.default.as_ref().unwrap();
+        assert_eq!(Some(vec!["cadastre".to_string(), "fantoir".to_string()]), default_metas.keyword);
+        assert_eq!(None, default_metas.data_processed);
+        assert_eq!(Some(&json!(false)), default_metas.extra.get("modified_updates_on_data_change"));
+
+        assert_eq!(Some("dataset"), metas.inspire.as_ref().unwrap().dataset_type.as_deref());
+        assert_eq!(None, metas.dcat);
+
+        assert_eq!(Some(json!(["DGFIP"])), metas.get("custom", "editeur"));
+        assert_eq!(Some(json!("Addresses")), metas.get("inspire", "theme"));
+        assert_eq!(None, metas.get("default", "data_processed"));
+    }
+
+    #[test]
+    fn test_deserialize_metas_with_unexpected_shapes () {
+        let metas: DatasetMetas = serde_json::from_value(json!({
+            "default": {
+                "title": "Contrôle technique",
+                "keyword": "automobile",
+                "records_count": "unknown",
+            },
+            "dcat": "not a template",
+        })).unwrap();
+
+        let default_metas = metas.default.as_ref().unwrap();
+        assert_eq!(Some("Contrôle technique"), default_metas.title.as_deref());
+        assert_eq!(None, default_metas.keyword);
+        assert_eq!(None, default_metas.records_count);
+        assert_eq!(Some(json!("automobile")), metas.get("default", "keyword"));
+
+        assert_eq!(Some(&json!("automobile")), default_metas.extra.get("keyword"));
+
+        assert_eq!(None, metas.dcat);
+        assert_eq!(Some(&json!("not a template")), metas.other.get("dcat"));
+    }
+
+    #[test]
+    fn test_serialize_metas_round_trip () {
+        let value = json!({
+            "default": {
+                "title": "Contrôle technique",
+                "keyword": "automobile",
+                "records_count": "unknown",
+                "data_processed": null,
+                "modified": "2022-11-04T09:03:03Z",
+            },
+            "inspire": {
+                "type": "dataset",
+            },
+            "dcat": "not a template",
+            "custom": {
+                "cycle": ["Finalisé"],
+            },
+        });
+
+        let metas: DatasetMetas = serde_json::from_value(value.clone()).unwrap();
+        let serialized = serde_json::to_value(&metas).unwrap();
+
+        assert_eq!(value, serialized);
+    }
+
+    #[test]
+    fn test_serialize_metas_keeps_unknown_keys () {
+        let value = json!({
+            "default": {
+                "title": "Contrôle technique",
+                "geographic_reference": ["world_fr"],
+            },
+            "custom": {
+                "cycle": ["Finalisé"],
+            },
+        });
+
+        let metas: DatasetMetas = serde_json::from_value(value).unwrap();
+        let serialized = serde_json::to_value(&metas).unwrap();
+
+        assert_eq!(json!(["world_fr"]), serialized["default"]["geographic_reference"]);
+        assert_eq!(json!(["Finalisé"]), serialized["custom"]["cycle"]);
+    }
+}
@@ -0,0 +1,219 @@
+//! Mirror the catalog metadata of a portal into a directory of JSON files.
+//!
+//! The directory is organized as:
+//!
+//! ```text
+//! catalog/datasets.json                 the datasets of the catalog
+//! catalog/facets.json                   the catalog facets
+//! datasets/<dataset_id>/dataset.json    the dataset information
+//! datasets/<dataset_id>/attachments.json
+//! datasets/<dataset_id>/export.<ext>    the dataset export, for selected datasets
+//! state.json                            the datasets states, to detect changes
+//! ```
+//!
+//! At each run, the datasets states are compared with the ones of the previous run,
+//! as the dataset watcher does, so only the changed datasets are refreshed.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use futures_util::TryStreamExt;
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::export::{ExportFormat, ExportOptions};
+use crate::query::RecordsQuery;
+use crate::requests::ExploreApiEndPoint;
+use crate::schema::DatasetProperties;
+use crate::watcher::{compare_states, read_states, write_states, DatasetState};
+
+/// The number of datasets to query by page
+static DATASETS_PAGE_SIZE: usize = 100;
+
+/*  -------------------------------------------------------------
+    Catalog mirror
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Snapshots the catalog metadata of a portal into a directory
+pub struct CatalogMirror<'a> {
+    endpoint: &'a ExploreApiEndPoint,
+
+    /// The directory to write the mirror to
+    pub directory: PathBuf,
+
+    /// The datasets to export in addition to their metadata
+    pub exports: Vec<String>,
+
+    pub export_format: ExportFormat,
+
+    /// Refreshes all the datasets, even when they didn't change
+    pub force_refresh: bool,
+}
+
+/// What a mirror run did
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MirrorReport {
+    pub datasets_count: usize,
+    pub refreshed: Vec<String>,
+    pub exported: Vec<String>,
+    pub removed: Vec<String>,
+
+    /// Datasets ignored as their identifier can't be used as directory name
+    pub skipped: Vec<String>,
+}
+
+impl<'a> CatalogMirror<'a> {
+    pub fn new<P> (endpoint: &'a ExploreApiEndPoint, directory: P) -> Self where P: AsRef<Path> {
+        Self {
+            endpoint,
+            directory: directory.as_ref().to_path_buf(),
+            exports: Vec::new(),
+            export_format: ExportFormat::Csv,
+            force_refresh: false,
+        }
+    }
+
+    pub fn with_exports (mut self, dataset_ids: Vec<String>, format: ExportFormat) -> Self {
+        self.exports = dataset_ids;
+        self.export_format = format;
+        self
+    }
+
+    pub fn with_force_refresh (mut self, force_refresh: bool) -> Self {
+        self.force_refresh = force_refresh;
+        self
+    }
+
+    pub async fn run (&self) -> Result<MirrorReport, Error> {
+        let mut report = MirrorReport::default();
+
+        let datasets = self.get_all_datasets().await?;
+        report.datasets_count = datasets.len();
+        self.write_json("catalog/datasets.json", &datasets).await?;
+
+        let facets = self.endpoint.get_facets(&RecordsQuery::new()).await?;
+        self.write_json("catalog/facets.json", &facets).await?;
+
+        let state_path = self.directory.join("state.json");
+        let previous_states = read_states(&state_path).await?;
+        let mut states = HashMap::new();
+
+        for dataset in &datasets {
+            let dataset_id = &dataset.dataset_id;
+            if !is_safe_directory_name(dataset_id) {
+                report.skipped.push(dataset_id.clone());
+                continue;
+            }
+
+            let state = DatasetState::from_dataset(dataset);
+            let has_changed = !compare_states(previous_states.get(dataset_id), &state).is_empty();
+
+            let must_refresh = has_changed || self.force_refresh
+                || !self.get_dataset_path(dataset_id).join("dataset.json").exists();
+            if must_refresh {
+                self.refresh_dataset(dataset_id).await?;
+                report.refreshed.push(dataset_id.clone());
+            }
+
+            let must_export = self.exports.contains(dataset_id)
+                && (must_refresh || !self.get_export_path(dataset_id).exists());
+            if must_export {
+                self.export_dataset(dataset_id).await?;
+                report.exported.push(dataset_id.clone());
+            }
+
+            states.insert(dataset_id.clone(), state);
+        }
+
+        for dataset_id in previous_states.keys() {
+            if !states.contains_key(dataset_id) {
+                self.remove_dataset(dataset_id).await?;
+                report.removed.push(dataset_id.clone());
+            }
+        }
+
+        write_states(&state_path, &states).await?;
+
+        Ok(report)
+    }
+
+    async fn get_all_datasets (&self) -> Result<Vec<DatasetProperties>, Error> {
+        let query = RecordsQuery::new()
+            .order_by("dataset_id")
+            .limit(DATASETS_PAGE_SIZE);
+
+        self.endpoint
+            .datasets_stream(&query)
+            .map_ok(|dataset| dataset.dataset)
+            .try_collect()
+            .await
+    }
+
+    async fn refresh_dataset (&self, dataset_id: &str) -> Result<(), Error> {
+        let dataset = self.endpoint.get_dataset_information(dataset_id).await?;
+        self.write_json(&format!("datasets/{}/dataset.json", dataset_id), &dataset).await?;
+
+        let attachments = self.endpoint.get_dataset_attachments(dataset_id).await?;
+        self.write_json(&format!("datasets/{}/attachments.json", dataset_id), &attachments).await
+    }
+
+    async fn export_dataset (&self, dataset_id: &str) -> Result<usize, Error> {
+        let path = self.get_export_path(dataset_id);
+
+        self.endpoint
+            .export_dataset_to_file(dataset_id, self.export_format, &RecordsQuery::new(), &ExportOptions::new(), path)
+            .await
+    }
+
+    async fn remove_dataset (&self, dataset_id: &str) -> Result<(), Error> {
+        let path = self.get_dataset_path(dataset_id);
+        if !is_safe_directory_name(dataset_id) || !path.exists() {
+            return Ok(());
+        }
+
+        tokio::fs::remove_dir_all(path).await
+            .map_err(Error::IO)
+    }
+
+    fn get_dataset_path (&self, dataset_id: &str) -> PathBuf {
+        self.directory.join("datasets").join(dataset_id)
+    }
+
+    fn get_export_path (&self, dataset_id: &str) -> PathBuf {
+        self.get_dataset_path(dataset_id)
+            .join(format!("export.{}", self.export_format.file_extension()))
+    }
+
+    async fn write_json<T> (&self, relative_path: &str, value: &T) -> Result<(), Error> where T: Serialize {
+        let path = self.directory.join(relative_path);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await
+                .map_err(Error::IO)?;
+        }
+
+        let content = serde_json::to_string_pretty(value)
+            .map_err(Error::Json)?;
+        tokio::fs::write(path, content).await
+            .map_err(Error::IO)
+    }
+}
+
+/// Determines if a dataset identifier can be used as is as a directory name.
+fn is_safe_directory_name (name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\', '\0'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_safe_directory_name () {
+        assert!(is_safe_directory_name("fichier-fantoir-des-voies-et-lieux-dits"));
+        assert!(!is_safe_directory_name(".."));
+        assert!(!is_safe_directory_name("foo/../../bar"));
+        assert!(!is_safe_directory_name(""));
+    }
+}
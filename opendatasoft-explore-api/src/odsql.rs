@@ -0,0 +1,628 @@
+//! Builder for ODSQL expressions, to use in where and select clauses.
+//!
+//! Strings are rendered between double quotes, with double quotes and backslashes
+//! escaped, so labels like "Rue de l'Église" don't need any special care.
+//! Fields names are enclosed in backquotes when they aren't plain identifiers.
+//! ODSQL can't express NaN or infinite numbers, so conditions with such
+//! a value fail with `Error::InvalidExpression`.
+//!
+//! ```
+//! use opendatasoft_explore_api::odsql::{field, search};
+//! use opendatasoft_explore_api::query::RecordsQuery;
+//!
+//! # fn main() -> Result<(), opendatasoft_explore_api::error::Error> {
+//! let condition = field("cct_code_commune").eq("L'Île-d'Yeu")?
+//!     .and(field("prix_visite").le(80)?)
+//!     .or(search("électrique"));
+//!
+//! assert_eq!(
+//!     r#"(cct_code_commune = "L'Île-d'Yeu" AND prix_visite <= 80) OR search("électrique")"#,
+//!     condition.to_string()
+//! );
+//!
+//! let query = RecordsQuery::new().where_clause(condition);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+
+use crate::error::Error;
+
+/*  -------------------------------------------------------------
+    Values
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A literal value, or a function returning a value like `now()`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+
+    /// An expression rendered as is
+    Expression(String),
+}
+
+impl Display for Value {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", quote_string(value)),
+            Value::Date(value) => write!(f, "date'{}'", value.format("%Y-%m-%d")),
+            Value::DateTime(value) => {
+                write!(f, "date'{}'", value.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            }
+            Value::Expression(expression) => write!(f, "{}", expression),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from (value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from (value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from (value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from (value: i32) -> Self {
+        Value::Int(value as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from (value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from (value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<NaiveDate> for Value {
+    fn from (value: NaiveDate) -> Self {
+        Value::Date(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from (value: DateTime<Utc>) -> Self {
+        Value::DateTime(value)
+    }
+}
+
+/// The current date and time
+pub fn now () -> Value {
+    Value::Expression("now()".to_string())
+}
+
+/// The current date and time, shifted by a number of days, e.g. -7 for a week ago
+pub fn now_shifted_by_days (days: i64) -> Value {
+    Value::Expression(format!("now(days={})", days))
+}
+
+/// Renders a string literal, escaping backslashes and double quotes.
+pub fn quote_string (value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"");
+
+    format!("\"{}\"", escaped)
+}
+
+/*  -------------------------------------------------------------
+    Fields and functions
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A field, or a function applied to fields, like `year(date_field)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    expression: String,
+}
+
+/// A field of the dataset
+pub fn field (name: &str) -> Field {
+    Field {
+        expression: quote_identifier(name),
+    }
+}
+
+/// The number of records, for aggregations
+pub fn count_all () -> Field {
+    Field {
+        expression: "count(*)".to_string(),
+    }
+}
+
+impl Field {
+    fn apply (&self, function: &str) -> Field {
+        Field {
+            expression: format!("{}({})", function, self.expression),
+        }
+    }
+
+    pub fn year (&self) -> Field {
+        self.apply("year")
+    }
+
+    pub fn month (&self) -> Field {
+        self.apply("month")
+    }
+
+    pub fn day (&self) -> Field {
+        self.apply("day")
+    }
+
+    pub fn count (&self) -> Field {
+        self.apply("count")
+    }
+
+    pub fn avg (&self) -> Field {
+        self.apply("avg")
+    }
+
+    pub fn sum (&self) -> Field {
+        self.apply("sum")
+    }
+
+    pub fn min (&self) -> Field {
+        self.apply("min")
+    }
+
+    pub fn max (&self) -> Field {
+        self.apply("max")
+    }
+
+    /*  - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+        Comparisons
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+    fn compare<V> (&self, operator: &str, value: V) -> Result<Condition, Error> where V: Into<Value> {
+        let value = self.check_value(value.into())?;
+
+        Ok(Condition::Expression(format!("{} {} {}", self.expression, operator, value)))
+    }
+
+    /// Fails with `Error::InvalidExpression` for a float which is NaN or infinite.
+    fn check_value (&self, value: Value) -> Result<Value, Error> {
+        match value {
+            Value::Float(number) => self.check_number(number).map(Value::Float),
+            value => Ok(value),
+        }
+    }
+
+    fn check_number (&self, number: f64) -> Result<f64, Error> {
+        if number.is_finite() {
+            Ok(number)
+        } else {
+            Err(Error::InvalidExpression(
+                format!("{} can't be compared to {}", self.expression, number)
+            ))
+        }
+    }
+
+    pub fn eq<V> (&self, value: V) -> Result<Condition, Error> where V: Into<Value> {
+        self.compare("=", value)
+    }
+
+    pub fn ne<V> (&self, value: V) -> Result<Condition, Error> where V: Into<Value> {
+        self.compare("!=", value)
+    }
+
+    pub fn lt<V> (&self, value: V) -> Result<Condition, Error> where V: Into<Value> {
+        self.compare("<", value)
+    }
+
+    pub fn le<V> (&self, value: V) -> Result<Condition, Error> where V: Into<Value> {
+        self.compare("<=", value)
+    }
+
+    pub fn gt<V> (&self, value: V) -> Result<Condition, Error> where V: Into<Value> {
+        self.compare(">", value)
+    }
+
+    pub fn ge<V> (&self, value: V) -> Result<Condition, Error> where V: Into<Value> {
+        self.compare(">=", value)
+    }
+
+    pub fn is_null (&self) -> Condition {
+        Condition::Expression(format!("{} is null", self.expression))
+    }
+
+    pub fn is_not_null (&self) -> Condition {
+        Condition::Expression(format!("{} is not null", self.expression))
+    }
+
+    /// Matches records where the field is one of the values.
+    ///
+    /// Fails with `Error::InvalidExpression` for an empty list,
+    /// or for a float value which is NaN or infinite, as ODSQL can't express them.
+    pub fn in_list<I, V> (&self, values: I) -> Result<Condition, Error>
+    where I: IntoIterator<Item = V>, V: Into<Value> {
+        let values = values
+            .into_iter()
+            .map(|value| self.check_value(value.into()).map(|value| value.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if values.is_empty() {
+            return Err(Error::InvalidExpression(
+                format!("{} can't be compared to an empty list", self.expression)
+            ));
+        }
+
+        Ok(Condition::Expression(format!("{} IN ({})", self.expression, values.join(", "))))
+    }
+
+    /// Matches records where the field is in the range, bounds included.
+    pub fn in_range<V> (&self, from: V, to: V) -> Result<Condition, Error> where V: Into<Value> {
+        let from = self.check_value(from.into())?;
+        let to = self.check_value(to.into())?;
+
+        Ok(Condition::Expression(format!("{} in [{}..{}]", self.expression, from, to)))
+    }
+
+    /// Full-text search restricted to this field
+    pub fn search (&self, text: &str) -> Condition {
+        Condition::Expression(format!("search({}, {})", self.expression, quote_string(text)))
+    }
+
+    /*  - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -
+        Geographic filters
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+    /// Matches records with a geo field at most at the distance of a point.
+    pub fn within_distance (&self, lon: f64, lat: f64, distance: Distance) -> Result<Condition, Error> {
+        let lon = self.check_number(lon)?;
+        let lat = self.check_number(lat)?;
+        self.check_number(distance.value())?;
+
+        Ok(Condition::Expression(format!(
+            "within_distance({}, geom'POINT({} {})', {})",
+            self.expression, lon, lat, distance
+        )))
+    }
+
+    /// Matches records with a geo field in a bounding box, given by two opposite corners.
+    pub fn in_bbox (&self, lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> Result<Condition, Error> {
+        let corners = [lat1, lon1, lat2, lon2]
+            .into_iter()
+            .map(|number| self.check_number(number))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Condition::Expression(format!(
+            "in_bbox({}, {}, {}, {}, {})",
+            self.expression, corners[0], corners[1], corners[2], corners[3]
+        )))
+    }
+}
+
+impl Display for Field {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
+
+static KEYWORDS: [&str; 16] = [
+    "and", "as", "asc", "by", "desc", "false", "group", "in", "is",
+    "like", "not", "null", "or", "order", "select", "true",
+];
+
+/// Encloses a field name in backquotes when it isn't a plain identifier.
+fn quote_identifier (name: &str) -> String {
+    let is_plain = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name.to_lowercase().as_str());
+
+    if is_plain {
+        name.to_string()
+    } else {
+        format!("`{}`", name.replace('`', "\\`"))
+    }
+}
+
+/// A distance unit for `within_distance`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distance {
+    Meters(f64),
+    Kilometers(f64),
+    Miles(f64),
+}
+
+impl Distance {
+    fn value (&self) -> f64 {
+        match self {
+            Distance::Meters(distance) | Distance::Kilometers(distance) | Distance::Miles(distance) => *distance,
+        }
+    }
+}
+
+impl Display for Distance {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Distance::Meters(distance) => write!(f, "{}m", distance),
+            Distance::Kilometers(distance) => write!(f, "{}km", distance),
+            Distance::Miles(distance) => write!(f, "{}mi", distance),
+        }
+    }
+}
+
+/*  -------------------------------------------------------------
+    Conditions
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A boolean expression, to use as where clause
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Expression(String),
+
+    /// Matches when all the conditions match, so always when there isn't any
+    And(Vec<Condition>),
+
+    /// Matches when one of the conditions match, so never when there isn't any
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+/// Full-text search in all the fields
+pub fn search (text: &str) -> Condition {
+    Condition::Expression(format!("search({})", quote_string(text)))
+}
+
+/// Search for records where a field starts with the text, e.g. for autocompletion
+pub fn suggest (text: &str) -> Condition {
+    Condition::Expression(format!("suggest({})", quote_string(text)))
+}
+
+/// Negates a condition.
+pub fn not (condition: Condition) -> Condition {
+    Condition::Not(Box::new(condition))
+}
+
+impl Condition {
+    pub fn and (self, other: Condition) -> Condition {
+        match self {
+            Condition::And(mut conditions) => {
+                conditions.push(other);
+                Condition::And(conditions)
+            }
+            _ => Condition::And(vec![self, other]),
+        }
+    }
+
+    pub fn or (self, other: Condition) -> Condition {
+        match self {
+            Condition::Or(mut conditions) => {
+                conditions.push(other);
+                Condition::Or(conditions)
+            }
+            _ => Condition::Or(vec![self, other]),
+        }
+    }
+
+    fn is_compound (&self) -> bool {
+        matches!(self, Condition::And(conditions) | Condition::Or(conditions) if conditions.len() > 1)
+    }
+
+    fn fmt_operand (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_compound() {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+
+    fn fmt_list (f: &mut Formatter<'_>, conditions: &[Condition], operator: &str) -> std::fmt::Result {
+        for (i, condition) in conditions.iter().enumerate() {
+            if i > 0 {
+                write!(f, " {} ", operator)?;
+            }
+            condition.fmt_operand(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Condition {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Expression(expression) => write!(f, "{}", expression),
+            Condition::And(conditions) if conditions.is_empty() => write!(f, "true"),
+            Condition::Or(conditions) if conditions.is_empty() => write!(f, "false"),
+            Condition::And(conditions) => Condition::fmt_list(f, conditions, "AND"),
+            Condition::Or(conditions) => Condition::fmt_list(f, conditions, "OR"),
+            Condition::Not(condition) => {
+                write!(f, "NOT ")?;
+                match condition.as_ref() {
+                    Condition::Expression(_) => condition.fmt_operand(f),
+                    _ => write!(f, "({})", condition),
+                }
+            }
+        }
+    }
+}
+
+/*  -------------------------------------------------------------
+    Select clause
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A list of fields or expressions to return, to use as select clause
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Select {
+    expressions: Vec<String>,
+}
+
+impl Select {
+    pub fn new () -> Self {
+        Self::default()
+    }
+
+    pub fn field (mut self, field: Field) -> Self {
+        self.expressions.push(field.to_string());
+        self
+    }
+
+    /// Adds a field or an expression, with an alias as name in the results.
+    pub fn field_as (mut self, field: Field, alias: &str) -> Self {
+        self.expressions.push(format!("{} as {}", field, quote_identifier(alias)));
+        self
+    }
+}
+
+impl Display for Select {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expressions.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_string () {
+        assert_eq!(r#""Rue de l'Église""#, quote_string("Rue de l'Église"));
+        assert_eq!(r#""Le \"Grand\" garage \\ Nord""#, quote_string(r#"Le "Grand" garage \ Nord"#));
+    }
+
+    #[test]
+    fn test_quote_identifier () {
+        assert_eq!("code_postal", quote_identifier("code_postal"));
+        assert_eq!("`order`", quote_identifier("order"));
+        assert_eq!("`prix-visite`", quote_identifier("prix-visite"));
+    }
+
+    #[test]
+    fn test_comparisons () {
+        assert_eq!("prix_visite >= 80", field("prix_visite").ge(80).unwrap().to_string());
+        assert_eq!("latitude is not null", field("latitude").is_not_null().to_string());
+        assert_eq!(
+            "year(date_application_visite) = 2020",
+            field("date_application_visite").year().eq(2020).unwrap().to_string()
+        );
+        assert_eq!(
+            "date_application_visite >= date'2020-09-14'",
+            field("date_application_visite").ge(NaiveDate::from_ymd_opt(2020, 9, 14).unwrap()).unwrap().to_string()
+        );
+        assert_eq!(
+            "date_application_visite > now(days=-7)",
+            field("date_application_visite").gt(now_shifted_by_days(-7)).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_in () {
+        assert_eq!(
+            r#"cat_energie_libelle IN ("Diesel", "Hybride")"#,
+            field("cat_energie_libelle").in_list(["Diesel", "Hybride"]).unwrap().to_string()
+        );
+        assert_eq!("prix_visite IN (65.5, 80)", field("prix_visite").in_list([65.5, 80.0]).unwrap().to_string());
+        assert_eq!("prix_visite in [50..80]", field("prix_visite").in_range(50, 80).unwrap().to_string());
+    }
+
+    #[test]
+    fn test_in_list_rejects_invalid_values () {
+        assert!(matches!(
+            field("cat_energie_libelle").in_list(Vec::<&str>::new()),
+            Err(Error::InvalidExpression(_))
+        ));
+        assert!(matches!(field("prix_visite").in_list([65.5, f64::NAN]), Err(Error::InvalidExpression(_))));
+        assert!(matches!(field("prix_visite").in_list([f64::INFINITY]), Err(Error::InvalidExpression(_))));
+    }
+
+    #[test]
+    fn test_non_finite_numbers_are_rejected () {
+        assert!(matches!(field("prix_visite").eq(f64::NAN), Err(Error::InvalidExpression(_))));
+        assert!(matches!(field("prix_visite").lt(f64::INFINITY), Err(Error::InvalidExpression(_))));
+        assert!(matches!(field("prix_visite").ge(f64::NEG_INFINITY), Err(Error::InvalidExpression(_))));
+        assert!(matches!(field("prix_visite").in_range(50.0, f64::NAN), Err(Error::InvalidExpression(_))));
+        assert!(matches!(
+            field("latitude").within_distance(f64::NAN, 46.85617, Distance::Kilometers(10.0)),
+            Err(Error::InvalidExpression(_))
+        ));
+        assert!(matches!(
+            field("latitude").within_distance(-1.867611, 46.85617, Distance::Meters(f64::INFINITY)),
+            Err(Error::InvalidExpression(_))
+        ));
+        assert!(matches!(
+            field("latitude").in_bbox(47.0, -2.5, f64::NAN, -0.5),
+            Err(Error::InvalidExpression(_))
+        ));
+    }
+
+    #[test]
+    fn test_geo_filters () {
+        assert_eq!(
+            "within_distance(latitude, geom'POINT(-1.867611 46.85617)', 10km)",
+            field("latitude").within_distance(-1.867611, 46.85617, Distance::Kilometers(10.0)).unwrap().to_string()
+        );
+        assert_eq!(
+            "in_bbox(latitude, 47, -2.5, 46.2, -0.5)",
+            field("latitude").in_bbox(47.0, -2.5, 46.2, -0.5).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_combinators () {
+        let condition = field("code_postal").eq("85300").unwrap()
+            .or(field("code_postal").eq("85700").unwrap())
+            .and(not(search("diesel")))
+            .and(suggest("auto"));
+
+        assert_eq!(
+            r#"(code_postal = "85300" OR code_postal = "85700") AND NOT search("diesel") AND suggest("auto")"#,
+            condition.to_string()
+        );
+
+        assert_eq!(
+            r#"NOT (a = 1 OR b = 2)"#,
+            not(field("a").eq(1).unwrap().or(field("b").eq(2).unwrap())).to_string()
+        );
+    }
+
+    #[test]
+    fn test_empty_combinators () {
+        assert_eq!("true", Condition::And(vec![]).to_string());
+        assert_eq!("false", Condition::Or(vec![]).to_string());
+        assert_eq!("NOT (false)", not(Condition::Or(vec![])).to_string());
+
+        let query = crate::query::RecordsQuery::new()
+            .where_clause(Condition::Or(vec![]))
+            .search("diesel");
+        assert_eq!(Some(r#"(false) AND search("diesel")"#), query.where_clause.as_deref());
+    }
+
+    #[test]
+    fn test_select () {
+        let select = Select::new()
+            .field(field("cct_code_dept"))
+            .field_as(field("prix_visite").avg(), "prix_moyen")
+            .field_as(count_all(), "count");
+
+        assert_eq!("cct_code_dept, avg(prix_visite) as prix_moyen, count(*) as count", select.to_string());
+    }
+}
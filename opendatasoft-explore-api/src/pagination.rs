@@ -0,0 +1,407 @@
+//! Walk through all the records of a dataset, or all the datasets of the catalog.
+//!
+//! The records endpoint can't go further than 10,000 records (offset + limit).
+//! When a dataset is larger, or when the offset is beyond this limit, all the records
+//! are read from the jsonl export instead, one line at a time. As the records endpoint
+//! and the export don't guarantee the same order, the first page is then dropped,
+//! so records aren't duplicated or lost.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use futures_util::{stream, Stream};
+use serde_json::Value as JsonValue;
+
+use crate::ApiHttpResponse;
+use crate::error::Error;
+use crate::export::ExportFormat;
+use crate::query::RecordsQuery;
+use crate::requests::ExploreApiEndPoint;
+use crate::schema::*;
+
+/// The maximum value of offset + limit for the records endpoint
+static MAX_RECORDS_OFFSET: usize = 10_000;
+
+/// The maximum number of records the records endpoint returns by page
+static MAX_RECORDS_PAGE_SIZE: usize = 100;
+
+/*  -------------------------------------------------------------
+    Records stream
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+pub(crate) fn records_stream<'a> (
+    endpoint: &'a ExploreApiEndPoint,
+    dataset_id: &str,
+    query: &RecordsQuery,
+) -> impl Stream<Item = Result<Record, Error>> + 'a {
+    let state = RecordsStreamState::new(endpoint, dataset_id, query);
+
+    stream::unfold(state, |mut state| async move {
+        let record = state.next_record().await?;
+
+        Some((record, state))
+    })
+}
+
+pub(crate) fn export_records_stream<'a> (
+    endpoint: &'a ExploreApiEndPoint,
+    dataset_id: &str,
+    query: &RecordsQuery,
+) -> impl Stream<Item = Result<Record, Error>> + 'a {
+    let mut state = RecordsStreamState::new(endpoint, dataset_id, query);
+    state.export_only = true;
+
+    stream::unfold(state, |mut state| async move {
+        let record = state.next_record().await?;
+
+        Some((record, state))
+    })
+}
+
+struct RecordsStreamState<'a> {
+    endpoint: &'a ExploreApiEndPoint,
+    dataset_id: String,
+    query: RecordsQuery,
+
+    page_size: usize,
+    next_offset: usize,
+    total_count: Option<usize>,
+    records: VecDeque<Record>,
+
+    export: Option<JsonLinesReader>,
+
+    /// Reads the jsonl export from the start, without querying the records endpoint
+    export_only: bool,
+
+    done: bool,
+}
+
+impl<'a> RecordsStreamState<'a> {
+    fn new (endpoint: &'a ExploreApiEndPoint, dataset_id: &str, query: &RecordsQuery) -> Self {
+        let offset = query.offset.unwrap_or(0);
+
+        Self {
+            endpoint,
+            dataset_id: dataset_id.to_string(),
+            query: query.clone(),
+
+            page_size: query.limit.unwrap_or(MAX_RECORDS_PAGE_SIZE).clamp(1, MAX_RECORDS_PAGE_SIZE),
+            next_offset: offset,
+            total_count: None,
+            records: VecDeque::new(),
+
+            export: None,
+            export_only: offset >= MAX_RECORDS_OFFSET,
+            done: false,
+        }
+    }
+
+    async fn next_record (&mut self) -> Option<Result<Record, Error>> {
+        loop {
+            if self.done {
+                return None;
+            }
+
+            if let Some(record) = self.records.pop_front() {
+                return Some(Ok(record));
+            }
+
+            if let Some(export) = &mut self.export {
+                let record = export.next_record().await;
+                if !matches!(record, Some(Ok(_))) {
+                    self.done = true;
+                }
+
+                return record;
+            }
+
+            if self.export_only {
+                if let Err(error) = self.start_export().await {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+
+                continue;
+            }
+
+            if let Some(total_count) = self.total_count {
+                if self.next_offset >= total_count {
+                    return None;
+                }
+            }
+
+            if let Err(error) = self.fetch_next_page().await {
+                self.done = true;
+                return Some(Err(error));
+            }
+        }
+    }
+
+    async fn fetch_next_page (&mut self) -> Result<(), Error> {
+        // The page can't end after the maximum offset.
+        let page_size = self.page_size.min(MAX_RECORDS_OFFSET.saturating_sub(self.next_offset));
+        let query = self.query.clone()
+            .limit(page_size)
+            .offset(self.next_offset);
+
+        let results = self.endpoint
+            .get_dataset_records(&self.dataset_id, &query)
+            .await?;
+
+        if self.total_count.is_none() && results.total_count > MAX_RECORDS_OFFSET {
+            // Some records can't be reached through the records endpoint.
+            // The export doesn't guarantee the same order, so this first page
+            // is dropped, and every record is read from the export.
+            self.export_only = true;
+            return Ok(());
+        }
+
+        if results.records.is_empty() {
+            self.done = true;
+            return Ok(());
+        }
+
+        self.total_count = Some(results.total_count);
+        self.next_offset += results.records.len();
+        self.records.extend(
+            results.records
+                .into_iter()
+                .filter_map(|record| match record {
+                    ResultsRecord::Record(record) => Some(record),
+                    ResultsRecord::Aggregation(_) => None,
+                })
+        );
+
+        Ok(())
+    }
+
+    async fn start_export (&mut self) -> Result<(), Error> {
+        let mut query = self.query.clone();
+        query.limit = None;
+        query.offset = None;
+
+        let response = self.endpoint
+            .export_dataset(&self.dataset_id, ExportFormat::Jsonl, &query)
+            .await?;
+
+        let skip = self.query.offset.unwrap_or(0);
+        self.export = Some(JsonLinesReader::new(response, skip));
+
+        Ok(())
+    }
+}
+
+/*  -------------------------------------------------------------
+    Datasets stream
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+pub(crate) fn datasets_stream<'a> (
+    endpoint: &'a ExploreApiEndPoint,
+    query: &RecordsQuery,
+) -> impl Stream<Item = Result<Dataset, Error>> + 'a {
+    let state = DatasetsStreamState::new(endpoint, query);
+
+    stream::unfold(state, |mut state| async move {
+        let dataset = state.next_dataset().await?;
+
+        Some((dataset, state))
+    })
+}
+
+struct DatasetsStreamState<'a> {
+    endpoint: &'a ExploreApiEndPoint,
+    query: RecordsQuery,
+
+    page_size: usize,
+    next_offset: usize,
+    total_count: Option<usize>,
+    datasets: VecDeque<Dataset>,
+
+    done: bool,
+}
+
+impl<'a> DatasetsStreamState<'a> {
+    fn new (endpoint: &'a ExploreApiEndPoint, query: &RecordsQuery) -> Self {
+        Self {
+            endpoint,
+            query: query.clone(),
+
+            page_size: query.limit.unwrap_or(MAX_RECORDS_PAGE_SIZE).clamp(1, MAX_RECORDS_PAGE_SIZE),
+            next_offset: query.offset.unwrap_or(0),
+            total_count: None,
+            datasets: VecDeque::new(),
+
+            done: false,
+        }
+    }
+
+    async fn next_dataset (&mut self) -> Option<Result<Dataset, Error>> {
+        loop {
+            if let Some(dataset) = self.datasets.pop_front() {
+                return Some(Ok(dataset));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            if let Some(total_count) = self.total_count {
+                if self.next_offset >= total_count.min(MAX_RECORDS_OFFSET) {
+                    return None;
+                }
+            }
+
+            if let Err(error) = self.fetch_next_page().await {
+                self.done = true;
+                return Some(Err(error));
+            }
+        }
+    }
+
+    async fn fetch_next_page (&mut self) -> Result<(), Error> {
+        // The catalog endpoint has the same offset + limit constraint as the records one.
+        let page_size = self.page_size.min(MAX_RECORDS_OFFSET.saturating_sub(self.next_offset));
+        if page_size == 0 {
+            self.done = true;
+            return Ok(());
+        }
+
+        let query = self.query.clone()
+            .limit(page_size)
+            .offset(self.next_offset);
+
+        let collection = self.endpoint
+            .get_datasets(&query)
+            .await?;
+
+        if collection.datasets.is_empty() {
+            self.done = true;
+            return Ok(());
+        }
+
+        self.total_count = Some(collection.total_count);
+        self.next_offset += collection.datasets.len();
+        self.datasets.extend(collection.datasets);
+
+        Ok(())
+    }
+}
+
+/*  -------------------------------------------------------------
+    jsonl export reader
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+struct JsonLinesReader {
+    response: ApiHttpResponse,
+    buffer: Vec<u8>,
+
+    /// The number of lines still to skip, to honour the query offset
+    skip: usize,
+}
+
+impl JsonLinesReader {
+    fn new (response: ApiHttpResponse, skip: usize) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+            skip,
+        }
+    }
+
+    async fn next_record (&mut self) -> Option<Result<Record, Error>> {
+        while let Some(line) = self.next_line().await {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+
+            return Some(parse_export_line(&line));
+        }
+
+        None
+    }
+
+    async fn next_line (&mut self) -> Option<Result<Vec<u8>, Error>> {
+        loop {
+            if let Some(position) = self.buffer.iter().position(|&byte| byte == b'\n') {
+                return Some(Ok(self.buffer.drain(..=position).collect()));
+            }
+
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                Ok(None) if self.buffer.is_empty() => return None,
+                Ok(None) => return Some(Ok(self.buffer.split_off(0))),
+                Err(error) => return Some(Err(Error::from_reqwest(error))),
+            }
+        }
+    }
+}
+
+/// Parses a line of a jsonl export as a record.
+///
+/// Exports can give records as the fields object only,
+/// or with the record metadata (recordid, record_timestamp, fields).
+fn parse_export_line (line: &[u8]) -> Result<Record, Error> {
+    let mut value: JsonValue = serde_json::from_slice(line)
+        .map_err(Error::Json)?;
+
+    if value.get("recordid").is_none() || !value.get("fields").is_some_and(JsonValue::is_object) {
+        return Ok(build_record(String::new(), DateTime::default(), line.len(), value));
+    }
+
+    let id = value["recordid"].as_str().unwrap_or_default().to_string();
+    let timestamp = value.get("record_timestamp")
+        .and_then(JsonValue::as_str)
+        .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .unwrap_or_default();
+    let fields = value["fields"].take();
+
+    Ok(build_record(id, timestamp, line.len(), fields))
+}
+
+fn build_record (id: String, timestamp: DateTime<Utc>, size: usize, fields: JsonValue) -> Record {
+    Record {
+        record: RecordProperties {
+            id,
+            timestamp,
+            size,
+            fields,
+        },
+        links: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_export_line () {
+        let record = parse_export_line(br#"{"code_postal": "85300", "prix_visite": 94}"#).unwrap();
+
+        assert_eq!("", record.record.id);
+        assert_eq!(Some(&json!("85300")), record.record.fields.get("code_postal"));
+    }
+
+    #[test]
+    fn test_parse_export_line_with_record_metadata () {
+        let line = br#"{"recordid": "b839362b", "record_timestamp": "2021-01-07T23:00:00Z", "fields": {"code_postal": "85300"}}"#;
+        let record = parse_export_line(line).unwrap();
+
+        assert_eq!("b839362b", record.record.id);
+        assert_eq!("2021-01-07T23:00:00+00:00", record.record.timestamp.to_rfc3339());
+        assert_eq!(json!({"code_postal": "85300"}), record.record.fields);
+    }
+}
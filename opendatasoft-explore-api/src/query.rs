@@ -0,0 +1,266 @@
+//! Optional parameters for the Opendatasoft Explore API v2 requests.
+//!
+//! The `RecordsQuery` builder allows to filter, sort, paginate or aggregate results
+//! of the catalog and records endpoints.
+//!
+//! ```
+//! use opendatasoft_explore_api::query::RecordsQuery;
+//!
+//! let query = RecordsQuery::new()
+//!     .select("code_postal, cct_denomination")
+//!     .where_clause("cat_vehicule_libelle = 'Voiture Particulière'")
+//!     .order_by("code_postal")
+//!     .limit(20)
+//!     .refine("cct_code_dept", "Vendée");
+//! ```
+//!
+//! To search the catalog, the [`CatalogFacet`] enum names the facets
+//! datasets can be refined or excluded on:
+//!
+//! ```
+//! use opendatasoft_explore_api::query::{CatalogFacet, RecordsQuery};
+//!
+//! let query = RecordsQuery::new()
+//!     .search("adresses")
+//!     .refine(CatalogFacet::Publisher, "DGFiP")
+//!     .exclude(CatalogFacet::Keyword, "budget");
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::odsql;
+
+/// Optional parameters for a request to the Explore API
+///
+/// Parameters left to None or empty aren't sent, so the API defaults apply.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RecordsQuery {
+    /// Fields to return, as an ODSQL select clause
+    pub select: Option<String>,
+
+    /// Filter, as an ODSQL where clause
+    pub where_clause: Option<String>,
+
+    /// Fields to group by, as an ODSQL group by clause
+    pub group_by: Option<String>,
+
+    /// Sort order, as an ODSQL order by clause
+    pub order_by: Option<String>,
+
+    /// Number of items to return
+    pub limit: Option<usize>,
+
+    /// Index of the first item to return
+    pub offset: Option<usize>,
+
+    /// Facets refinements, as "facet:value" expressions
+    pub refine: Vec<String>,
+
+    /// Facets exclusions, as "facet:value" expressions
+    pub exclude: Vec<String>,
+
+    /// Language used to format strings, for example "fr"
+    pub lang: Option<String>,
+
+    /// Timezone used to interpret and format dates, for example "Europe/Paris"
+    pub timezone: Option<String>,
+}
+
+impl RecordsQuery {
+    pub fn new () -> Self {
+        Self::default()
+    }
+
+    /// Sets the `select` parameter, from a string or an `odsql::Select` builder.
+    pub fn select<S> (mut self, select: S) -> Self where S: ToString {
+        self.select = Some(select.to_string());
+        self
+    }
+
+    /// Sets the `where` parameter, from a string or an `odsql::Condition` builder.
+    pub fn where_clause<S> (mut self, where_clause: S) -> Self where S: ToString {
+        self.where_clause = Some(where_clause.to_string());
+        self
+    }
+
+    pub fn group_by (mut self, group_by: &str) -> Self {
+        self.group_by = Some(group_by.to_string());
+        self
+    }
+
+    pub fn order_by (mut self, order_by: &str) -> Self {
+        self.order_by = Some(order_by.to_string());
+        self
+    }
+
+    pub fn limit (mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset (mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Restricts results to items matching the text, through a full-text search.
+    ///
+    /// The search is combined with the where clause, if any.
+    pub fn search (mut self, text: &str) -> Self {
+        let condition = odsql::search(text).to_string();
+
+        self.where_clause = Some(match self.where_clause {
+            Some(where_clause) => format!("({}) AND {}", where_clause, condition),
+            None => condition,
+        });
+        self
+    }
+
+    /// Restricts results to items where the facet has the specified value.
+    ///
+    /// Can be called several times to refine on several facets.
+    pub fn refine<F> (mut self, facet: F, value: &str) -> Self where F: ToString {
+        self.refine.push(format!("{}:{}", facet.to_string(), value));
+        self
+    }
+
+    /// Excludes items where the facet has the specified value.
+    ///
+    /// Can be called several times to exclude several values.
+    pub fn exclude<F> (mut self, facet: F, value: &str) -> Self where F: ToString {
+        self.exclude.push(format!("{}:{}", facet.to_string(), value));
+        self
+    }
+
+    pub fn lang (mut self, lang: &str) -> Self {
+        self.lang = Some(lang.to_string());
+        self
+    }
+
+    pub fn timezone (mut self, timezone: &str) -> Self {
+        self.timezone = Some(timezone.to_string());
+        self
+    }
+
+    /// Gets the parameters to append to the request URL
+    pub fn to_query_pairs (&self) -> Vec<(&'static str, String)> {
+        let mut pairs = Vec::new();
+
+        push_optional_pair(&mut pairs, "select", &self.select);
+        push_optional_pair(&mut pairs, "where", &self.where_clause);
+        push_optional_pair(&mut pairs, "group_by", &self.group_by);
+        push_optional_pair(&mut pairs, "order_by", &self.order_by);
+        push_optional_pair(&mut pairs, "limit", &self.limit);
+        push_optional_pair(&mut pairs, "offset", &self.offset);
+
+        for refine in &self.refine {
+            pairs.push(("refine", refine.clone()));
+        }
+        for exclude in &self.exclude {
+            pairs.push(("exclude", exclude.clone()));
+        }
+
+        push_optional_pair(&mut pairs, "lang", &self.lang);
+        push_optional_pair(&mut pairs, "timezone", &self.timezone);
+
+        pairs
+    }
+}
+
+/*  -------------------------------------------------------------
+    Catalog facets
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// The facets of the catalog, to refine or exclude datasets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CatalogFacet {
+    /// The features of the dataset, like "geo", "analyze" or "timeserie"
+    Features,
+
+    /// The year of last modification
+    Modified,
+
+    Publisher,
+    Keyword,
+    Theme,
+    Language,
+}
+
+impl CatalogFacet {
+    pub fn name (&self) -> &'static str {
+        match self {
+            CatalogFacet::Features => "features",
+            CatalogFacet::Modified => "modified",
+            CatalogFacet::Publisher => "publisher",
+            CatalogFacet::Keyword => "keyword",
+            CatalogFacet::Theme => "theme",
+            CatalogFacet::Language => "language",
+        }
+    }
+}
+
+impl Display for CatalogFacet {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/*  -------------------------------------------------------------
+    Helper methods
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+fn push_optional_pair<T> (pairs: &mut Vec<(&'static str, String)>, key: &'static str, value: &Option<T>)
+where T: ToString
+{
+    if let Some(value) = value {
+        pairs.push((key, value.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_query_pairs () {
+        let query = RecordsQuery::new()
+            .where_clause("code_postal = '85300'")
+            .limit(10)
+            .refine("cct_code_dept", "Vendée")
+            .refine("cat_energie_libelle", "Diesel")
+            .timezone("Europe/Paris");
+
+        let expected = vec![
+            ("where", "code_postal = '85300'".to_string()),
+            ("limit", "10".to_string()),
+            ("refine", "cct_code_dept:Vendée".to_string()),
+            ("refine", "cat_energie_libelle:Diesel".to_string()),
+            ("timezone", "Europe/Paris".to_string()),
+        ];
+
+        assert_eq!(expected, query.to_query_pairs());
+    }
+
+    #[test]
+    fn test_search () {
+        let query = RecordsQuery::new()
+            .search("voie");
+        assert_eq!(Some(r#"search("voie")"#), query.where_clause.as_deref());
+
+        let query = RecordsQuery::new()
+            .where_clause("modified >= date'2022'")
+            .search("lieux-dits")
+            .refine(CatalogFacet::Theme, "IMPOTS");
+
+        let expected = vec![
+            ("where", r#"(modified >= date'2022') AND search("lieux-dits")"#.to_string()),
+            ("refine", "theme:IMPOTS".to_string()),
+        ];
+        assert_eq!(expected, query.to_query_pairs());
+    }
+
+    #[test]
+    fn test_to_query_pairs_when_empty () {
+        assert!(RecordsQuery::new().to_query_pairs().is_empty());
+    }
+}
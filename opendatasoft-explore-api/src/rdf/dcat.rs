@@ -0,0 +1,192 @@
+//! Read the datasets and distributions described with DCAT in a catalog graph.
+
+use oxrdf::vocab::rdf;
+use oxrdf::{Graph, NamedNodeRef, SubjectRef, TermRef};
+
+/*  -------------------------------------------------------------
+    Vocabulary
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+pub mod vocab {
+    //! The DCAT and Dublin Core terms used by the catalog exports.
+
+    use oxrdf::NamedNodeRef;
+
+    pub const DCAT_DATASET: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#Dataset");
+    pub const DCAT_DISTRIBUTION: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#distribution");
+    pub const DCAT_ACCESS_URL: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#accessURL");
+    pub const DCAT_DOWNLOAD_URL: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#downloadURL");
+    pub const DCAT_MEDIA_TYPE: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#mediaType");
+    pub const DCAT_KEYWORD: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#keyword");
+
+    pub const DCT_IDENTIFIER: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://purl.org/dc/terms/identifier");
+    pub const DCT_TITLE: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://purl.org/dc/terms/title");
+    pub const DCT_DESCRIPTION: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://purl.org/dc/terms/description");
+    pub const DCT_LICENSE: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://purl.org/dc/terms/license");
+    pub const DCT_FORMAT: NamedNodeRef<'_> = NamedNodeRef::new_unchecked("http://purl.org/dc/terms/format");
+}
+
+use vocab::*;
+
+/*  -------------------------------------------------------------
+    Datasets and distributions
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A dcat:Dataset resource of the catalog
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct CatalogDataset {
+    /// The IRI of the resource, or the blank node identifier
+    pub iri: String,
+
+    pub identifier: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+
+    /// The license IRI, from the dataset or else from its distributions
+    pub license: Option<String>,
+
+    pub keywords: Vec<String>,
+    pub distributions: Vec<Distribution>,
+}
+
+/// A dcat:Distribution resource, for example an export of the dataset
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct Distribution {
+    pub iri: String,
+    pub access_url: Option<String>,
+    pub download_url: Option<String>,
+    pub media_type: Option<String>,
+    pub format: Option<String>,
+    pub license: Option<String>,
+}
+
+/// Lists the dcat:Dataset resources of a graph, sorted by IRI.
+pub fn get_catalog_datasets (graph: &Graph) -> Vec<CatalogDataset> {
+    let mut datasets: Vec<_> = graph
+        .subjects_for_predicate_object(rdf::TYPE, DCAT_DATASET)
+        .map(|subject| read_dataset(graph, subject))
+        .collect();
+
+    datasets.sort_by(|a, b| a.iri.cmp(&b.iri));
+    datasets
+}
+
+fn read_dataset (graph: &Graph, subject: SubjectRef) -> CatalogDataset {
+    let mut distributions: Vec<_> = graph
+        .objects_for_subject_predicate(subject, DCAT_DISTRIBUTION)
+        .filter_map(|object| match object {
+            TermRef::NamedNode(node) => Some(read_distribution(graph, node.into())),
+            TermRef::BlankNode(node) => Some(read_distribution(graph, node.into())),
+            _ => None,
+        })
+        .collect();
+    distributions.sort_by(|a, b| a.iri.cmp(&b.iri));
+
+    let license = get_value(graph, subject, DCT_LICENSE)
+        .or_else(|| distributions.iter().find_map(|distribution| distribution.license.clone()));
+
+    let mut keywords: Vec<_> = graph
+        .objects_for_subject_predicate(subject, DCAT_KEYWORD)
+        .map(get_term_value)
+        .collect();
+    keywords.sort();
+
+    CatalogDataset {
+        iri: get_subject_value(subject),
+        identifier: get_value(graph, subject, DCT_IDENTIFIER),
+        title: get_value(graph, subject, DCT_TITLE),
+        description: get_value(graph, subject, DCT_DESCRIPTION),
+        license,
+        keywords,
+        distributions,
+    }
+}
+
+fn read_distribution (graph: &Graph, subject: SubjectRef) -> Distribution {
+    Distribution {
+        iri: get_subject_value(subject),
+        access_url: get_value(graph, subject, DCAT_ACCESS_URL),
+        download_url: get_value(graph, subject, DCAT_DOWNLOAD_URL),
+        media_type: get_value(graph, subject, DCAT_MEDIA_TYPE),
+        format: get_value(graph, subject, DCT_FORMAT),
+        license: get_value(graph, subject, DCT_LICENSE),
+    }
+}
+
+/*  -------------------------------------------------------------
+    Helper methods
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+fn get_value (graph: &Graph, subject: SubjectRef, predicate: NamedNodeRef) -> Option<String> {
+    graph
+        .object_for_subject_predicate(subject, predicate)
+        .map(get_term_value)
+}
+
+/// Gets the IRI of a resource, the value of a literal, or the identifier of a blank node.
+fn get_term_value (term: TermRef) -> String {
+    match term {
+        TermRef::NamedNode(node) => node.as_str().to_string(),
+        TermRef::BlankNode(node) => node.as_str().to_string(),
+        TermRef::Literal(literal) => literal.value().to_string(),
+        #[allow(unreachable_patterns)]
+        _ => term.to_string(),
+    }
+}
+
+fn get_subject_value (subject: SubjectRef) -> String {
+    match subject {
+        SubjectRef::NamedNode(node) => node.as_str().to_string(),
+        SubjectRef::BlankNode(node) => node.as_str().to_string(),
+        #[allow(unreachable_patterns)]
+        _ => subject.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rdf::{parse_graph, RdfSyntax};
+
+    use super::*;
+
+    const CATALOG: &str = r#"
+@prefix dcat: <http://www.w3.org/ns/dcat#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+
+<https://example.com/datasets/controle_techn> a dcat:Dataset ;
+    dct:identifier "controle_techn" ;
+    dct:title "Contrôle technique"@fr ;
+    dcat:keyword "voiture", "automobile" ;
+    dcat:distribution <https://example.com/datasets/controle_techn/json>, <https://example.com/datasets/controle_techn/csv> .
+
+<https://example.com/datasets/controle_techn/csv> a dcat:Distribution ;
+    dcat:accessURL <https://example.com/api/controle_techn/exports/csv> ;
+    dcat:mediaType "text/csv" ;
+    dct:format "CSV" ;
+    dct:license <https://www.etalab.gouv.fr/licence-ouverte-open-licence> .
+
+<https://example.com/datasets/controle_techn/json> a dcat:Distribution ;
+    dct:format "JSON" .
+"#;
+
+    #[test]
+    fn test_get_catalog_datasets () {
+        let graph = parse_graph(RdfSyntax::Turtle, CATALOG, None).unwrap();
+        let datasets = get_catalog_datasets(&graph);
+
+        assert_eq!(1, datasets.len());
+
+        let dataset = &datasets[0];
+        assert_eq!(Some("controle_techn"), dataset.identifier.as_deref());
+        assert_eq!(Some("Contrôle technique"), dataset.title.as_deref());
+        assert_eq!(vec!["automobile", "voiture"], dataset.keywords);
+        assert_eq!(Some("https://www.etalab.gouv.fr/licence-ouverte-open-licence"), dataset.license.as_deref());
+
+        assert_eq!(2, dataset.distributions.len());
+        let csv = &dataset.distributions[0];
+        assert_eq!("https://example.com/datasets/controle_techn/csv", csv.iri);
+        assert_eq!(Some("text/csv"), csv.media_type.as_deref());
+        assert_eq!(Some("https://example.com/api/controle_techn/exports/csv"), csv.access_url.as_deref());
+        assert_eq!(None, dataset.distributions[1].license);
+    }
+}
@@ -0,0 +1,65 @@
+//! Parse the RDF catalog exports into oxrdf graphs.
+//!
+//! The catalog can be exported as RDF/XML (rdf and dcat formats)
+//! or as Turtle (ttl and n3 formats). Both syntaxes are parsed into
+//! an [`oxrdf::Graph`], which can then be queried directly, or with the
+//! helpers of the dcat module to list the datasets and their distributions.
+//!
+//! ```no_run
+//! use opendatasoft_explore_api::export::ExportFormat;
+//! use opendatasoft_explore_api::rdf::dcat::get_catalog_datasets;
+//! use opendatasoft_explore_api::requests::ExploreApiEndPoint;
+//!
+//! async fn print_catalog (endpoint: &ExploreApiEndPoint) {
+//!     let graph = endpoint.export_datasets_catalog_graph(ExportFormat::Dcat).await
+//!         .expect("Can't export catalog");
+//!
+//!     for dataset in get_catalog_datasets(&graph) {
+//!         println!("{:?}: {} distributions", dataset.title, dataset.distributions.len());
+//!     }
+//! }
+//! ```
+
+use ds_rdf::{resolve_iri, turtle};
+use oxrdf::Graph;
+
+use crate::export::ExportFormat;
+
+pub use ds_rdf::RdfParseError;
+
+pub mod dcat;
+mod xml;
+
+/*  -------------------------------------------------------------
+    RDF syntaxes
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// The RDF serializations used by the catalog exports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RdfSyntax {
+    RdfXml,
+
+    /// Turtle, also used to parse the n3 export, as it only uses the Turtle subset of N3
+    Turtle,
+}
+
+impl RdfSyntax {
+    /// Gets the syntax of an export format, if it's an RDF one.
+    pub fn from_export_format (format: ExportFormat) -> Option<Self> {
+        match format {
+            ExportFormat::RdfXml | ExportFormat::Dcat => Some(RdfSyntax::RdfXml),
+            ExportFormat::Turtle | ExportFormat::N3 => Some(RdfSyntax::Turtle),
+            _ => None,
+        }
+    }
+}
+
+/// Parses an RDF document into a graph.
+///
+/// * `base_iri` - The IRI to resolve relative IRIs against, if the document contains some
+pub fn parse_graph (syntax: RdfSyntax, document: &str, base_iri: Option<&str>) -> Result<Graph, RdfParseError> {
+    match syntax {
+        RdfSyntax::RdfXml => xml::parse(document, base_iri),
+        RdfSyntax::Turtle => turtle::parse(document, base_iri),
+    }
+}
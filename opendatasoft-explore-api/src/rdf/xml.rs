@@ -0,0 +1,713 @@
+//! RDF/XML parser.
+//!
+//! Supports the striped syntax used by the catalog exports: node elements
+//! with rdf:about, rdf:ID or rdf:nodeID, typed node elements, property
+//! attributes, property elements with rdf:resource, rdf:nodeID, rdf:datatype,
+//! nested node elements and rdf:parseType="Resource", reification with rdf:ID
+//! on property elements, and xml:lang and xml:base.
+//!
+//! The rdf:parseType="Literal" and rdf:parseType="Collection" constructs
+//! are rejected as unsupported, as is any construct the grammar forbids,
+//! like text in a node element or a syntax term used as a property.
+
+use std::collections::HashMap;
+
+use oxrdf::vocab::{rdf, xsd};
+use oxrdf::{BlankNode, Graph, Literal, NamedNode, Subject, Term, Triple};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+
+use super::{resolve_iri, RdfParseError};
+
+static RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// The terms of the RDF namespace which are part of the syntax, and can't be used as nodes or properties
+static CORE_SYNTAX_TERMS: [&str; 7] = ["RDF", "ID", "about", "parseType", "resource", "nodeID", "datatype"];
+
+/// The terms removed from the RDF/XML grammar, which can't be used anymore
+static OLD_TERMS: [&str; 3] = ["aboutEach", "aboutEachPrefix", "bagID"];
+
+/*  -------------------------------------------------------------
+    Parser state
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// An open XML element, with the context its children need
+enum Frame {
+    /// The rdf:RDF element, containing node elements
+    Root {
+        language: Option<String>,
+        base_iri: Option<String>,
+    },
+
+    /// A node element, containing property elements
+    Node {
+        subject: Subject,
+        language: Option<String>,
+        base_iri: Option<String>,
+        next_item: usize,
+    },
+
+    /// A property element, containing a literal or a node element
+    Property {
+        subject: Subject,
+        predicate: NamedNode,
+        object: Option<Term>,
+        datatype: Option<NamedNode>,
+        text: String,
+        language: Option<String>,
+        base_iri: Option<String>,
+
+        /// The statement given by rdf:ID, to reify the triple
+        statement: Option<NamedNode>,
+    },
+}
+
+impl Frame {
+    fn language (&self) -> Option<&String> {
+        match self {
+            Frame::Root { language, .. } | Frame::Node { language, .. } | Frame::Property { language, .. } => language.as_ref(),
+        }
+    }
+
+    fn base_iri (&self) -> Option<&String> {
+        match self {
+            Frame::Root { base_iri, .. } | Frame::Node { base_iri, .. } | Frame::Property { base_iri, .. } => base_iri.as_ref(),
+        }
+    }
+}
+
+/// An attribute, with its name resolved as an IRI
+struct ResolvedAttribute {
+    name: String,
+    value: String,
+}
+
+struct RdfXmlParser {
+    graph: Graph,
+    stack: Vec<Frame>,
+    base_iri: Option<String>,
+
+    /// The rdf:nodeID values, mapped to unique blank nodes
+    blank_nodes: HashMap<String, BlankNode>,
+}
+
+/*  -------------------------------------------------------------
+    Parser
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+pub fn parse (document: &str, base_iri: Option<&str>) -> Result<Graph, RdfParseError> {
+    let mut reader = NsReader::from_str(document);
+    reader.expand_empty_elements(true);
+
+    let mut parser = RdfXmlParser {
+        graph: Graph::new(),
+        stack: Vec::new(),
+        base_iri: base_iri.map(str::to_string),
+        blank_nodes: HashMap::new(),
+    };
+
+    loop {
+        let event = match reader.read_resolved_event() {
+            Ok(event) => event,
+            Err(error) => {
+                let line = get_line(document, reader.buffer_position());
+                return Err(RdfParseError::new(format!("Invalid XML: {}", error)).at_line(line));
+            }
+        };
+
+        let result = match event {
+            (ResolveResult::Bound(namespace), Event::Start(element)) => {
+                let name = format!(
+                    "{}{}",
+                    String::from_utf8_lossy(namespace.as_ref()),
+                    String::from_utf8_lossy(element.local_name().as_ref()),
+                );
+                let attributes = resolve_attributes(&reader, &element);
+
+                attributes.and_then(|attributes| parser.start_element(name, attributes))
+            }
+            (_, Event::Start(element)) => Err(RdfParseError::new(format!(
+                "Element {} isn't in a namespace",
+                String::from_utf8_lossy(element.name().as_ref()),
+            ))),
+            (_, Event::Text(text)) => text.unescape()
+                .map_err(|error| RdfParseError::new(format!("Invalid XML text: {}", error)))
+                .and_then(|text| parser.push_text(&text)),
+            (_, Event::CData(text)) => parser.push_text(&String::from_utf8_lossy(&text)),
+            (_, Event::End(_)) => parser.end_element(),
+            (_, Event::Eof) => return Ok(parser.graph),
+            _ => Ok(()),
+        };
+
+        result.map_err(|error| error.at_line(get_line(document, reader.buffer_position())))?;
+    }
+}
+
+fn get_line (document: &str, position: usize) -> usize {
+    document.as_bytes()[..position.min(document.len())]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count() + 1
+}
+
+fn resolve_attributes (reader: &NsReader<&[u8]>, element: &BytesStart) -> Result<Vec<ResolvedAttribute>, RdfParseError> {
+    let mut attributes = Vec::new();
+
+    for attribute in element.attributes() {
+        let attribute = attribute
+            .map_err(|error| RdfParseError::new(format!("Invalid XML attribute: {}", error)))?;
+        let value = attribute.unescape_value()
+            .map_err(|error| RdfParseError::new(format!("Invalid XML attribute: {}", error)))?
+            .to_string();
+
+        let raw_name = attribute.key.as_ref();
+        if raw_name == b"xml:lang" || raw_name == b"xml:base" {
+            attributes.push(ResolvedAttribute {
+                name: String::from_utf8_lossy(raw_name).to_string(),
+                value,
+            });
+            continue;
+        }
+
+        if let (ResolveResult::Bound(namespace), local_name) = reader.resolve_attribute(attribute.key) {
+            attributes.push(ResolvedAttribute {
+                name: format!(
+                    "{}{}",
+                    String::from_utf8_lossy(namespace.as_ref()),
+                    String::from_utf8_lossy(local_name.as_ref()),
+                ),
+                value,
+            });
+        }
+
+        // Namespaces declarations and unqualified attributes are ignored.
+    }
+
+    Ok(attributes)
+}
+
+impl RdfXmlParser {
+    fn start_element (&mut self, name: String, attributes: Vec<ResolvedAttribute>) -> Result<(), RdfParseError> {
+        let parent_language = self.stack.last().and_then(Frame::language).cloned();
+        let parent_base_iri = self.stack.last().and_then(Frame::base_iri).cloned()
+            .or_else(|| self.base_iri.clone());
+
+        let mut language = parent_language;
+        let mut base_iri = parent_base_iri;
+        for attribute in &attributes {
+            match attribute.name.as_str() {
+                "xml:lang" if attribute.value.is_empty() => language = None,
+                "xml:lang" => language = Some(attribute.value.to_lowercase()),
+                "xml:base" => base_iri = Some(resolve_iri(base_iri.as_deref(), &attribute.value)?.into_string()),
+                _ => {}
+            }
+        }
+
+        let attributes: Vec<_> = attributes
+            .into_iter()
+            .filter(|attribute| !attribute.name.starts_with("xml:"))
+            .collect();
+
+        match self.stack.last() {
+            None if name == rdf_term("RDF") => {
+                self.stack.push(Frame::Root { language, base_iri });
+                Ok(())
+            }
+            None | Some(Frame::Root { .. }) | Some(Frame::Property { .. }) => {
+                self.start_node_element(name, attributes, language, base_iri)
+            }
+            Some(Frame::Node { .. }) => {
+                self.start_property_element(name, attributes, language, base_iri)
+            }
+        }
+    }
+
+    fn start_node_element (
+        &mut self,
+        name: String,
+        attributes: Vec<ResolvedAttribute>,
+        language: Option<String>,
+        base_iri: Option<String>,
+    ) -> Result<(), RdfParseError> {
+        if let Some(term) = name.strip_prefix(RDF_NAMESPACE) {
+            if CORE_SYNTAX_TERMS.contains(&term) || OLD_TERMS.contains(&term) || term == "li" {
+                return Err(RdfParseError::new(format!("rdf:{} can't be used as a node element", term)));
+            }
+        }
+
+        let mut subject = None;
+        let mut properties = Vec::new();
+
+        for attribute in attributes {
+            let node: Subject = match attribute.name.strip_prefix(RDF_NAMESPACE) {
+                Some("about") => resolve_iri(base_iri.as_deref(), &attribute.value)?.into(),
+                Some("ID") => build_id_iri(base_iri.as_deref(), &attribute.value)?.into(),
+                Some("nodeID") => self.get_blank_node(&attribute.value)?.into(),
+                _ => {
+                    check_property_attribute(&attribute)?;
+                    properties.push(attribute);
+                    continue;
+                }
+            };
+
+            if subject.is_some() {
+                return Err(RdfParseError::new("A node element can only have one of rdf:about, rdf:ID and rdf:nodeID"));
+            }
+            subject = Some(node);
+        }
+        let subject: Subject = subject.unwrap_or_else(|| BlankNode::default().into());
+
+        if let Some(Frame::Property { object, datatype, text, .. }) = self.stack.last_mut() {
+            if object.is_some() {
+                return Err(RdfParseError::new("A property element can only contain one node element"));
+            }
+            if datatype.is_some() {
+                return Err(RdfParseError::new("rdf:datatype can only be used on a literal property element"));
+            }
+            if !text.trim().is_empty() {
+                return Err(RdfParseError::new("A property element can't contain both text and a node element"));
+            }
+            *object = Some(subject.clone().into());
+        }
+
+        if name != rdf_term("Description") {
+            self.insert(subject.clone(), rdf::TYPE.into_owned(), NamedNode::new_unchecked(name));
+        }
+        self.insert_property_attributes(&subject, properties, language.as_ref(), base_iri.as_deref())?;
+
+        self.stack.push(Frame::Node { subject, language, base_iri, next_item: 1 });
+
+        Ok(())
+    }
+
+    fn start_property_element (
+        &mut self,
+        name: String,
+        attributes: Vec<ResolvedAttribute>,
+        language: Option<String>,
+        base_iri: Option<String>,
+    ) -> Result<(), RdfParseError> {
+        if let Some(term) = name.strip_prefix(RDF_NAMESPACE) {
+            if CORE_SYNTAX_TERMS.contains(&term) || OLD_TERMS.contains(&term) || term == "Description" {
+                return Err(RdfParseError::new(format!("rdf:{} can't be used as a property element", term)));
+            }
+        }
+
+        let (subject, predicate) = match self.stack.last_mut() {
+            Some(Frame::Node { subject, next_item, .. }) => {
+                let predicate = if name == rdf_term("li") {
+                    *next_item += 1;
+                    rdf_term(&format!("_{}", *next_item - 1))
+                } else {
+                    name
+                };
+
+                (subject.clone(), NamedNode::new_unchecked(predicate))
+            }
+            _ => unreachable!("Property elements are only started in node elements"),
+        };
+
+        let mut object: Option<Term> = None;
+        let mut datatype = None;
+        let mut parse_type = None;
+        let mut statement = None;
+        let mut properties = Vec::new();
+
+        for attribute in attributes {
+            match attribute.name.strip_prefix(RDF_NAMESPACE) {
+                Some("resource") | Some("nodeID") if object.is_some() => {
+                    return Err(RdfParseError::new("A property element can only have one of rdf:resource and rdf:nodeID"));
+                }
+                Some("resource") => object = Some(resolve_iri(base_iri.as_deref(), &attribute.value)?.into()),
+                Some("nodeID") => object = Some(self.get_blank_node(&attribute.value)?.into()),
+                Some("datatype") => datatype = Some(resolve_iri(base_iri.as_deref(), &attribute.value)?),
+                Some("parseType") => parse_type = Some(attribute.value),
+                Some("ID") => statement = Some(build_id_iri(base_iri.as_deref(), &attribute.value)?),
+                _ => {
+                    check_property_attribute(&attribute)?;
+                    properties.push(attribute);
+                }
+            }
+        }
+
+        if datatype.is_some() && (object.is_some() || !properties.is_empty()) {
+            return Err(RdfParseError::new("rdf:datatype can only be used on a literal property element"));
+        }
+
+        match parse_type.as_deref() {
+            None => {}
+            Some(_) if object.is_some() || datatype.is_some() || !properties.is_empty() => {
+                return Err(RdfParseError::new("rdf:parseType can't be used with other attributes than rdf:ID"));
+            }
+            Some("Resource") => {
+                let node = BlankNode::default();
+                self.insert_statement(subject, predicate, node.clone().into(), statement);
+                self.stack.push(Frame::Node { subject: node.into(), language, base_iri, next_item: 1 });
+
+                return Ok(());
+            }
+            Some(parse_type) => {
+                return Err(RdfParseError::new(format!("rdf:parseType=\"{}\" isn't supported", parse_type)));
+            }
+        }
+
+        if !properties.is_empty() {
+            // Property attributes describe the object, a blank node if not given.
+            let node: Subject = match object.take() {
+                None => BlankNode::default().into(),
+                Some(Term::NamedNode(node)) => node.into(),
+                Some(Term::BlankNode(node)) => node.into(),
+                Some(_) => unreachable!("Objects given as attributes are IRIs or blank nodes"),
+            };
+            self.insert_property_attributes(&node, properties, language.as_ref(), base_iri.as_deref())?;
+            object = Some(node.into());
+        }
+
+        self.stack.push(Frame::Property {
+            subject,
+            predicate,
+            object,
+            datatype,
+            text: String::new(),
+            language,
+            base_iri,
+            statement,
+        });
+
+        Ok(())
+    }
+
+    fn insert_property_attributes (
+        &mut self,
+        subject: &Subject,
+        attributes: Vec<ResolvedAttribute>,
+        language: Option<&String>,
+        base_iri: Option<&str>,
+    ) -> Result<(), RdfParseError> {
+        for attribute in attributes {
+            if attribute.name == rdf_term("type") {
+                let object = resolve_iri(base_iri, &attribute.value)?;
+                self.insert(subject.clone(), rdf::TYPE.into_owned(), object);
+                continue;
+            }
+
+            let object = build_literal(attribute.value, None, language)?;
+            self.insert(subject.clone(), NamedNode::new_unchecked(attribute.name), object);
+        }
+
+        Ok(())
+    }
+
+    fn push_text (&mut self, text: &str) -> Result<(), RdfParseError> {
+        if let Some(Frame::Property { text: content, .. }) = self.stack.last_mut() {
+            content.push_str(text);
+            return Ok(());
+        }
+
+        // Only whitespace is allowed between node or property elements.
+        if !text.trim().is_empty() {
+            return Err(RdfParseError::new(format!("Unexpected text outside of a property element: {:?}", text.trim())));
+        }
+
+        Ok(())
+    }
+
+    fn end_element (&mut self) -> Result<(), RdfParseError> {
+        let frame = match self.stack.pop() {
+            Some(frame) => frame,
+            None => return Err(RdfParseError::new("Unexpected closing tag")),
+        };
+
+        if let Frame::Property { subject, predicate, object, datatype, text, language, statement, .. } = frame {
+            let object = match object {
+                Some(_) if !text.trim().is_empty() => {
+                    return Err(RdfParseError::new("A property element can't contain both text and a resource"));
+                }
+                Some(object) => object,
+                None => build_literal(text, datatype, language.as_ref())?.into(),
+            };
+
+            self.insert_statement(subject, predicate, object, statement);
+        }
+
+        Ok(())
+    }
+
+    /// Gets the blank node for a rdf:nodeID value, unique to this document.
+    fn get_blank_node (&mut self, id: &str) -> Result<BlankNode, RdfParseError> {
+        if !is_xml_ncname(id) {
+            return Err(RdfParseError::new(format!("Invalid node identifier: {}", id)));
+        }
+
+        let node = self.blank_nodes
+            .entry(id.to_string())
+            .or_default()
+            .clone();
+
+        Ok(node)
+    }
+
+    /// Inserts a triple, and reifies it as the statement given by rdf:ID if any.
+    fn insert_statement (&mut self, subject: Subject, predicate: NamedNode, object: Term, statement: Option<NamedNode>) {
+        if let Some(statement) = statement {
+            self.insert(statement.clone(), rdf::TYPE.into_owned(), rdf::STATEMENT.into_owned());
+            self.insert(statement.clone(), rdf::SUBJECT.into_owned(), subject.clone());
+            self.insert(statement.clone(), rdf::PREDICATE.into_owned(), predicate.clone());
+            self.insert(statement, rdf::OBJECT.into_owned(), object.clone());
+        }
+
+        self.insert(subject, predicate, object);
+    }
+
+    fn insert (&mut self, subject: impl Into<Subject>, predicate: NamedNode, object: impl Into<Term>) {
+        self.graph.insert(&Triple::new(subject, predicate, object));
+    }
+}
+
+/*  -------------------------------------------------------------
+    Helper methods
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+fn rdf_term (local_name: &str) -> String {
+    format!("{}{}", RDF_NAMESPACE, local_name)
+}
+
+/// Ensures an attribute can be used as a property attribute.
+fn check_property_attribute (attribute: &ResolvedAttribute) -> Result<(), RdfParseError> {
+    match attribute.name.strip_prefix(RDF_NAMESPACE) {
+        Some(term) if CORE_SYNTAX_TERMS.contains(&term) || OLD_TERMS.contains(&term) || term == "li" || term == "Description" => {
+            Err(RdfParseError::new(format!("rdf:{} can't be used as a property attribute here", term)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Builds the IRI of a rdf:ID value, relative to the base IRI.
+fn build_id_iri (base_iri: Option<&str>, id: &str) -> Result<NamedNode, RdfParseError> {
+    if !is_xml_ncname(id) {
+        return Err(RdfParseError::new(format!("Invalid rdf:ID: {}", id)));
+    }
+
+    resolve_iri(base_iri, &format!("#{}", id))
+}
+
+/// Determines if a value is an XML name without colon, as required for rdf:ID and rdf:nodeID.
+fn is_xml_ncname (value: &str) -> bool {
+    let mut chars = value.chars();
+
+    match chars.next() {
+        Some(c) if is_ncname_start_char(c) => chars.all(|c| {
+            is_ncname_start_char(c)
+                || c == '-' || c == '.' || c.is_ascii_digit()
+                || matches!(c, '\u{00B7}' | '\u{0300}'..='\u{036F}' | '\u{203F}'..='\u{2040}')
+        }),
+        _ => false,
+    }
+}
+
+fn is_ncname_start_char (c: char) -> bool {
+    matches!(c,
+        'A'..='Z' | '_' | 'a'..='z'
+        | '\u{00C0}'..='\u{00D6}' | '\u{00D8}'..='\u{00F6}' | '\u{00F8}'..='\u{02FF}'
+        | '\u{0370}'..='\u{037D}' | '\u{037F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}'
+        | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}'
+    )
+}
+
+fn build_literal (value: String, datatype: Option<NamedNode>, language: Option<&String>) -> Result<Literal, RdfParseError> {
+    match (datatype, language) {
+        (Some(datatype), _) if datatype.as_ref() != xsd::STRING => Ok(Literal::new_typed_literal(value, datatype)),
+        (None, Some(language)) => Literal::new_language_tagged_literal(value, language)
+            .map_err(|error| RdfParseError::new(format!("Invalid language tag {}: {}", language, error))),
+        _ => Ok(Literal::new_simple_literal(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use oxrdf::{LiteralRef, NamedNodeRef, TermRef};
+
+    use super::*;
+
+    static DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rdf:RDF
+   xmlns:dcat="http://www.w3.org/ns/dcat#"
+   xmlns:dct="http://purl.org/dc/terms/"
+   xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+   xml:base="https://data.economie.gouv.fr/api/v2/catalog/"
+>
+  <dcat:Dataset rdf:about="datasets/controle_techn" dct:identifier="controle_techn">
+    <dct:title xml:lang="fr">Contrôle technique &amp; prix</dct:title>
+    <dct:issued rdf:datatype="http://www.w3.org/2001/XMLSchema#date">2019-03-01</dct:issued>
+    <dct:publisher rdf:nodeID="N3dce"/>
+    <dcat:distribution>
+      <dcat:Distribution rdf:about="datasets/controle_techn-csv">
+        <dcat:mediaType>text/csv</dcat:mediaType>
+      </dcat:Distribution>
+    </dcat:distribution>
+    <dcat:contactPoint rdf:parseType="Resource">
+      <dct:title>DGE</dct:title>
+    </dcat:contactPoint>
+  </dcat:Dataset>
+  <rdf:Description rdf:nodeID="N3dce">
+    <dct:title></dct:title>
+  </rdf:Description>
+</rdf:RDF>"#;
+
+    fn dct (local_name: &str) -> NamedNode {
+        NamedNode::new_unchecked(format!("http://purl.org/dc/terms/{}", local_name))
+    }
+
+    #[test]
+    fn test_parse () {
+        let graph = parse(DOCUMENT, None).unwrap();
+        let dataset = NamedNodeRef::new_unchecked("https://data.economie.gouv.fr/api/v2/catalog/datasets/controle_techn");
+
+        assert_eq!(11, graph.len());
+        assert_eq!(
+            Some(TermRef::NamedNode(NamedNodeRef::new_unchecked("http://www.w3.org/ns/dcat#Dataset"))),
+            graph.object_for_subject_predicate(dataset, rdf::TYPE),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_language_tagged_literal_unchecked("Contrôle technique & prix", "fr").into()),
+            graph.object_for_subject_predicate(dataset, &dct("title")),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_typed_literal("2019-03-01", xsd::DATE).into()),
+            graph.object_for_subject_predicate(dataset, &dct("issued")),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_simple_literal("controle_techn").into()),
+            graph.object_for_subject_predicate(dataset, &dct("identifier")),
+        );
+
+        // The node identifiers are mapped to blank nodes unique to the document.
+        let publisher = match graph.object_for_subject_predicate(dataset, &dct("publisher")) {
+            Some(TermRef::BlankNode(publisher)) => publisher,
+            object => panic!("Expected a blank node as publisher, found {:?}", object),
+        };
+        assert_ne!("N3dce", publisher.as_str());
+        assert_eq!(
+            Some(LiteralRef::new_simple_literal("").into()),
+            graph.object_for_subject_predicate(publisher, &dct("title")),
+        );
+    }
+
+    fn parse_description (content: &str) -> Result<Graph, RdfParseError> {
+        let document = format!(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="http://example.org/">{}</rdf:RDF>"#,
+            content,
+        );
+
+        parse(&document, Some("http://example.org/doc"))
+    }
+
+    #[test]
+    fn test_parse_node_identifiers_per_document () {
+        let content = r#"<rdf:Description rdf:about="http://example.org/a"><ex:p rdf:nodeID="b"/></rdf:Description>"#;
+
+        let first = parse_description(content).unwrap();
+        let second = parse_description(content).unwrap();
+        assert_ne!(first, second);
+
+        let content = r#"<rdf:Description rdf:nodeID="b"><ex:p rdf:nodeID="b"/></rdf:Description>"#;
+        let triple = parse_description(content).unwrap().iter().next().unwrap().into_owned();
+        assert_eq!(Term::from(triple.subject), triple.object);
+    }
+
+    #[test]
+    fn test_parse_reification () {
+        let content = r#"<rdf:Description rdf:about="http://example.org/a"><ex:p rdf:ID="s1">v</ex:p></rdf:Description>"#;
+
+        let graph = parse_description(content).unwrap();
+        let statement = NamedNodeRef::new_unchecked("http://example.org/doc#s1");
+        assert_eq!(5, graph.len());
+        assert_eq!(
+            Some(rdf::STATEMENT.into()),
+            graph.object_for_subject_predicate(statement, rdf::TYPE),
+        );
+        assert_eq!(
+            Some(NamedNodeRef::new_unchecked("http://example.org/a").into()),
+            graph.object_for_subject_predicate(statement, rdf::SUBJECT),
+        );
+        assert_eq!(
+            Some(NamedNodeRef::new_unchecked("http://example.org/p").into()),
+            graph.object_for_subject_predicate(statement, rdf::PREDICATE),
+        );
+        assert_eq!(
+            Some(LiteralRef::new_simple_literal("v").into()),
+            graph.object_for_subject_predicate(statement, rdf::OBJECT),
+        );
+    }
+
+    #[test]
+    fn test_parse_valid_documents () {
+        let contents = [
+            r#"<rdf:Description rdf:about="a"><ex:p>text</ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p><![CDATA[text]]></ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p>
+                <rdf:Description rdf:about="b"/>
+            </ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:ID="a"><ex:p rdf:resource="b"/></rdf:Description>"#,
+            r#"<rdf:Description rdf:nodeID="a"><ex:p rdf:nodeID="b" ex:q="v"/></rdf:Description>"#,
+            r#"<rdf:Description><ex:p rdf:parseType="Resource" rdf:ID="s"><ex:q>v</ex:q></ex:p></rdf:Description>"#,
+            r#"<ex:Type><rdf:li>1</rdf:li><rdf:type rdf:resource="b"/></ex:Type>"#,
+        ];
+
+        for content in contents {
+            assert!(parse_description(content).is_ok(), "{} should be accepted", content);
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_documents () {
+        let contents = [
+            // Text outside of property elements
+            r#"text<rdf:Description rdf:about="a"/>"#,
+            r#"<rdf:Description rdf:about="a">text<ex:p>v</ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:parseType="Resource">text</ex:p></rdf:Description>"#,
+            // Text and resources in property elements
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:resource="b">text</ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p>text<rdf:Description rdf:about="b"/></ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p><rdf:Description rdf:about="b"/>text</ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p><rdf:Description/><rdf:Description/></ex:p></rdf:Description>"#,
+            // Conflicting attributes
+            r#"<rdf:Description rdf:about="a" rdf:nodeID="b"/>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:resource="b" rdf:nodeID="c"/></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:resource="b" rdf:datatype="c"/></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:datatype="c"><rdf:Description/></ex:p></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:parseType="Resource" rdf:resource="b"/></rdf:Description>"#,
+            // Syntax terms used as nodes or properties
+            r#"<rdf:li rdf:about="a"/>"#,
+            r#"<rdf:about rdf:about="a"/>"#,
+            r#"<rdf:Description rdf:about="a"><rdf:Description/></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a"><rdf:resource>v</rdf:resource></rdf:Description>"#,
+            r#"<rdf:Description rdf:about="a" rdf:resource="b"/>"#,
+            r#"<rdf:Description rdf:about="a" rdf:li="v"/>"#,
+            r#"<rdf:Description rdf:aboutEach="a"/>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:bagID="b">v</ex:p></rdf:Description>"#,
+            // Invalid identifiers
+            r#"<rdf:Description rdf:nodeID="1a"/>"#,
+            r#"<rdf:Description rdf:ID="a:b"/>"#,
+            r#"<rdf:Description rdf:about="a"><ex:p rdf:ID="1s">v</ex:p></rdf:Description>"#,
+        ];
+
+        for content in contents {
+            assert!(parse_description(content).is_err(), "{} should be rejected", content);
+        }
+    }
+
+    #[test]
+    fn test_parse_when_unsupported () {
+        let document = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="http://example.org/">
+  <rdf:Description rdf:about="http://example.org/a">
+    <ex:p rdf:parseType="Literal"><b>bold</b></ex:p>
+  </rdf:Description>
+</rdf:RDF>"#;
+
+        let error = parse(document, None).unwrap_err();
+        assert_eq!(Some(3), error.line);
+    }
+}
@@ -3,40 +3,112 @@
 //! The `ExploreApiEndPoint` struct allows to define an API end-point, and call the Explore API,
 //! ie the methods starting by /catalog in the open data portal documentation.
 
-use reqwest::Client;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use ds_http_client::Client as HttpClient;
+use futures_util::Stream;
+use oxrdf::Graph;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
 use crate::ApiHttpResponse;
+use crate::auth::ApiKeyLocation;
+use crate::error::Error;
+use crate::export::{ExportFormat, ExportOptions};
+use crate::pagination::{datasets_stream, export_records_stream, records_stream};
+use crate::query::RecordsQuery;
+use crate::rdf::{parse_graph, RdfParseError, RdfSyntax};
 use crate::schema::*;
+use crate::routes::*;
+use crate::transport::{build_default_http_client, Credentials, Transport};
+
+/// The responses schema of an Explore API version
+///
+/// The v2 and v2.1 offer the same requests, only their responses differ.
+pub trait ApiSchema {
+    type DatasetsCollection: DeserializeOwned;
+    type Dataset: DeserializeOwned;
+    type Results: DeserializeOwned;
+    type Record: DeserializeOwned;
+}
 
-static USER_AGENT: &str = concat!(
-    env!("CARGO_PKG_NAME"),
-    "/",
-    env!("CARGO_PKG_VERSION"),
-);
+/// The Explore API v2 responses schema
+#[derive(Debug)]
+pub struct V2Schema;
+
+impl ApiSchema for V2Schema {
+    type DatasetsCollection = DatasetsCollection;
+    type Dataset = Dataset;
+    type Results = Results;
+    type Record = Record;
+}
 
-/// The Explore API end-point
-pub struct ExploreApiEndPoint {
-    /// The Opendatasoft Explore API v2 server to use
+/// An Explore API end-point, for the version of the responses schema
+#[derive(Debug)]
+pub struct EndPoint<S: ApiSchema> {
+    /// The Opendatasoft Explore API server to use
     pub url: String,
 
-    client: Client,
+    transport: Transport,
+    schema: PhantomData<S>,
 }
 
-impl ExploreApiEndPoint {
+/// The Explore API v2 end-point
+pub type ExploreApiEndPoint = EndPoint<V2Schema>;
+
+impl<S: ApiSchema> EndPoint<S> {
     /// Get a new instance of the API end-point, with an HTTP client ready to run queries.
     ///
-    /// * `url` - The Explore API url, for example DOMAIN/api/v2/
+    /// * `url` - The Explore API url, for example DOMAIN/api/v2/ or DOMAIN/api/explore/v2.1
     pub fn new (url: &str) -> Self {
+        Self::from_http_client(url, build_default_http_client())
+    }
+
+    /// Get a new instance of the API end-point, using the specified HTTP client.
+    ///
+    /// This allows to share the client configuration, e.g. the User-Agent
+    /// or the proxies, with the other requests of the application.
+    ///
+    /// * `url` - The Explore API url, for example DOMAIN/api/v2/ or DOMAIN/api/explore/v2.1
+    /// * `http_client` - The HTTP client to use for every request
+    pub fn from_http_client (url: &str, http_client: HttpClient) -> Self {
         Self {
             url: url.to_string(),
-            client: Client::builder()
-                .user_agent(USER_AGENT)
-                .build()
-                .expect("A HTTP client should be built"),
+            transport: Transport::new(http_client),
+            schema: PhantomData,
         }
     }
 
+    /// Authenticates requests with an API key, to query restricted datasets.
+    ///
+    /// * `api_key` - The API key, as generated in the portal account settings
+    /// * `location` - Sends the key as `Authorization` header or `apikey` URL parameter
+    ///
+    /// Fails with `Error::InvalidHeader` if the key contains invalid characters, e.g. a newline.
+    pub fn with_api_key (mut self, api_key: &str, location: ApiKeyLocation) -> Result<Self, Error> {
+        self.transport.set_api_key(api_key, location)?;
+        Ok(self)
+    }
+
+    /// Retries requests failing with a network or server error.
+    ///
+    /// Attempts are spaced by an exponential backoff, from 500 ms up to 1 minute.
+    pub fn with_retries (mut self, retries: u32) -> Self {
+        self.transport.set_retries(retries);
+        self
+    }
+
+    /// Adds a header to send with every request.
+    ///
+    /// Headers values are considered as sensitive, and so aren't printed by `Debug`.
+    ///
+    /// Fails with `Error::InvalidHeader` if the name or the value can't be sent in a request.
+    pub fn with_header (mut self, name: &str, value: &str) -> Result<Self, Error> {
+        self.transport.insert_header(name, value)?;
+        Ok(self)
+    }
+
     /*  -------------------------------------------------------------
         Part 1 - catalog
 
@@ -44,8 +116,10 @@ impl ExploreApiEndPoint {
         - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
 
     /// Query catalog datasets
-    pub async fn get_datasets(&self) -> DatasetsCollection {
-        let url = self.get_url("/catalog/datasets");
+    ///
+    /// * `query` - Optional parameters to filter, sort or paginate datasets
+    pub async fn get_datasets(&self, query: &RecordsQuery) -> Result<S::DatasetsCollection, Error> {
+        let url = get_datasets_url(&self.url, query)?;
 
         self.fetch(url).await
     }
@@ -56,15 +130,17 @@ impl ExploreApiEndPoint {
     /// the chunk() method to get the next part of the export,
     /// as a Bytes object from bytes crate.
     ///
-    /// * `format` - The format you want, API seems to support "json", "csv", "xls", "rdf", "ttl",
-    ///              "data.json", "rss" et "dcat".
+    /// * `format` - The format you want, for example Json, Csv, Xls, RdfXml, Turtle,
+    ///   DataJson, Rss or Dcat.
     ///
     /// Example:
     /// ```
+    /// use opendatasoft_explore_api::export::ExportFormat;
     /// use opendatasoft_explore_api::requests::ExploreApiEndPoint;
     ///
     /// async fn print_catalog_rdf (api: ExploreApiEndPoint) {
-    ///     let mut response = api.export_datasets_catalog("rdf").await;
+    ///     let mut response = api.export_datasets_catalog(ExportFormat::RdfXml).await
+    ///         .expect("Can't export catalog");
     ///
     ///     while let Some(chunk) = response.chunk().await.unwrap() {
     ///         let bytes = chunk.to_vec(); // Vec<u8>
@@ -75,10 +151,8 @@ impl ExploreApiEndPoint {
     ///     println!();
     /// }
     /// ```
-    pub async fn export_datasets_catalog(&self, format: &str) -> ApiHttpResponse {
-        let url = self
-            .get_url("/catalog/exports/?")
-            .replace("?", format);
+    pub async fn export_datasets_catalog(&self, format: ExportFormat) -> Result<ApiHttpResponse, Error> {
+        let url = get_catalog_export_url(&self.url, format);
 
         self.fetch_resource(url).await
     }
@@ -87,8 +161,10 @@ impl ExploreApiEndPoint {
     ///
     /// Enumerate facet values for datasets and returns a list of values for each facet.
     /// Can be used to implement guided navigation in large result sets.
-    pub async fn get_facets(&self) -> FacetsCollection {
-        let url = self.get_url("/catalog/facets");
+    ///
+    /// * `query` - Optional parameters, for example to refine or exclude facets values
+    pub async fn get_facets(&self, query: &RecordsQuery) -> Result<FacetsCollection, Error> {
+        let url = get_facets_url(&self.url, query)?;
 
         self.fetch(url).await
     }
@@ -102,10 +178,9 @@ impl ExploreApiEndPoint {
     /// Query datasets records
     ///
     /// * `dataset_id` - The identifier of the dataset to be queried.
-    pub async fn get_dataset_records(&self, dataset_id: &str) -> Results {
-        let url = self
-            .get_url("/catalog/datasets/?/records")
-            .replace("?", dataset_id);
+    /// * `query` - Optional parameters to select, filter, sort, paginate or aggregate records
+    pub async fn get_dataset_records(&self, dataset_id: &str, query: &RecordsQuery) -> Result<S::Results, Error> {
+        let url = get_dataset_records_url(&self.url, dataset_id, query)?;
 
         self.fetch(url).await
     }
@@ -118,17 +193,37 @@ impl ExploreApiEndPoint {
     /// See `export_datasets_catalog` for an example.
     ///
     /// * `dataset_id` - The identifier of the dataset to be queried.
-    /// * `format` - The format you want, API seems to support "json", "geojson", "shp", "csv",
-    ///              "xls", "jsonl", "jsonld", "rdfxml", "turtle" and "n3"
-    pub async fn export_dataset(&self, dataset_id: &str, format: &str) -> ApiHttpResponse {
-        let url = self
-            .get_url("/catalog/datasets/:id/exports/:format")
-            .replace(":id", dataset_id)
-            .replace(":format", format);
+    /// * `format` - The format you want, for example Json, GeoJson, Shapefile, Csv,
+    ///   Xlsx, Jsonl, JsonLd, RdfXml, Turtle or N3
+    /// * `query` - Optional parameters to select, filter or sort exported records
+    pub async fn export_dataset(&self, dataset_id: &str, format: ExportFormat, query: &RecordsQuery) -> Result<ApiHttpResponse, Error> {
+        let url = get_export_url(&self.url, dataset_id, format, query, &ExportOptions::default())?;
 
         self.fetch_resource(url).await
     }
 
+    /// Export a dataset to a file, streaming the response to disk.
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `format` - The format you want
+    /// * `query` - Optional parameters to select, filter or sort exported records
+    /// * `options` - Optional export parameters, like CSV delimiter or use of fields labels
+    /// * `target_path` - The file to write, overwritten if it exists
+    pub async fn export_dataset_to_file<P>(
+        &self,
+        dataset_id: &str,
+        format: ExportFormat,
+        query: &RecordsQuery,
+        options: &ExportOptions,
+        target_path: P,
+    ) -> Result<usize, Error> where P: AsRef<Path> {
+        let url = get_export_url(&self.url, dataset_id, format, query, options)?;
+
+        self.transport.download(url, target_path, Credentials::Included).await
+    }
+
     /// Show dataset information
     ///
     /// Returns a list of available endpoints for the specified dataset, with metadata and endpoints.
@@ -141,9 +236,8 @@ impl ExploreApiEndPoint {
     /// * the files endpoint
     /// * the records endpoint
     /// * the catalog endpoint
-    pub async fn get_dataset_information(&self, dataset_id: &str) -> Dataset {
-        let mut url = self.get_url("/catalog/datasets/");
-        url.push_str(dataset_id);
+    pub async fn get_dataset_information(&self, dataset_id: &str) -> Result<S::Dataset, Error> {
+        let url = get_dataset_url(&self.url, dataset_id);
 
         self.fetch(url).await
     }
@@ -154,10 +248,8 @@ impl ExploreApiEndPoint {
     /// Can be used to implement guided navigation in large result sets.
     ///
     /// * `dataset_id` - The identifier of the dataset to be queried.
-    pub async fn get_dataset_facets(&self, dataset_id: &str) -> FacetsCollection {
-        let url = self
-            .get_url("/catalog/datasets/?/facets")
-            .replace("?", dataset_id);
+    pub async fn get_dataset_facets(&self, dataset_id: &str) -> Result<FacetsCollection, Error> {
+        let url = get_dataset_facets_url(&self.url, dataset_id);
 
         self.fetch(url).await
     }
@@ -169,12 +261,10 @@ impl ExploreApiEndPoint {
     /// at what URL download it.
     ///
     /// * `dataset_id` - The identifier of the dataset to be queried.
-    pub async fn get_dataset_attachments(&self, dataset_id: &str) -> AttachmentCollection {
-        let url = self
-            .get_url("/catalog/datasets/?/attachments")
-            .replace("?", dataset_id);
+    pub async fn get_dataset_attachments(&self, dataset_id: &str) -> Result<AttachmentCollection, Error> {
+        let url = get_dataset_attachments_url(&self.url, dataset_id);
 
-         self.fetch(url).await
+        self.fetch(url).await
     }
 
     /// Read a dataset record
@@ -183,36 +273,205 @@ impl ExploreApiEndPoint {
     ///
     /// * `dataset_id` - The identifier of the dataset to be queried.
     /// * `record_id` - Record identified, for example an UUID
-    pub async fn get_dataset_record(&self, dataset_id: &str, record_id: &str) -> Record {
-        let url = self
-            .get_url("/catalog/datasets/:id/records/:record")
-            .replace(":id", dataset_id)
-            .replace(":record", record_id);
+    pub async fn get_dataset_record(&self, dataset_id: &str, record_id: &str) -> Result<S::Record, Error> {
+        let url = get_dataset_record_url(&self.url, dataset_id, record_id);
 
         self.fetch(url).await
     }
 
+    /*  -------------------------------------------------------------
+        Part 3 - files
+
+        API to download attachments and files fields
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    /// Download a dataset attachment to a file
+    ///
+    /// The request uses the end-point configuration: API key, headers and retries.
+    /// The API key and the headers are only sent when the attachment is on the same
+    /// origin as the API end-point, so they don't leak to another host.
+    /// Returns the number of bytes written.
+    ///
+    /// * `attachment` - The attachment, as listed by `get_dataset_attachments`
+    /// * `target_path` - The file to write, overwritten if it exists
+    pub async fn download_attachment<P>(&self, attachment: &Attachment, target_path: P) -> Result<usize, Error>
+    where P: AsRef<Path> {
+        let credentials = Credentials::for_url(&self.url, &attachment.href);
+
+        self.transport.download(attachment.href.clone(), target_path, credentials).await
+    }
+
+    /// Get a file from a file field, like an image
+    ///
+    /// As the method returns the raw HTTP response, you can use
+    /// the chunk() method to get the next part of the file.
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `file_id` - The file identifier, as the `id` of a `fields::FileField` value
+    pub async fn get_file(&self, dataset_id: &str, file_id: &str) -> Result<ApiHttpResponse, Error> {
+        let url = get_file_url(&self.url, dataset_id, file_id);
+
+        self.fetch_resource(url).await
+    }
+
+    /// Download a file from a file field, like an image, to a file
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `file_id` - The file identifier, as the `id` of a `fields::FileField` value
+    /// * `target_path` - The file to write, overwritten if it exists
+    pub async fn download_file<P>(&self, dataset_id: &str, file_id: &str, target_path: P) -> Result<usize, Error>
+    where P: AsRef<Path> {
+        let url = get_file_url(&self.url, dataset_id, file_id);
+
+        self.transport.download(url, target_path, Credentials::Included).await
+    }
+
     /*  -------------------------------------------------------------
         Helper methods
         - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
 
-    fn get_url (&self, method: &str) -> String {
-        format!("{}{}", self.url, method)
+    async fn fetch_resource (&self, url: String) -> Result<ApiHttpResponse, Error> {
+        self.transport.fetch_resource(url).await
+    }
+
+    async fn fetch<T> (&self, url: String) -> Result<T, Error> where for<'a> T: Deserialize<'a> {
+        self.transport.fetch(url).await
+    }
+}
+
+impl ExploreApiEndPoint {
+    /*  -------------------------------------------------------------
+        Part 1 - catalog
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    /// Stream all the datasets of the catalog matching a query
+    ///
+    /// Pages are requested as the stream is consumed. As for records, the catalog
+    /// can't be paginated beyond 10,000 datasets, so the stream ends there.
+    ///
+    /// * `query` - Optional parameters to search, refine, exclude or sort datasets.
+    ///   The limit is used as page size, the offset as first dataset to return.
+    ///
+    /// Example:
+    /// ```
+    /// use futures_util::{pin_mut, StreamExt};
+    /// use opendatasoft_explore_api::query::{CatalogFacet, RecordsQuery};
+    /// use opendatasoft_explore_api::requests::ExploreApiEndPoint;
+    ///
+    /// async fn print_address_datasets (api: ExploreApiEndPoint) {
+    ///     let query = RecordsQuery::new()
+    ///         .search("adresse")
+    ///         .refine(CatalogFacet::Publisher, "DGFiP");
+    ///
+    ///     let datasets = api.datasets_stream(&query);
+    ///     pin_mut!(datasets);
+    ///
+    ///     while let Some(dataset) = datasets.next().await {
+    ///         println!("{}", dataset.expect("Can't read dataset").dataset.dataset_id);
+    ///     }
+    /// }
+    /// ```
+    pub fn datasets_stream<'a>(&'a self, query: &RecordsQuery) -> impl Stream<Item = Result<Dataset, Error>> + 'a {
+        datasets_stream(self, query)
+    }
+
+    /// Export a catalog as an RDF graph
+    ///
+    /// Downloads the catalog export, then parses it into an [`oxrdf::Graph`].
+    /// The rdf and dcat formats are parsed as RDF/XML, the ttl and n3 formats as Turtle.
+    ///
+    /// The helpers of the [`rdf::dcat`](crate::rdf::dcat) module allow then to list
+    /// the datasets of the catalog, with their distributions.
+    ///
+    /// The whole export is buffered in memory before being parsed, as the graph
+    /// is built in memory anyway. To process a large catalog without holding it,
+    /// use [`export_datasets_catalog`](Self::export_datasets_catalog) and stream the response.
+    ///
+    /// * `format` - The RDF format of the export, other formats are rejected
+    pub async fn export_datasets_catalog_graph(&self, format: ExportFormat) -> Result<Graph, Error> {
+        let syntax = RdfSyntax::from_export_format(format)
+            .ok_or_else(|| Error::Rdf(RdfParseError::new(
+                format!("{} export isn't an RDF format", format.catalog_name())
+            )))?;
+
+        let mut response = self.export_datasets_catalog(format).await?;
+        let base_iri = response.url().to_string();
+
+        let mut buffer = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(Error::from_reqwest)? {
+            buffer.extend_from_slice(&chunk);
+        }
+
+        let document = String::from_utf8(buffer)
+            .map_err(|_| Error::Rdf(RdfParseError::new("Export isn't a valid UTF-8 document")))?;
+
+        parse_graph(syntax, &document, Some(&base_iri))
+            .map_err(Error::Rdf)
     }
 
-    async fn fetch_resource (&self, url: String) -> ApiHttpResponse {
-        self.client.get(url)
-            .send().await
-            .expect("Can't fetch API URL")
+    /*  -------------------------------------------------------------
+        Part 2 - datasets
+        - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+    /// Query datasets records, deserializing their fields into a user struct
+    ///
+    /// See the fields module for the Rust types matching Opendatasoft field types.
+    /// Aggregations results are ignored.
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `query` - Optional parameters to select, filter, sort or paginate records
+    pub async fn get_dataset_records_as<T>(&self, dataset_id: &str, query: &RecordsQuery) -> Result<TypedResults<T>, Error>
+    where T: DeserializeOwned
+    {
+        self.get_dataset_records(dataset_id, query).await?
+            .records_as()
     }
 
-    async fn fetch<T> (&self, url: String) -> T where for<'a> T: Deserialize<'a> {
-        let body = self.fetch_resource(url).await
-            .text().await
-            .expect("Can't get HTTP response content");
+    /// Stream all the records of a dataset
+    ///
+    /// Pages are fetched as needed, with `limit` as page size, up to 100 records by page.
+    ///
+    /// The records endpoint can't return records after the 10,000th one.
+    /// For larger datasets, or an offset beyond, all the records are read from the jsonl export instead.
+    /// Records read from an export don't have links, and can lack id and timestamp.
+    ///
+    /// Aggregations results are ignored, so `group_by` shouldn't be used.
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `query` - Optional parameters to select, filter or sort records
+    ///
+    /// Example:
+    /// ```
+    /// use futures_util::{pin_mut, StreamExt};
+    /// use opendatasoft_explore_api::query::RecordsQuery;
+    /// use opendatasoft_explore_api::requests::ExploreApiEndPoint;
+    ///
+    /// async fn print_records_ids (api: ExploreApiEndPoint) {
+    ///     let records = api.records_stream("controle_techn", &RecordsQuery::new());
+    ///     pin_mut!(records);
+    ///
+    ///     while let Some(record) = records.next().await {
+    ///         println!("{}", record.expect("Can't read record").record.id);
+    ///     }
+    /// }
+    /// ```
+    pub fn records_stream<'a>(&'a self, dataset_id: &str, query: &RecordsQuery) -> impl Stream<Item = Result<Record, Error>> + 'a {
+        records_stream(self, dataset_id, query)
+    }
 
-        serde_json::from_str(&body)
-            .expect("HTTP response should be a valid dataset, can't parse it.")
+    /// Stream all the records of a dataset from its jsonl export
+    ///
+    /// The export is read one line at a time, so it's the fastest way
+    /// to read a full dataset, e.g. to load it into a database.
+    /// Records don't have links, and can lack id and timestamp.
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `query` - Optional parameters to select, filter or sort records.
+    ///   The limit is ignored, the offset skips the first lines.
+    pub fn export_records_stream<'a>(&'a self, dataset_id: &str, query: &RecordsQuery) -> impl Stream<Item = Result<Record, Error>> + 'a {
+        export_records_stream(self, dataset_id, query)
     }
 }
 
@@ -221,10 +480,25 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_get_url () {
-        let endpoint = ExploreApiEndPoint::new("https://foo");
-        assert_eq!("https://foo/bar", endpoint.get_url("/bar"));
-        assert_eq!("https://foo", endpoint.get_url(""));
+    fn test_debug_doesnt_print_secrets () {
+        let endpoint = ExploreApiEndPoint::new("https://foo")
+            .with_api_key("s3cr3t", ApiKeyLocation::Header)
+            .unwrap()
+            .with_header("X-Token", "t0k3n")
+            .unwrap();
+
+        let output = format!("{:?}", endpoint);
+        assert!(output.contains("x-token"));
+        assert!(!output.contains("s3cr3t"));
+        assert!(!output.contains("t0k3n"));
+    }
+
+    #[test]
+    fn test_with_api_key_when_invalid () {
+        let result = ExploreApiEndPoint::new("https://foo")
+            .with_api_key("key\n", ApiKeyLocation::Header);
+
+        assert!(matches!(result, Err(Error::InvalidHeader(_))));
     }
 
     // Requests integration tests are located in tests/ folder.
@@ -0,0 +1,134 @@
+//! URLs of the Explore API v2 requests.
+//!
+//! Shared by the async and the blocking end-points.
+
+use crate::error::Error;
+use crate::export::{ExportFormat, ExportOptions};
+use crate::query::RecordsQuery;
+use crate::transport::{append_export_query, append_query};
+
+/*  -------------------------------------------------------------
+    Catalog
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+pub(crate) fn get_datasets_url (base_url: &str, query: &RecordsQuery) -> Result<String, Error> {
+    get_url_with_query(base_url, "/catalog/datasets", query)
+}
+
+pub(crate) fn get_catalog_export_url (base_url: &str, format: ExportFormat) -> String {
+    get_url(base_url, "/catalog/exports/?")
+        .replace("?", format.catalog_name())
+}
+
+pub(crate) fn get_facets_url (base_url: &str, query: &RecordsQuery) -> Result<String, Error> {
+    get_url_with_query(base_url, "/catalog/facets", query)
+}
+
+/*  -------------------------------------------------------------
+    Datasets
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+pub(crate) fn get_dataset_records_url (base_url: &str, dataset_id: &str, query: &RecordsQuery) -> Result<String, Error> {
+    let method = "/catalog/datasets/?/records"
+        .replace("?", dataset_id);
+
+    get_url_with_query(base_url, &method, query)
+}
+
+pub(crate) fn get_export_url (
+    base_url: &str,
+    dataset_id: &str,
+    format: ExportFormat,
+    query: &RecordsQuery,
+    options: &ExportOptions,
+) -> Result<String, Error> {
+    let method = "/catalog/datasets/:id/exports/:format"
+        .replace(":id", dataset_id)
+        .replace(":format", format.name());
+
+    append_export_query(get_url(base_url, &method), query, options)
+}
+
+pub(crate) fn get_dataset_url (base_url: &str, dataset_id: &str) -> String {
+    let mut url = get_url(base_url, "/catalog/datasets/");
+    url.push_str(dataset_id);
+
+    url
+}
+
+pub(crate) fn get_dataset_facets_url (base_url: &str, dataset_id: &str) -> String {
+    get_url(base_url, "/catalog/datasets/?/facets")
+        .replace("?", dataset_id)
+}
+
+pub(crate) fn get_dataset_attachments_url (base_url: &str, dataset_id: &str) -> String {
+    get_url(base_url, "/catalog/datasets/?/attachments")
+        .replace("?", dataset_id)
+}
+
+pub(crate) fn get_dataset_record_url (base_url: &str, dataset_id: &str, record_id: &str) -> String {
+    get_url(base_url, "/catalog/datasets/:id/records/:record")
+        .replace(":id", dataset_id)
+        .replace(":record", record_id)
+}
+
+pub(crate) fn get_file_url (base_url: &str, dataset_id: &str, file_id: &str) -> String {
+    get_url(base_url, "/catalog/datasets/:id/files/:file")
+        .replace(":id", dataset_id)
+        .replace(":file", file_id)
+}
+
+/*  -------------------------------------------------------------
+    Helper methods
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+fn get_url (base_url: &str, method: &str) -> String {
+    format!("{}{}", base_url, method)
+}
+
+fn get_url_with_query (base_url: &str, method: &str, query: &RecordsQuery) -> Result<String, Error> {
+    append_query(get_url(base_url, method), query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_url () {
+        assert_eq!("https://foo/bar", get_url("https://foo", "/bar"));
+        assert_eq!("https://foo", get_url("https://foo", ""));
+    }
+
+    #[test]
+    fn test_get_url_with_query () {
+        let query = RecordsQuery::new();
+        assert_eq!("https://foo/bar", get_url_with_query("https://foo", "/bar", &query).unwrap());
+
+        let query = RecordsQuery::new()
+            .where_clause("libelle = 'Rue de l''Église'")
+            .limit(10);
+        assert_eq!(
+            "https://foo/bar?where=libelle+%3D+%27Rue+de+l%27%27%C3%89glise%27&limit=10",
+            get_url_with_query("https://foo", "/bar", &query).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_url_with_query_when_invalid () {
+        let query = RecordsQuery::new().limit(10);
+
+        assert!(matches!(
+            get_url_with_query("not an url", "/bar", &query),
+            Err(Error::InvalidUrl(url)) if url == "not an url/bar"
+        ));
+    }
+
+    #[test]
+    fn test_get_dataset_record_url () {
+        assert_eq!(
+            "https://foo/catalog/datasets/controle_techn/records/eb04cba1",
+            get_dataset_record_url("https://foo", "controle_techn", "eb04cba1"),
+        );
+    }
+}
@@ -1,10 +1,16 @@
 //! Schema for Opendatasoft Explore API v2
 
 use chrono::{DateTime, Utc};
+use serde::de::{DeserializeOwned, Deserializer, Error as DeserializeError};
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
+use serde_json::Map as JsonMap;
 use serde_json::Value as JsonValue;
 
+use crate::error::Error;
+use crate::fields::FieldType;
+use crate::metas::{DatasetMetas, DefaultMetas};
+
 /*  -------------------------------------------------------------
     links
     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
@@ -34,12 +40,67 @@ pub struct DatasetProperties {
     pub data_visible: bool,
     /// A map of available features for a dataset, with the fields they apply to.
     pub features: Vec<String>,
-    pub metas: JsonValue,
+    pub metas: DatasetMetas,
     pub fields: Vec<DatasetField>,
     #[serde(rename = "additionalProperties", default, skip_serializing_if = "Option::is_none")]
     pub additional_properties: Option<JsonValue>,
 }
 
+impl Dataset {
+    pub fn title (&self) -> Option<&str> {
+        self.dataset.title()
+    }
+
+    pub fn license (&self) -> Option<&str> {
+        self.dataset.license()
+    }
+
+    pub fn modified (&self) -> Option<DateTime<Utc>> {
+        self.dataset.modified()
+    }
+}
+
+/// Accessors for the most used metadata of the default template
+impl DatasetProperties {
+    fn default_metas (&self) -> Option<&DefaultMetas> {
+        self.metas.default.as_ref()
+    }
+
+    pub fn title (&self) -> Option<&str> {
+        self.default_metas()?.title.as_deref()
+    }
+
+    pub fn description (&self) -> Option<&str> {
+        self.default_metas()?.description.as_deref()
+    }
+
+    pub fn keywords (&self) -> &[String] {
+        self.default_metas()
+            .and_then(|metas| metas.keyword.as_deref())
+            .unwrap_or_default()
+    }
+
+    pub fn license (&self) -> Option<&str> {
+        self.default_metas()?.license.as_deref()
+    }
+
+    pub fn publisher (&self) -> Option<&str> {
+        self.default_metas()?.publisher.as_deref()
+    }
+
+    pub fn modified (&self) -> Option<DateTime<Utc>> {
+        self.default_metas()?.modified
+    }
+
+    pub fn data_processed (&self) -> Option<DateTime<Utc>> {
+        self.default_metas()?.data_processed
+    }
+
+    pub fn records_count (&self) -> Option<u64> {
+        self.default_metas()?.records_count
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub struct DatasetField {
     pub name: String,
@@ -51,6 +112,12 @@ pub struct DatasetField {
     pub description: Option<String>,
 }
 
+impl DatasetField {
+    pub fn get_field_type (&self) -> FieldType {
+        FieldType::from(self.field_type.as_str())
+    }
+}
+
 /*  -------------------------------------------------------------
     results_dataset
     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
@@ -88,10 +155,56 @@ pub struct FacetEnumeration {
     aggregation
     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
 
+/// A result of a query with a group_by clause
+///
+/// The values are the group keys and the aggregate columns,
+/// named as in the group_by and select clauses, or by their alias:
+/// `select=count(*) as n&group_by=code_postal` gives `{"code_postal": "85300", "n": 12}`.
 #[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Aggregation {
-    pub count: usize,
-    pub cou_name_en: String,
+    pub values: JsonMap<String, JsonValue>,
+}
+
+impl Aggregation {
+    pub fn get (&self, name: &str) -> Option<&JsonValue> {
+        self.values.get(name)
+    }
+
+    pub fn get_str (&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(JsonValue::as_str)
+    }
+
+    pub fn get_i64 (&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(JsonValue::as_i64)
+    }
+
+    pub fn get_f64 (&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(JsonValue::as_f64)
+    }
+
+    /// Gets the records count, for `count(*)` without alias or aliased as `count`.
+    pub fn get_count (&self) -> Option<u64> {
+        self.get("count(*)")
+            .or_else(|| self.get("count"))
+            .and_then(JsonValue::as_u64)
+    }
+
+    /// Deserializes a value into a Rust type, e.g. a date for a `year(...)` group key.
+    pub fn get_as<T> (&self, name: &str) -> Result<Option<T>, Error> where T: DeserializeOwned {
+        match self.get(name) {
+            None | Some(JsonValue::Null) => Ok(None),
+            Some(value) => T::deserialize(value)
+                .map(Some)
+                .map_err(Error::Json),
+        }
+    }
+
+    /// Deserializes the whole aggregation into a user struct.
+    pub fn values_as<T> (&self) -> Result<T, Error> where T: DeserializeOwned {
+        T::deserialize(JsonValue::Object(self.values.clone()))
+            .map_err(Error::Json)
+    }
 }
 
 /*  -------------------------------------------------------------
@@ -112,6 +225,16 @@ pub struct RecordProperties {
     pub fields: JsonValue,
 }
 
+impl Record {
+    /// Deserializes the record fields into a user struct.
+    ///
+    /// See the fields module for the Rust types matching Opendatasoft field types.
+    pub fn fields_as<T> (&self) -> Result<T, Error> where T: DeserializeOwned {
+        T::deserialize(&self.record.fields)
+            .map_err(Error::Json)
+    }
+}
+
 /*  -------------------------------------------------------------
     results
     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
@@ -123,11 +246,86 @@ pub struct Results {
     pub records: Vec<ResultsRecord>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// An item of the records results
+///
+/// An object with a `record` key is a record, and fails to deserialize
+/// if it's malformed. Any other object is an aggregation.
+#[derive(Clone, Debug, Serialize)]
 #[serde(untagged)]
 pub enum ResultsRecord {
-    Aggregation(Aggregation),
     Record(Record),
+    Aggregation(Aggregation),
+}
+
+impl<'de> serde::Deserialize<'de> for ResultsRecord {
+    fn deserialize<D> (deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let values: JsonMap<String, JsonValue> = serde::Deserialize::deserialize(deserializer)?;
+
+        if !values.contains_key("record") {
+            return Ok(ResultsRecord::Aggregation(Aggregation { values }));
+        }
+
+        serde_json::from_value(JsonValue::Object(values))
+            .map(ResultsRecord::Record)
+            .map_err(|error| D::Error::custom(format!("invalid record: {}", error)))
+    }
+}
+
+impl Results {
+    /// Deserializes the records fields into user structs.
+    ///
+    /// Aggregations results are ignored.
+    pub fn records_as<T> (&self) -> Result<TypedResults<T>, Error> where T: DeserializeOwned {
+        let records = self.records
+            .iter()
+            .filter_map(|record| match record {
+                ResultsRecord::Record(record) => Some(record.fields_as()),
+                ResultsRecord::Aggregation(_) => None,
+            })
+            .collect::<Result<Vec<T>, Error>>()?;
+
+        Ok(TypedResults {
+            total_count: self.total_count,
+            records,
+        })
+    }
+
+    /// Gets the records of the results, ignoring aggregations.
+    pub fn get_records (&self) -> Vec<&Record> {
+        self.records
+            .iter()
+            .filter_map(|record| match record {
+                ResultsRecord::Record(record) => Some(record),
+                ResultsRecord::Aggregation(_) => None,
+            })
+            .collect()
+    }
+
+    /// Gets the results of a query with a group_by clause.
+    pub fn get_aggregations (&self) -> Vec<&Aggregation> {
+        self.records
+            .iter()
+            .filter_map(|record| match record {
+                ResultsRecord::Aggregation(aggregation) => Some(aggregation),
+                ResultsRecord::Record(_) => None,
+            })
+            .collect()
+    }
+
+    /// Deserializes the results of a query with a group_by clause into user structs.
+    pub fn aggregations_as<T> (&self) -> Result<Vec<T>, Error> where T: DeserializeOwned {
+        self.get_aggregations()
+            .into_iter()
+            .map(Aggregation::values_as)
+            .collect()
+    }
+}
+
+/// Records deserialized into a user struct
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct TypedResults<T> {
+    pub total_count: usize,
+    pub records: Vec<T>,
 }
 
 /*  -------------------------------------------------------------
@@ -168,3 +366,40 @@ pub struct FacetsCollection {
     pub links: Vec<Link>,
     pub facets: Vec<FacetEnumeration>,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_results_record () {
+        let record: ResultsRecord = serde_json::from_value(json!({
+            "links": [],
+            "record": {
+                "id": "b839362b",
+                "timestamp": "2021-01-07T23:00:00Z",
+                "size": 20,
+                "fields": {"code_postal": "85300"},
+            },
+        })).unwrap();
+        assert!(matches!(record, ResultsRecord::Record(record) if record.record.id == "b839362b"));
+
+        let aggregation: ResultsRecord = serde_json::from_value(json!({"code_postal": "85300", "n": 12})).unwrap();
+        assert!(matches!(aggregation, ResultsRecord::Aggregation(aggregation) if aggregation.get_i64("n") == Some(12)));
+    }
+
+    #[test]
+    fn test_deserialize_results_record_when_malformed () {
+        let result = serde_json::from_value::<ResultsRecord>(json!({
+            "links": [],
+            "record": {
+                "id": "b839362b",
+                "fields": {"code_postal": "85300"},
+            },
+        }));
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,359 @@
+//! HTTP transport shared by the API versions end-points.
+//!
+//! Sends the requests with the configured authentication and headers,
+//! then converts error responses and JSON bodies.
+
+use std::ffi::OsString;
+use std::fmt::{Debug, Formatter};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use ds_http_client::Client as HttpClient;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{StatusCode, Url};
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+use crate::ApiHttpResponse;
+use crate::auth::{ApiKey, ApiKeyLocation};
+use crate::error::Error;
+use crate::export::ExportOptions;
+use crate::query::RecordsQuery;
+
+pub(crate) static USER_AGENT: &str = concat!(
+    env!("CARGO_PKG_NAME"),
+    "/",
+    env!("CARGO_PKG_VERSION"),
+);
+
+/// The delay before the first retry, doubled at each new attempt
+pub(crate) static RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// The maximum delay between two attempts
+pub(crate) static RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// The number of temporary files created by downloads, to give each one a distinct name
+static TEMPORARY_FILES_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Determines if a request is sent with the API key and the extra headers
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Credentials {
+    Included,
+    Omitted,
+}
+
+impl Credentials {
+    /// Includes the credentials only for an URL with the same origin as the API,
+    /// so they aren't leaked to another host, e.g. an attachment stored elsewhere.
+    pub(crate) fn for_url (base_url: &str, url: &str) -> Self {
+        match (Url::parse(base_url), Url::parse(url)) {
+            (Ok(base_url), Ok(url)) if base_url.origin() == url.origin() => Credentials::Included,
+            _ => Credentials::Omitted,
+        }
+    }
+}
+
+pub(crate) struct Transport {
+    client: HttpClient,
+
+    /// The API key to query restricted datasets
+    api_key: Option<ApiKey>,
+
+    /// Extra headers sent with every request
+    headers: HeaderMap,
+
+    /// The number of times to retry a request after a network or server error
+    retries: u32,
+}
+
+impl Transport {
+    pub(crate) fn new (client: HttpClient) -> Self {
+        Self {
+            client,
+            api_key: None,
+            headers: HeaderMap::new(),
+            retries: 0,
+        }
+    }
+
+    pub(crate) fn set_retries (&mut self, retries: u32) {
+        self.retries = retries;
+    }
+
+    pub(crate) fn set_api_key (&mut self, api_key: &str, location: ApiKeyLocation) -> Result<(), Error> {
+        self.api_key = Some(ApiKey::new(api_key, location)?);
+        Ok(())
+    }
+
+    /// Adds a header to send with every request, as a sensitive value.
+    pub(crate) fn insert_header (&mut self, name: &str, value: &str) -> Result<(), Error> {
+        insert_sensitive_header(&mut self.headers, name, value)
+    }
+
+    /// Sends a GET request, and checks the response status.
+    ///
+    /// Failed attempts are retried according to `get_retry_delay`.
+    ///
+    /// When the server replies with an error status, the body is read
+    /// to get the API error code and message.
+    pub(crate) async fn fetch_resource (&self, url: String) -> Result<ApiHttpResponse, Error> {
+        self.fetch_resource_with(url, Credentials::Included).await
+    }
+
+    async fn fetch_resource_with (&self, url: String, credentials: Credentials) -> Result<ApiHttpResponse, Error> {
+        let mut attempt = 0;
+
+        let response = loop {
+            let result = self.send(&url, credentials).await;
+
+            let outcome = result.as_ref().map(|response| response.status());
+            match get_retry_delay(outcome, attempt, self.retries) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => break result.map_err(Error::from_reqwest)?,
+            }
+            attempt += 1;
+        };
+
+        let status = response.status();
+        if is_error_status(status) {
+            let body = response
+                .text().await
+                .map_err(Error::from_reqwest)?;
+
+            return Err(Error::from_response_body(status, &body));
+        }
+
+        Ok(response)
+    }
+
+    async fn send (&self, url: &str, credentials: Credentials) -> Result<ApiHttpResponse, reqwest::Error> {
+        let mut request = self.client.prepare_get(url);
+
+        if credentials == Credentials::Included {
+            request = request.headers(self.headers.clone());
+            if let Some(api_key) = &self.api_key {
+                request = api_key.authenticate(request);
+            }
+        }
+
+        request.send().await
+    }
+
+    pub(crate) async fn fetch<T> (&self, url: String) -> Result<T, Error> where for<'a> T: Deserialize<'a> {
+        let body = self.fetch_resource(url).await?
+            .text().await
+            .map_err(Error::from_reqwest)?;
+
+        serde_json::from_str(&body)
+            .map_err(Error::Json)
+    }
+
+    /// Streams a resource to a file, chunk by chunk.
+    ///
+    /// The resource is written to a temporary file next to the target, renamed once complete,
+    /// so the target is never left truncated by a failed download.
+    ///
+    /// Returns the number of bytes written.
+    pub(crate) async fn download<P> (&self, url: String, target_path: P, credentials: Credentials) -> Result<usize, Error>
+    where P: AsRef<Path> {
+        let response = self.fetch_resource_with(url, credentials).await?;
+
+        let temporary_path = get_temporary_path(target_path.as_ref());
+        let result = match write_response(response, &temporary_path).await {
+            Ok(bytes_written) => tokio::fs::rename(&temporary_path, target_path).await
+                .map(|_| bytes_written)
+                .map_err(Error::IO),
+            Err(error) => Err(error),
+        };
+
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temporary_path).await;
+        }
+
+        result
+    }
+}
+
+async fn write_response (mut response: ApiHttpResponse, path: &Path) -> Result<usize, Error> {
+    let mut file = File::create(path).await
+        .map_err(Error::IO)?;
+
+    let mut bytes_written = 0;
+    while let Some(chunk) = response.chunk().await.map_err(Error::from_reqwest)? {
+        file.write_all(chunk.as_ref()).await
+            .map_err(Error::IO)?;
+        bytes_written += chunk.len();
+    }
+
+    file.flush().await
+        .map_err(Error::IO)?;
+
+    Ok(bytes_written)
+}
+
+impl Debug for Transport {
+    fn fmt (&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let headers: Vec<_> = self.headers.keys().collect();
+
+        f.debug_struct("Transport")
+            .field("api_key", &self.api_key)
+            .field("headers", &headers)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
+/// Adds a header to a map, marking its value as sensitive, so it's not printed.
+pub(crate) fn insert_sensitive_header (headers: &mut HeaderMap, name: &str, value: &str) -> Result<(), Error> {
+    let invalid_header = || Error::InvalidHeader(name.to_string());
+
+    let header_name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| invalid_header())?;
+    let mut value = HeaderValue::from_str(value)
+        .map_err(|_| invalid_header())?;
+    value.set_sensitive(true);
+
+    headers.insert(header_name, value);
+
+    Ok(())
+}
+
+/// Gets a temporary path in the directory of a download target, unique for this process.
+pub(crate) fn get_temporary_path (target_path: &Path) -> PathBuf {
+    let mut file_name = target_path.file_name()
+        .map(OsString::from)
+        .unwrap_or_default();
+    file_name.push(format!(
+        ".{}.{}.part",
+        process::id(),
+        TEMPORARY_FILES_COUNT.fetch_add(1, Ordering::Relaxed),
+    ));
+
+    target_path.with_file_name(file_name)
+}
+
+/// Builds an HTTP client identifying this library as User-Agent.
+pub(crate) fn build_default_http_client () -> HttpClient {
+    let mut headers = HeaderMap::new();
+    headers.insert("User-Agent", HeaderValue::from_static(USER_AGENT));
+
+    HttpClient::new(Some(headers))
+}
+
+/*  -------------------------------------------------------------
+    Retry and error policy
+
+    Shared by the async and the blocking end-points
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Gets the delay to wait before retrying a request, from the outcome of the attempt.
+///
+/// Network errors, server errors and 429 Too Many Requests responses are retried,
+/// with an exponential backoff capped to `RETRY_MAX_DELAY`, up to `retries` times. Returns None when the outcome
+/// should be kept, as a success or as a definitive failure.
+///
+/// * `outcome` - The response status, or the error if the request couldn't be sent
+/// * `attempt` - The number of attempts already retried
+/// * `retries` - The maximum number of retries
+pub(crate) fn get_retry_delay (outcome: Result<StatusCode, &reqwest::Error>, attempt: u32, retries: u32) -> Option<Duration> {
+    let should_retry = match outcome {
+        Ok(status) => is_retryable(status),
+        Err(error) => error.is_connect() || error.is_timeout(),
+    };
+
+    if !should_retry || attempt >= retries {
+        return None;
+    }
+
+    // The factor saturates, as a large number of retries would overflow it.
+    let factor = 2u32.checked_pow(attempt).unwrap_or(u32::MAX);
+
+    Some(RETRY_BASE_DELAY.saturating_mul(factor).min(RETRY_MAX_DELAY))
+}
+
+/// Determines if a response is an error, so its body should be read as an API error document.
+pub(crate) fn is_error_status (status: StatusCode) -> bool {
+    status.is_client_error() || status.is_server_error()
+}
+
+fn is_retryable (status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/*  -------------------------------------------------------------
+    Query parameters
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Appends the query parameters to an URL.
+///
+/// Fails with `Error::InvalidUrl` if the URL can't be parsed.
+pub(crate) fn append_query (url: String, query: &RecordsQuery) -> Result<String, Error> {
+    append_query_pairs(url, query.to_query_pairs())
+}
+
+/// Appends the query and the export options parameters to an URL.
+pub(crate) fn append_export_query (url: String, query: &RecordsQuery, options: &ExportOptions) -> Result<String, Error> {
+    let mut pairs = query.to_query_pairs();
+    pairs.extend(options.to_query_pairs());
+
+    append_query_pairs(url, pairs)
+}
+
+fn append_query_pairs (url: String, pairs: Vec<(&'static str, String)>) -> Result<String, Error> {
+    if pairs.is_empty() {
+        return Ok(url);
+    }
+
+    Url::parse_with_params(&url, pairs)
+        .map(String::from)
+        .map_err(|_| Error::InvalidUrl(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_retry_delay () {
+        assert_eq!(Some(RETRY_BASE_DELAY), get_retry_delay(Ok(StatusCode::BAD_GATEWAY), 0, 2));
+        assert_eq!(Some(RETRY_BASE_DELAY * 2), get_retry_delay(Ok(StatusCode::TOO_MANY_REQUESTS), 1, 2));
+        assert_eq!(None, get_retry_delay(Ok(StatusCode::BAD_GATEWAY), 2, 2));
+    }
+
+    #[test]
+    fn test_get_retry_delay_is_capped () {
+        assert_eq!(Some(RETRY_MAX_DELAY), get_retry_delay(Ok(StatusCode::BAD_GATEWAY), 20, 100));
+        assert_eq!(Some(RETRY_MAX_DELAY), get_retry_delay(Ok(StatusCode::BAD_GATEWAY), 40, 100));
+    }
+
+    #[test]
+    fn test_credentials_for_url () {
+        let base_url = "https://data.economie.gouv.fr/api/v2";
+
+        assert_eq!(Credentials::Included, Credentials::for_url(base_url, "https://data.economie.gouv.fr/explore/file.zip"));
+        assert_eq!(Credentials::Omitted, Credentials::for_url(base_url, "https://static.data.gouv.fr/file.zip"));
+        assert_eq!(Credentials::Omitted, Credentials::for_url(base_url, "http://data.economie.gouv.fr/explore/file.zip"));
+        assert_eq!(Credentials::Omitted, Credentials::for_url(base_url, "/explore/file.zip"));
+    }
+
+    #[test]
+    fn test_get_retry_delay_when_not_retryable () {
+        assert_eq!(None, get_retry_delay(Ok(StatusCode::OK), 0, 2));
+        assert_eq!(None, get_retry_delay(Ok(StatusCode::NOT_FOUND), 0, 2));
+    }
+
+    #[test]
+    fn test_get_temporary_path () {
+        let target_path = Path::new("/tmp/exports/records.csv");
+
+        let first_path = get_temporary_path(target_path);
+        let second_path = get_temporary_path(target_path);
+
+        assert_eq!(Some(Path::new("/tmp/exports")), first_path.parent());
+        assert!(first_path.to_str().unwrap().starts_with("/tmp/exports/records.csv."));
+        assert_ne!(first_path, second_path);
+    }
+}
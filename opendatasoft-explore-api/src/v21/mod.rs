@@ -0,0 +1,23 @@
+//! Client for the Opendatasoft Explore API v2.1.
+//!
+//! The v2.1 offers the same requests as the v2, but with simpler responses.
+//! The `ExploreApiV21EndPoint` struct allows to call it:
+//!
+//! ```rust,no_run
+//! use opendatasoft_explore_api::query::RecordsQuery;
+//! use opendatasoft_explore_api::v21::requests::ExploreApiV21EndPoint;
+//!
+//! async fn print_postal_codes () {
+//!     let endpoint = ExploreApiV21EndPoint::new("https://data.economie.gouv.fr/api/explore/v2.1");
+//!     let query = RecordsQuery::new().select("code_postal").limit(10);
+//!
+//!     let results = endpoint.get_dataset_records("controle_techn", &query).await
+//!         .expect("Can't query records");
+//!     for record in results.results {
+//!         println!("{}", record["code_postal"]);
+//!     }
+//! }
+//! ```
+
+pub mod requests;
+pub mod schema;
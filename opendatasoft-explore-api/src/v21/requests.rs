@@ -0,0 +1,42 @@
+//! Requests for the Opendatasoft Explore API v2.1.
+//!
+//! The `ExploreApiV21EndPoint` struct allows to define an API end-point, and call the Explore API,
+//! ie the methods starting by /catalog in the open data portal documentation.
+//!
+//! The requests are shared with the v2 end-point, only the responses schema differs.
+
+use serde::de::DeserializeOwned;
+
+use crate::error::Error;
+use crate::query::RecordsQuery;
+use crate::requests::{ApiSchema, EndPoint};
+use crate::v21::schema::*;
+
+/// The Explore API v2.1 responses schema
+#[derive(Debug)]
+pub struct V21Schema;
+
+impl ApiSchema for V21Schema {
+    type DatasetsCollection = DatasetsCollection;
+    type Dataset = Dataset;
+    type Results = Results;
+    type Record = Record;
+}
+
+/// The Explore API v2.1 end-point
+pub type ExploreApiV21EndPoint = EndPoint<V21Schema>;
+
+impl ExploreApiV21EndPoint {
+    /// Query datasets records, deserializing them into a user struct
+    ///
+    /// See the fields module for the Rust types matching Opendatasoft field types.
+    ///
+    /// * `dataset_id` - The identifier of the dataset to be queried.
+    /// * `query` - Optional parameters to select, filter, sort or paginate records
+    pub async fn get_dataset_records_as<T>(&self, dataset_id: &str, query: &RecordsQuery) -> Result<TypedResults<T>, Error>
+    where T: DeserializeOwned
+    {
+        self.get_dataset_records(dataset_id, query).await?
+            .records_as()
+    }
+}
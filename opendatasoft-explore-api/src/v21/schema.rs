@@ -0,0 +1,77 @@
+//! Schema for Opendatasoft Explore API v2.1
+//!
+//! Compared to v2, datasets and records aren't wrapped with their links anymore:
+//! collections are returned as a `results` array of flat objects.
+
+use serde::de::DeserializeOwned;
+use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use serde_json::Map as JsonMap;
+use serde_json::Value as JsonValue;
+
+use crate::error::Error;
+
+pub use crate::schema::{
+    Attachment,
+    AttachmentCollection,
+    AttachmentProperties,
+    DatasetField,
+    FacetEnumeration,
+    FacetValueEnumeration,
+    FacetsCollection,
+    Link,
+    TypedResults,
+};
+
+/*  -------------------------------------------------------------
+    dataset
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A dataset, with the same properties as in the v2 `dataset` object
+pub type Dataset = crate::schema::DatasetProperties;
+
+/*  -------------------------------------------------------------
+    results_dataset
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct DatasetsCollection {
+    pub total_count: usize,
+    pub results: Vec<Dataset>,
+}
+
+/*  -------------------------------------------------------------
+    record
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A record, as a map between fields names and values
+///
+/// When the query uses group_by, the map contains the aggregation results.
+pub type Record = JsonMap<String, JsonValue>;
+
+/*  -------------------------------------------------------------
+    results
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct Results {
+    pub total_count: usize,
+    pub results: Vec<Record>,
+}
+
+impl Results {
+    /// Deserializes the records into user structs.
+    ///
+    /// See the fields module for the Rust types matching Opendatasoft field types.
+    pub fn records_as<T> (&self) -> Result<TypedResults<T>, Error> where T: DeserializeOwned {
+        let records = self.results
+            .iter()
+            .map(|record| T::deserialize(record).map_err(Error::Json))
+            .collect::<Result<Vec<T>, Error>>()?;
+
+        Ok(TypedResults {
+            total_count: self.total_count,
+            records,
+        })
+    }
+}
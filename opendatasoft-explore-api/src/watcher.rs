@@ -0,0 +1,325 @@
+//! Detect changes in datasets, to drive a data pipeline.
+//!
+//! The watcher compares the current state of a dataset with the state stored
+//! in a JSON file at the previous run: modification timestamps, records count,
+//! attachments and fields.
+//!
+//! ```no_run
+//! use opendatasoft_explore_api::requests::ExploreApiEndPoint;
+//! use opendatasoft_explore_api::watcher::{Change, DatasetWatcher};
+//!
+//! async fn check_fantoir (endpoint: &ExploreApiEndPoint) {
+//!     let watcher = DatasetWatcher::new(endpoint, "state.json");
+//!
+//!     let change_set = watcher.check("fichier-fantoir-des-voies-et-lieux-dits").await
+//!         .expect("Can't check dataset");
+//!     for change in &change_set.changes {
+//!         if let Change::NewAttachment(attachment) = change {
+//!             println!("New file: {}", attachment.title);
+//!         }
+//!     }
+//!
+//!     // Once the changes are processed, the state is saved for next run.
+//!     watcher.acknowledge(&change_set).await
+//!         .expect("Can't save watcher state");
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::drift::{compare_schemas, SchemaDiff};
+use crate::error::Error;
+use crate::requests::ExploreApiEndPoint;
+use crate::schema::{AttachmentProperties, DatasetField, DatasetProperties};
+
+/*  -------------------------------------------------------------
+    Dataset state
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// The properties of a dataset compared from one run to another
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct DatasetState {
+    pub dataset_id: String,
+
+    /// The metas.default.modified timestamp
+    pub modified: Option<DateTime<Utc>>,
+
+    /// The metas.default.data_processed timestamp
+    pub data_processed: Option<DateTime<Utc>>,
+
+    pub records_count: Option<usize>,
+
+    pub attachments: Vec<AttachmentProperties>,
+
+    pub fields: Vec<DatasetField>,
+}
+
+impl DatasetState {
+    pub fn from_dataset (dataset: &DatasetProperties) -> Self {
+        Self {
+            dataset_id: dataset.dataset_id.clone(),
+            modified: dataset.modified(),
+            data_processed: dataset.data_processed(),
+            records_count: dataset.records_count().map(|count| count as usize),
+            attachments: dataset.attachments.clone(),
+            fields: dataset.fields.clone(),
+        }
+    }
+}
+
+/*  -------------------------------------------------------------
+    Changes
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// A change detected in a dataset since the previous run
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// The dataset wasn't in the state file
+    NewDataset,
+
+    NewAttachment(AttachmentProperties),
+
+    AttachmentRemoved(AttachmentProperties),
+
+    /// The data_processed timestamp changed, or modified if the dataset has no data processing
+    DataUpdated {
+        previous: Option<DateTime<Utc>>,
+        current: Option<DateTime<Utc>>,
+    },
+
+    RecordsCountChanged {
+        previous: Option<usize>,
+        current: Option<usize>,
+    },
+
+    /// Fields have been added, removed, renamed, or have a new type or new annotations
+    SchemaChanged(SchemaDiff),
+}
+
+/// The changes of a dataset, with the state to store once they're processed
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangeSet {
+    pub dataset_id: String,
+    pub changes: Vec<Change>,
+    pub current_state: DatasetState,
+}
+
+impl ChangeSet {
+    pub fn is_empty (&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn get_new_attachments (&self) -> Vec<&AttachmentProperties> {
+        self.changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::NewAttachment(attachment) => Some(attachment),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Compares two states of a dataset.
+pub fn compare_states (previous: Option<&DatasetState>, current: &DatasetState) -> Vec<Change> {
+    let previous = match previous {
+        None => return vec![Change::NewDataset],
+        Some(previous) => previous,
+    };
+
+    let mut changes = Vec::new();
+
+    for attachment in &current.attachments {
+        if !previous.attachments.iter().any(|candidate| candidate.id == attachment.id) {
+            changes.push(Change::NewAttachment(attachment.clone()));
+        }
+    }
+    for attachment in &previous.attachments {
+        if !current.attachments.iter().any(|candidate| candidate.id == attachment.id) {
+            changes.push(Change::AttachmentRemoved(attachment.clone()));
+        }
+    }
+
+    // Some datasets don't process data, e.g. when they only offer attachments.
+    let (previous_data, current_data) = if current.data_processed.is_some() || previous.data_processed.is_some() {
+        (previous.data_processed, current.data_processed)
+    } else {
+        (previous.modified, current.modified)
+    };
+    if previous_data != current_data {
+        changes.push(Change::DataUpdated {
+            previous: previous_data,
+            current: current_data,
+        });
+    }
+
+    if previous.records_count != current.records_count {
+        changes.push(Change::RecordsCountChanged {
+            previous: previous.records_count,
+            current: current.records_count,
+        });
+    }
+
+    let diff = compare_schemas(&previous.fields, &current.fields);
+    if !diff.is_empty() {
+        changes.push(Change::SchemaChanged(diff));
+    }
+
+    changes
+}
+
+/*  -------------------------------------------------------------
+    Watcher
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Checks datasets for changes, against a state file
+pub struct DatasetWatcher<'a> {
+    endpoint: &'a ExploreApiEndPoint,
+
+    /// The JSON file to store datasets states, created at first acknowledgement
+    pub state_path: PathBuf,
+}
+
+impl<'a> DatasetWatcher<'a> {
+    pub fn new<P> (endpoint: &'a ExploreApiEndPoint, state_path: P) -> Self where P: AsRef<Path> {
+        Self {
+            endpoint,
+            state_path: state_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Queries the dataset and compares it to the stored state.
+    ///
+    /// The state file isn't updated: call `acknowledge` once the changes are processed.
+    pub async fn check (&self, dataset_id: &str) -> Result<ChangeSet, Error> {
+        let dataset = self.endpoint.get_dataset_information(dataset_id).await?;
+        let current_state = DatasetState::from_dataset(&dataset.dataset);
+
+        let states = self.read_states().await?;
+        let changes = compare_states(states.get(dataset_id), &current_state);
+
+        Ok(ChangeSet {
+            dataset_id: dataset_id.to_string(),
+            changes,
+            current_state,
+        })
+    }
+
+    /// Stores the current state of the dataset, so next checks compare against it.
+    pub async fn acknowledge (&self, change_set: &ChangeSet) -> Result<(), Error> {
+        let mut states = self.read_states().await?;
+        states.insert(change_set.dataset_id.clone(), change_set.current_state.clone());
+
+        write_states(&self.state_path, &states).await
+    }
+
+    async fn read_states (&self) -> Result<HashMap<String, DatasetState>, Error> {
+        read_states(&self.state_path).await
+    }
+}
+
+/*  -------------------------------------------------------------
+    State file
+    - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - -    */
+
+/// Reads datasets states from a JSON file, or returns an empty map if it doesn't exist.
+pub async fn read_states<P> (path: P) -> Result<HashMap<String, DatasetState>, Error>
+where P: AsRef<Path> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let content = tokio::fs::read_to_string(path).await
+        .map_err(Error::IO)?;
+
+    serde_json::from_str(&content)
+        .map_err(Error::Json)
+}
+
+pub async fn write_states<P> (path: P, states: &HashMap<String, DatasetState>) -> Result<(), Error>
+where P: AsRef<Path> {
+    let content = serde_json::to_string_pretty(states)
+        .map_err(Error::Json)?;
+
+    tokio::fs::write(path, content).await
+        .map_err(Error::IO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_attachment (id: &str) -> AttachmentProperties {
+        AttachmentProperties {
+            mime_type: None,
+            title: id.to_string(),
+            url: format!("odsfile://{}.zip", id),
+            id: id.to_string(),
+        }
+    }
+
+    fn build_field (name: &str, field_type: &str) -> DatasetField {
+        DatasetField {
+            name: name.to_string(),
+            label: name.to_string(),
+            field_type: field_type.to_string(),
+            annotations: serde_json::json!({}),
+            description: None,
+        }
+    }
+
+    fn build_state () -> DatasetState {
+        DatasetState {
+            dataset_id: "fantoir".to_string(),
+            modified: DateTime::parse_from_rfc3339("2022-11-04T09:03:03Z").ok().map(|date| date.with_timezone(&Utc)),
+            data_processed: None,
+            records_count: Some(0),
+            attachments: vec![build_attachment("fantoir_2022_10")],
+            fields: vec![build_field("code", "text")],
+        }
+    }
+
+    #[test]
+    fn test_compare_states_when_new () {
+        assert_eq!(vec![Change::NewDataset], compare_states(None, &build_state()));
+    }
+
+    #[test]
+    fn test_compare_states_when_unchanged () {
+        let state = build_state();
+
+        assert!(compare_states(Some(&state), &state).is_empty());
+    }
+
+    #[test]
+    fn test_compare_states () {
+        let previous = build_state();
+
+        let mut current = build_state();
+        current.modified = None;
+        current.attachments.push(build_attachment("fantoir_2023_01"));
+        current.fields = vec![
+            build_field("code", "int"),
+            build_field("libelle", "text"),
+        ];
+
+        let changes = compare_states(Some(&previous), &current);
+
+        assert_eq!(3, changes.len());
+        assert_eq!(Change::NewAttachment(build_attachment("fantoir_2023_01")), changes[0]);
+        assert!(matches!(changes[1], Change::DataUpdated { current: None, .. }));
+        match &changes[2] {
+            Change::SchemaChanged(diff) => {
+                assert_eq!(vec![build_field("libelle", "text")], diff.added);
+                assert!(diff.removed.is_empty());
+                assert_eq!("code", diff.type_changed[0].name);
+            }
+            change => panic!("Expected a schema change, found {:?}", change),
+        }
+    }
+}
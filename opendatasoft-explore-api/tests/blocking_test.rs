@@ -0,0 +1,96 @@
+//! Integration tests for blocking API requests
+
+#![cfg(feature = "blocking")]
+
+use mockito::{Matcher, Server};
+
+use opendatasoft_explore_api::Error;
+use opendatasoft_explore_api::auth::ApiKeyLocation;
+use opendatasoft_explore_api::blocking::ExploreApiEndPoint;
+use opendatasoft_explore_api::export::{ExportFormat, ExportOptions};
+use opendatasoft_explore_api::query::RecordsQuery;
+
+static TEST_DATASET_ID: &str = "fichier-fantoir-des-voies-et-lieux-dits";
+static TEST_DATASET_WITH_RECORDS_ID: &str = "controle_techn";
+
+#[test]
+fn test_get_dataset_information() {
+    let mut server = Server::new();
+    let mock = server
+        .mock("GET", "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits")
+        .match_header("Authorization", "Apikey s3cr3t")
+        .with_body(include_str!("requests/catalog_dataset_fantoir.json"))
+        .create();
+
+    let dataset = ExploreApiEndPoint::new(&server.url())
+        .with_api_key("s3cr3t", ApiKeyLocation::Header)
+        .unwrap()
+        .get_dataset_information(TEST_DATASET_ID)
+        .unwrap();
+
+    mock.assert();
+    assert_eq!(TEST_DATASET_ID, dataset.dataset.dataset_id);
+    assert_eq!(Some("Fichier FANTOIR des voies et lieux-dits"), dataset.title());
+}
+
+#[test]
+fn test_get_dataset_records() {
+    let mut server = Server::new();
+    server
+        .mock("GET", "/catalog/datasets/controle_techn/records")
+        .match_query(Matcher::UrlEncoded("where".into(), "code_postal = '85300'".into()))
+        .with_body(include_str!("requests/catalog_datasets_records.json"))
+        .create();
+
+    let query = RecordsQuery::new()
+        .where_clause("code_postal = '85300'");
+    let results = ExploreApiEndPoint::new(&server.url())
+        .get_dataset_records(TEST_DATASET_WITH_RECORDS_ID, &query)
+        .unwrap();
+
+    assert_eq!(222629, results.total_count);
+    assert_eq!(3, results.get_records().len());
+}
+
+#[test]
+fn test_api_error() {
+    let mut server = Server::new();
+    server
+        .mock("GET", "/catalog/datasets/unknown")
+        .with_status(404)
+        .with_body(r#"{"error_code": "DatasetNotFound", "message": "Unknown dataset: unknown"}"#)
+        .create();
+
+    let result = ExploreApiEndPoint::new(&server.url())
+        .get_dataset_information("unknown");
+
+    match result {
+        Err(Error::Api { error_code, .. }) => assert_eq!("DatasetNotFound", error_code),
+        other => panic!("Expected an API error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_export_dataset_to_file() {
+    let mut server = Server::new();
+    server
+        .mock("GET", "/catalog/datasets/controle_techn/exports/csv")
+        .match_query(Matcher::UrlEncoded("delimiter".into(), ",".into()))
+        .with_body("Code postal,Dénomination\n85300,AUTO BILAN CHALLANDAIS\n")
+        .create();
+
+    let options = ExportOptions::new()
+        .delimiter(',');
+
+    let target_path = std::env::temp_dir()
+        .join(format!("opendatasoft-explore-api-test-blocking-export-{}.csv", std::process::id()));
+    let bytes_written = ExploreApiEndPoint::new(&server.url())
+        .export_dataset_to_file(TEST_DATASET_WITH_RECORDS_ID, ExportFormat::Csv, &RecordsQuery::new(), &options, &target_path)
+        .unwrap();
+
+    let content = std::fs::read_to_string(&target_path).unwrap();
+    std::fs::remove_file(&target_path).unwrap();
+
+    assert_eq!(content.len(), bytes_written);
+    assert!(content.starts_with("Code postal,"));
+}
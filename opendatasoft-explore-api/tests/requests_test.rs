@@ -2,12 +2,26 @@
 
 use std::collections::HashMap;
 
+use chrono::NaiveDate;
+use ds_http_client::{parse_headers, Client as HttpClient};
+use futures_util::StreamExt;
 use lazy_static::lazy_static;
-use mockito::{Server, ServerGuard};
-use serde_json::json;
+use mockito::{Matcher, Server, ServerGuard};
+use serde_derive::Deserialize;
+use serde_json::{json, Value as JsonValue};
 
+use opendatasoft_explore_api::Error;
+use opendatasoft_explore_api::auth::ApiKeyLocation;
+use opendatasoft_explore_api::export::{ExportFormat, ExportOptions};
+use opendatasoft_explore_api::fields::GeoPoint2D;
+#[cfg(feature = "geo")]
+use opendatasoft_explore_api::geo::FeatureCollectionBuilder;
+use opendatasoft_explore_api::mirror::CatalogMirror;
+use opendatasoft_explore_api::query::{CatalogFacet, RecordsQuery};
+use opendatasoft_explore_api::rdf::dcat::get_catalog_datasets;
 use opendatasoft_explore_api::requests::ExploreApiEndPoint;
 use opendatasoft_explore_api::schema::*;
+use opendatasoft_explore_api::watcher::{Change, DatasetWatcher};
 
 static TEST_URL: &'static str = "https://data.economie.gouv.fr/api/v2";
 static TEST_DATASET_ID: &'static str = "fichier-fantoir-des-voies-et-lieux-dits";
@@ -69,7 +83,7 @@ async fn test_get_datasets() {
     let server = prepare_mock("/catalog/datasets").await;
 
     let endpoint = ExploreApiEndPoint::new(&server.url());
-    let catalog = endpoint.get_datasets().await;
+    let catalog = endpoint.get_datasets(&RecordsQuery::new()).await.unwrap();
 
     assert_eq!(426, catalog.total_count);
     assert_eq!(
@@ -88,8 +102,9 @@ async fn test_export_datasets_catalog() {
     let server = prepare_mock("/catalog/exports/rdf").await;
 
     let mut response = ExploreApiEndPoint::new(&server.url())
-        .export_datasets_catalog("rdf")
-        .await;
+        .export_datasets_catalog(ExportFormat::RdfXml)
+        .await
+        .unwrap();
 
     let mut rdf_about_found = false;
     while let Some(chunk) = response.chunk().await.unwrap() {
@@ -103,12 +118,110 @@ async fn test_export_datasets_catalog() {
     assert!(rdf_about_found);
 }
 
+#[tokio::test]
+async fn test_export_datasets_catalog_graph() {
+    let server = prepare_mock("/catalog/exports/rdf").await;
+
+    let graph = ExploreApiEndPoint::new(&server.url())
+        .export_datasets_catalog_graph(ExportFormat::RdfXml)
+        .await
+        .unwrap();
+
+    let datasets = get_catalog_datasets(&graph);
+    assert_eq!(426, datasets.len());
+
+    let dataset = datasets
+        .iter()
+        .find(|dataset| dataset.identifier.as_deref() == Some("retraite-cube-stock-civil-droit-direct-2"))
+        .expect("Dataset not found in catalog graph");
+
+    assert_eq!(Some("Retraite - Cube stock civil droit direct 2"), dataset.title.as_deref());
+    assert_eq!(Some("http://wiki.data.gouv.fr/wiki/Licence_Ouverte_/_Open_Licence"), dataset.license.as_deref());
+    assert!(dataset.keywords.contains(&"retraites".to_string()));
+
+    let formats: Vec<_> = dataset.distributions
+        .iter()
+        .filter_map(|distribution| distribution.format.as_deref())
+        .collect();
+    assert_eq!(vec!["csv", "json"], formats);
+
+    let csv = &dataset.distributions[0];
+    assert_eq!(Some("text/csv"), csv.media_type.as_deref());
+    assert_eq!(
+        Some("https://data.economie.gouv.fr/api/v2/catalog/datasets/retraite-cube-stock-civil-droit-direct-2/exports/csv"),
+        csv.access_url.as_deref(),
+    );
+}
+
+#[tokio::test]
+async fn test_export_datasets_catalog_graph_when_not_rdf() {
+    let result = ExploreApiEndPoint::new(TEST_URL)
+        .export_datasets_catalog_graph(ExportFormat::Csv)
+        .await;
+
+    assert!(matches!(result, Err(Error::Rdf(_))));
+}
+
+fn build_datasets_page(total_count: usize, ids: &[&str]) -> String {
+    let dataset: JsonValue = serde_json::from_str(MOCK_FILES["/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits"]).unwrap();
+
+    let datasets: Vec<JsonValue> = ids
+        .iter()
+        .map(|id| {
+            let mut dataset = dataset.clone();
+            dataset["dataset"]["dataset_id"] = json!(id);
+            dataset
+        })
+        .collect();
+
+    json!({
+        "total_count": total_count,
+        "links": [],
+        "datasets": datasets,
+    }).to_string()
+}
+
+#[tokio::test]
+async fn test_datasets_stream() {
+    let mut server = Server::new_async().await;
+    for (offset, ids) in [("0", vec!["fantoir", "cadastre"]), ("2", vec!["ban"])] {
+        server
+            .mock("GET", "/catalog/datasets")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("where".into(), r#"search("voies")"#.into()),
+                Matcher::UrlEncoded("refine".into(), "publisher:DGFiP".into()),
+                Matcher::UrlEncoded("exclude".into(), "theme:BUDGET DE L'ETAT".into()),
+                Matcher::UrlEncoded("limit".into(), "2".into()),
+                Matcher::UrlEncoded("offset".into(), offset.into()),
+            ]))
+            .with_body(build_datasets_page(3, &ids))
+            .expect(1)
+            .create_async()
+            .await;
+    }
+
+    let query = RecordsQuery::new()
+        .search("voies")
+        .refine(CatalogFacet::Publisher, "DGFiP")
+        .exclude(CatalogFacet::Theme, "BUDGET DE L'ETAT")
+        .limit(2);
+
+    let endpoint = ExploreApiEndPoint::new(&server.url());
+    let ids: Vec<_> = endpoint
+        .datasets_stream(&query)
+        .map(|dataset| dataset.unwrap().dataset.dataset_id)
+        .collect()
+        .await;
+
+    assert_eq!(vec!["fantoir", "cadastre", "ban"], ids);
+}
+
 #[tokio::test]
 async fn test_get_facets() {
     let server = prepare_mock("/catalog/facets").await;
 
     let endpoint = ExploreApiEndPoint::new(&server.url());
-    let facets = endpoint.get_facets().await;
+    let facets = endpoint.get_facets(&RecordsQuery::new()).await.unwrap();
 
     assert!(facets.links[0].href.starts_with(TEST_URL));
 
@@ -130,8 +243,9 @@ async fn test_get_dataset_records() {
     let server = prepare_mock("/catalog/datasets/controle_techn/records").await;
 
     let results = ExploreApiEndPoint::new(&server.url())
-        .get_dataset_records(TEST_DATASET_WITH_RECORDS_ID)
-        .await;
+        .get_dataset_records(TEST_DATASET_WITH_RECORDS_ID, &RecordsQuery::new())
+        .await
+        .unwrap();
 
     assert_eq!(222629, results.total_count);
 
@@ -153,17 +267,352 @@ async fn test_get_dataset_records() {
     assert!(link.href.contains(TEST_DATASET_WITH_RECORDS_ID));
 }
 
+#[derive(Debug, Deserialize)]
+struct TestingCenter {
+    cct_denomination: String,
+    code_postal: String,
+    cct_tel: Option<String>,
+    prix_visite: u32,
+    date_application_visite: NaiveDate,
+    latitude: GeoPoint2D,
+}
+
+async fn get_group_by_results(dataset_id: &str, query: &RecordsQuery, body: &str) -> Results {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", format!("/catalog/datasets/{}/records", dataset_id).as_str())
+        .match_query(Matcher::UrlEncoded("group_by".into(), query.group_by.clone().unwrap()))
+        .with_body(body)
+        .create_async()
+        .await;
+
+    ExploreApiEndPoint::new(&server.url())
+        .get_dataset_records(dataset_id, query)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_get_dataset_records_group_by() {
+    let query = RecordsQuery::new()
+        .select("count(*), avg(prix_visite)")
+        .where_clause("code_postal like '8530*'")
+        .group_by("code_postal");
+    let results = get_group_by_results(
+        TEST_DATASET_WITH_RECORDS_ID,
+        &query,
+        include_str!("requests/catalog_datasets_records_group_by_code_postal.json"),
+    ).await;
+
+    let aggregations = results.get_aggregations();
+    assert_eq!(3, aggregations.len());
+    assert_eq!(Some("85300"), aggregations[0].get_str("code_postal"));
+    assert_eq!(Some(52), aggregations[0].get_count());
+    assert_eq!(Some(75.0), aggregations[1].get_f64("avg(prix_visite)"));
+    assert_eq!(None, aggregations[2].get_f64("avg(prix_visite)"));
+
+    // Aggregations aren't records
+    assert!(results.records_as::<JsonValue>().unwrap().records.is_empty());
+}
+
+#[tokio::test]
+async fn test_get_dataset_records_group_by_with_aliases() {
+    #[derive(Deserialize)]
+    struct VisitsByYear {
+        year: String,
+        count: u64,
+    }
+
+    let query = RecordsQuery::new()
+        .select("count(*) as count")
+        .group_by("year(date_application_visite) as year");
+    let results = get_group_by_results(
+        TEST_DATASET_WITH_RECORDS_ID,
+        &query,
+        include_str!("requests/catalog_datasets_records_group_by_year.json"),
+    ).await;
+
+    let aggregations: Vec<VisitsByYear> = results.aggregations_as().unwrap();
+    assert_eq!(2, aggregations.len());
+    assert_eq!("2023", aggregations[1].year);
+    assert_eq!(109925, aggregations[1].count);
+
+    let aggregation = results.get_aggregations()[0];
+    assert_eq!(Some(112704), aggregation.get_count());
+    assert_eq!(Some("2022".to_string()), aggregation.get_as::<String>("year").unwrap());
+    assert_eq!(None, aggregation.get_as::<String>("month").unwrap());
+    assert!(aggregation.get_as::<u32>("year").is_err());
+}
+
+#[tokio::test]
+async fn test_get_dataset_records_group_by_on_another_dataset() {
+    let query = RecordsQuery::new()
+        .select("count(*) as count, sum(population) as population")
+        .group_by("cou_name_en")
+        .order_by("count desc");
+    let results = get_group_by_results(
+        "geonames-all-cities-with-a-population-1000",
+        &query,
+        include_str!("requests/catalog_datasets_records_group_by_country.json"),
+    ).await;
+
+    let aggregations = results.get_aggregations();
+    assert_eq!(2, aggregations.len());
+    assert_eq!(Some("United States"), aggregations[0].get_str("cou_name_en"));
+    assert_eq!(Some(24076), aggregations[0].get_count());
+    assert_eq!(Some(263184375), aggregations[0].get_i64("population"));
+}
+
+#[tokio::test]
+async fn test_get_dataset_records_as() {
+    let server = prepare_mock("/catalog/datasets/controle_techn/records").await;
+
+    let results = ExploreApiEndPoint::new(&server.url())
+        .get_dataset_records_as::<TestingCenter>(TEST_DATASET_WITH_RECORDS_ID, &RecordsQuery::new())
+        .await
+        .unwrap();
+
+    assert_eq!(222629, results.total_count);
+
+    let center = &results.records[0];
+    assert_eq!("AUTO BILAN CHALLANDAIS", center.cct_denomination);
+    assert_eq!("85300", center.code_postal);
+    assert_eq!(Some("0251683888".to_string()), center.cct_tel);
+    assert_eq!(94, center.prix_visite);
+    assert_eq!(NaiveDate::from_ymd_opt(2020, 9, 14).unwrap(), center.date_application_visite);
+    assert_eq!(GeoPoint2D { lon: -1.867611, lat: 46.85617 }, center.latitude);
+}
+
+#[tokio::test]
+async fn test_get_dataset_records_with_query() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/records")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("where".into(), "code_postal = '85300'".into()),
+            Matcher::UrlEncoded("limit".into(), "3".into()),
+            Matcher::UrlEncoded("refine".into(), "cct_code_dept:Vendée".into()),
+        ]))
+        .with_body(MOCK_FILES["/catalog/datasets/controle_techn/records"])
+        .create_async()
+        .await;
+
+    let query = RecordsQuery::new()
+        .where_clause("code_postal = '85300'")
+        .limit(3)
+        .refine("cct_code_dept", "Vendée");
+    let results = ExploreApiEndPoint::new(&server.url())
+        .get_dataset_records(TEST_DATASET_WITH_RECORDS_ID, &query)
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(3, results.records.len());
+}
+
+#[cfg(feature = "geo")]
+#[tokio::test]
+async fn test_records_to_geojson() {
+    let server = prepare_mock("/catalog/datasets/controle_techn/records").await;
+
+    let results = ExploreApiEndPoint::new(&server.url())
+        .get_dataset_records(TEST_DATASET_WITH_RECORDS_ID, &RecordsQuery::new())
+        .await
+        .unwrap();
+
+    let collection = FeatureCollectionBuilder::new("latitude")
+        .with_properties(&["cct_denomination", "code_postal"])
+        .from_results(&results);
+    assert_eq!(3, collection.features.len());
+
+    let geojson: JsonValue = serde_json::from_str(&collection.to_string()).unwrap();
+    assert_eq!("FeatureCollection", geojson["type"]);
+    assert_eq!(json!({"type": "Point", "coordinates": [-1.867611, 46.85617]}), geojson["features"][0]["geometry"]);
+    assert_eq!(json!("85300"), geojson["features"][0]["properties"]["code_postal"]);
+}
+
+fn build_records_page(total_count: usize, ids: &[&str]) -> String {
+    let records: Vec<JsonValue> = ids
+        .iter()
+        .map(|id| json!({
+            "links": [],
+            "record": {
+                "id": id,
+                "timestamp": "2021-01-07T23:00:00Z",
+                "size": 20,
+                "fields": { "code_postal": "85300" },
+            },
+        }))
+        .collect();
+
+    json!({
+        "total_count": total_count,
+        "links": [],
+        "records": records,
+    }).to_string()
+}
+
+#[tokio::test]
+async fn test_records_stream() {
+    let mut server = Server::new_async().await;
+    for (offset, ids) in [("0", vec!["a", "b", "c"]), ("3", vec!["d", "e"])] {
+        server
+            .mock("GET", "/catalog/datasets/controle_techn/records")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("limit".into(), "3".into()),
+                Matcher::UrlEncoded("offset".into(), offset.into()),
+            ]))
+            .with_body(build_records_page(5, &ids))
+            .expect(1)
+            .create_async()
+            .await;
+    }
+
+    let endpoint = ExploreApiEndPoint::new(&server.url());
+    let query = RecordsQuery::new().limit(3);
+    let ids: Vec<_> = endpoint
+        .records_stream(TEST_DATASET_WITH_RECORDS_ID, &query)
+        .map(|record| record.unwrap().record.id)
+        .collect()
+        .await;
+
+    assert_eq!(vec!["a", "b", "c", "d", "e"], ids);
+}
+
+fn build_export_lines(ids: &[&str]) -> String {
+    ids
+        .iter()
+        .map(|id| json!({"recordid": id, "fields": {"code_postal": "85300"}}).to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[tokio::test]
+async fn test_records_stream_switches_to_export() {
+    // The dataset has more records than the records endpoint can return,
+    // and the export doesn't give them in the same order.
+    let mut server = Server::new_async().await;
+    let records_mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/records")
+        .match_query(Matcher::Any)
+        .with_body(build_records_page(20_000, &["b", "a", "c"]))
+        .expect(1)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/catalog/datasets/controle_techn/exports/jsonl")
+        .match_query(Matcher::UrlEncoded("where".into(), "code_postal = '85300'".into()))
+        .with_body(build_export_lines(&["a", "b", "c", "d", "e", "f"]))
+        .create_async()
+        .await;
+
+    let endpoint = ExploreApiEndPoint::new(&server.url());
+    let query = RecordsQuery::new()
+        .where_clause("code_postal = '85300'")
+        .limit(3)
+        .offset(1);
+    let ids: Vec<_> = endpoint
+        .records_stream(TEST_DATASET_WITH_RECORDS_ID, &query)
+        .map(|record| record.unwrap().record.id)
+        .collect()
+        .await;
+
+    // The first page is dropped, every record is read from the export, after the offset.
+    records_mock.assert_async().await;
+    assert_eq!(vec!["b", "c", "d", "e", "f"], ids);
+}
+
+#[tokio::test]
+async fn test_records_stream_with_offset_beyond_records_endpoint() {
+    let mut server = Server::new_async().await;
+    let records_mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/records")
+        .match_query(Matcher::Any)
+        .expect(0)
+        .create_async()
+        .await;
+    let ids: Vec<String> = (0..10_002).map(|n| n.to_string()).collect();
+    let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+    server
+        .mock("GET", "/catalog/datasets/controle_techn/exports/jsonl")
+        .with_body(build_export_lines(&ids))
+        .create_async()
+        .await;
+
+    let endpoint = ExploreApiEndPoint::new(&server.url());
+    let query = RecordsQuery::new().offset(10_000);
+    let ids: Vec<_> = endpoint
+        .records_stream(TEST_DATASET_WITH_RECORDS_ID, &query)
+        .map(|record| record.unwrap().record.id)
+        .collect()
+        .await;
+
+    records_mock.assert_async().await;
+    assert_eq!(vec!["10000", "10001"], ids);
+}
+
+#[tokio::test]
+async fn test_export_records_stream() {
+    let mut server = Server::new_async().await;
+    let records_mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/records")
+        .match_query(Matcher::Any)
+        .expect(0)
+        .create_async()
+        .await;
+    server
+        .mock("GET", "/catalog/datasets/controle_techn/exports/jsonl")
+        .with_body("{\"code_postal\": \"85300\"}\n{\"code_postal\": \"85310\"}\n")
+        .create_async()
+        .await;
+
+    let endpoint = ExploreApiEndPoint::new(&server.url());
+    let records: Vec<_> = endpoint
+        .export_records_stream(TEST_DATASET_WITH_RECORDS_ID, &RecordsQuery::new())
+        .collect()
+        .await;
+
+    records_mock.assert_async().await;
+    assert_eq!(2, records.len());
+    let record = records[1].as_ref().unwrap();
+    assert_eq!(Some(&json!("85310")), record.record.fields.get("code_postal"));
+}
+
 #[tokio::test]
 async fn test_get_dataset_information() {
     let server = prepare_mock("/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits").await;
 
     let dataset = ExploreApiEndPoint::new(&server.url())
         .get_dataset_information(TEST_DATASET_ID)
-        .await;
+        .await
+        .unwrap();
 
     assert_eq!(TEST_DATASET_ID, dataset.dataset.dataset_id);
 }
 
+#[tokio::test]
+async fn test_get_dataset_information_metas() {
+    let server = prepare_mock("/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits").await;
+
+    let dataset = ExploreApiEndPoint::new(&server.url())
+        .get_dataset_information(TEST_DATASET_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(Some("Fichier FANTOIR des voies et lieux-dits"), dataset.title());
+    assert_eq!(Some("Licence Ouverte v2.0 (Etalab)"), dataset.license());
+    assert_eq!(
+        Some(NaiveDate::from_ymd_opt(2022, 11, 4).unwrap()),
+        dataset.modified().map(|modified| modified.date_naive())
+    );
+    assert_eq!(Some("DGFiP"), dataset.dataset.publisher());
+    assert!(dataset.dataset.keywords().contains(&"fantoir".to_string()));
+    assert_eq!(None, dataset.dataset.data_processed());
+
+    // Custom templates are kept
+    assert_eq!(Some(json!(["DGFIP"])), dataset.dataset.metas.get("custom", "editeur"));
+}
+
 #[tokio::test]
 async fn test_get_dataset_attachments() {
     let server =
@@ -171,7 +620,8 @@ async fn test_get_dataset_attachments() {
 
     let attachments = ExploreApiEndPoint::new(&server.url())
         .get_dataset_attachments(TEST_DATASET_ID)
-        .await;
+        .await
+        .unwrap();
 
     assert!(attachments.attachments[0]
         .metas
@@ -186,7 +636,8 @@ async fn test_get_dataset_facets() {
 
     let facets = ExploreApiEndPoint::new(&server.url())
         .get_dataset_facets(TEST_DATASET_ID)
-        .await;
+        .await
+        .unwrap();
 
     assert!(facets.links[0].href.starts_with(TEST_URL));
 }
@@ -200,7 +651,383 @@ async fn test_get_dataset_record() {
 
     let record = ExploreApiEndPoint::new(&server.url())
         .get_dataset_record(TEST_DATASET_WITH_RECORDS_ID, TEST_RECORD_ID)
-        .await;
+        .await
+        .unwrap();
 
     assert_eq!(TEST_RECORD_ID, record.record.id);
 }
+
+#[tokio::test]
+async fn test_get_dataset_information_when_dataset_is_unknown() {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", "/catalog/datasets/foo")
+        .with_status(404)
+        .with_body(r#"{"message": "Unknown dataset: foo", "error_code": "NotFound"}"#)
+        .create_async()
+        .await;
+
+    let error = ExploreApiEndPoint::new(&server.url())
+        .get_dataset_information("foo")
+        .await
+        .unwrap_err();
+
+    match error {
+        Error::Api { status, error_code, message } => {
+            assert_eq!(404, status.as_u16());
+            assert_eq!("NotFound", error_code);
+            assert_eq!("Unknown dataset: foo", message);
+        }
+        _ => panic!("Expected an API error, got {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn test_get_datasets_when_response_is_invalid() {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", "/catalog/datasets")
+        .with_body("{}")
+        .create_async()
+        .await;
+
+    let result = ExploreApiEndPoint::new(&server.url())
+        .get_datasets(&RecordsQuery::new())
+        .await;
+
+    assert!(matches!(result, Err(Error::Json(_))));
+}
+
+#[tokio::test]
+async fn test_api_key_as_header() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits")
+        .match_header("Authorization", "Apikey s3cr3t")
+        .match_header("X-Partner", "nasqueron")
+        .with_body(MOCK_FILES["/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits"])
+        .create_async()
+        .await;
+
+    ExploreApiEndPoint::new(&server.url())
+        .with_api_key("s3cr3t", ApiKeyLocation::Header)
+        .unwrap()
+        .with_header("X-Partner", "nasqueron")
+        .unwrap()
+        .get_dataset_information(TEST_DATASET_ID)
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[test]
+fn test_with_invalid_header() {
+    let result = ExploreApiEndPoint::new(TEST_URL)
+        .with_header("X-Partner", "nasqueron\r\nX-Injected: 1");
+
+    match result {
+        Err(Error::InvalidHeader(name)) => assert_eq!("X-Partner", name),
+        other => panic!("Expected an invalid header error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_from_http_client() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits")
+        .match_header("User-Agent", "fantoir-datasource/0.1.0 (https://databases.nasqueron.org/)")
+        .match_header("Authorization", "Apikey s3cr3t")
+        .with_body(MOCK_FILES["/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits"])
+        .create_async()
+        .await;
+
+    let mut headers = HashMap::new();
+    headers.insert(
+        "User-Agent".to_string(),
+        "fantoir-datasource/0.1.0 (https://databases.nasqueron.org/)".to_string(),
+    );
+    let http_client = HttpClient::new(Some(parse_headers(headers)));
+
+    ExploreApiEndPoint::from_http_client(&server.url(), http_client)
+        .with_api_key("s3cr3t", ApiKeyLocation::Header)
+        .unwrap()
+        .get_dataset_information(TEST_DATASET_ID)
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_key_as_query_parameter() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/records")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("limit".into(), "10".into()),
+            Matcher::UrlEncoded("apikey".into(), "s3cr3t".into()),
+        ]))
+        .with_body(MOCK_FILES["/catalog/datasets/controle_techn/records"])
+        .create_async()
+        .await;
+
+    ExploreApiEndPoint::new(&server.url())
+        .with_api_key("s3cr3t", ApiKeyLocation::QueryParameter)
+        .unwrap()
+        .get_dataset_records(TEST_DATASET_WITH_RECORDS_ID, &RecordsQuery::new().limit(10))
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_api_key_is_redacted_from_errors() {
+    // Nothing listens on port 1, so the request fails with an URL in the error.
+    let error = ExploreApiEndPoint::new("http://127.0.0.1:1")
+        .with_api_key("s3cr3t", ApiKeyLocation::QueryParameter)
+        .unwrap()
+        .get_dataset_information(TEST_DATASET_ID)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, Error::Reqwest(_)));
+    assert!(!error.to_string().contains("s3cr3t"));
+    assert!(!format!("{:?}", error).contains("s3cr3t"));
+}
+
+#[tokio::test]
+async fn test_invalid_endpoint_url() {
+    let query = RecordsQuery::new().limit(10);
+    let endpoint = ExploreApiEndPoint::new("not an url");
+
+    let error = endpoint.get_datasets(&query).await.unwrap_err();
+    assert!(matches!(error, Error::InvalidUrl(_)));
+
+    let error = endpoint.get_dataset_records(TEST_DATASET_ID, &query).await.unwrap_err();
+    assert!(matches!(error, Error::InvalidUrl(_)));
+}
+
+#[tokio::test]
+async fn test_export_dataset_to_file() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/exports/csv")
+        .match_query(Matcher::AllOf(vec![
+            Matcher::UrlEncoded("select".into(), "code_postal, cct_denomination".into()),
+            Matcher::UrlEncoded("where".into(), "code_postal = '85300'".into()),
+            Matcher::UrlEncoded("delimiter".into(), ",".into()),
+            Matcher::UrlEncoded("use_labels".into(), "true".into()),
+        ]))
+        .with_body("Code postal,Dénomination\n85300,AUTO BILAN CHALLANDAIS\n")
+        .create_async()
+        .await;
+
+    let query = RecordsQuery::new()
+        .select("code_postal, cct_denomination")
+        .where_clause("code_postal = '85300'");
+    let options = ExportOptions::new()
+        .delimiter(',')
+        .use_labels(true);
+
+    let target_path = std::env::temp_dir().join("opendatasoft-explore-api-test-export.csv");
+    let bytes_written = ExploreApiEndPoint::new(&server.url())
+        .export_dataset_to_file(TEST_DATASET_WITH_RECORDS_ID, ExportFormat::Csv, &query, &options, &target_path)
+        .await
+        .unwrap();
+
+    let content = std::fs::read_to_string(&target_path).unwrap();
+    std::fs::remove_file(&target_path).unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(content.len(), bytes_written);
+    assert_eq!("Code postal,Dénomination\n85300,AUTO BILAN CHALLANDAIS\n", content);
+}
+
+#[tokio::test]
+async fn test_dataset_watcher() {
+    let server = prepare_mock("/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits").await;
+    let endpoint = ExploreApiEndPoint::new(&server.url());
+
+    let state_path = std::env::temp_dir()
+        .join(format!("opendatasoft-explore-api-test-dataset-watcher-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&state_path);
+    let watcher = DatasetWatcher::new(&endpoint, &state_path);
+
+    let change_set = watcher.check(TEST_DATASET_ID).await.unwrap();
+    assert_eq!(vec![Change::NewDataset], change_set.changes);
+    assert!(!change_set.current_state.attachments.is_empty());
+
+    watcher.acknowledge(&change_set).await.unwrap();
+    let change_set = watcher.check(TEST_DATASET_ID).await.unwrap();
+    std::fs::remove_file(&state_path).unwrap();
+
+    assert!(change_set.is_empty());
+}
+
+#[tokio::test]
+async fn test_catalog_mirror() {
+    let mut server = Server::new_async().await;
+    let dataset: JsonValue = serde_json::from_str(MOCK_FILES["/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits"]).unwrap();
+    server
+        .mock("GET", "/catalog/datasets")
+        .match_query(Matcher::Any)
+        .with_body(json!({"total_count": 1, "links": [], "datasets": [dataset]}).to_string())
+        .create_async()
+        .await;
+    for url in [
+        "/catalog/facets",
+        "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits",
+        "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits/attachments",
+    ] {
+        server
+            .mock("GET", url)
+            .match_query(Matcher::Any)
+            .with_body(MOCK_FILES[url])
+            .create_async()
+            .await;
+    }
+    let export_mock = server
+        .mock("GET", "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits/exports/csv")
+        .match_query(Matcher::Any)
+        .with_body("code;libelle\n")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let directory = std::env::temp_dir()
+        .join(format!("opendatasoft-explore-api-test-catalog-mirror-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+
+    let endpoint = ExploreApiEndPoint::new(&server.url());
+    let mirror = CatalogMirror::new(&endpoint, &directory)
+        .with_exports(vec![TEST_DATASET_ID.to_string()], ExportFormat::Csv);
+
+    let report = mirror.run().await.unwrap();
+    assert_eq!(1, report.datasets_count);
+    assert_eq!(vec![TEST_DATASET_ID.to_string()], report.refreshed);
+    assert_eq!(vec![TEST_DATASET_ID.to_string()], report.exported);
+
+    let dataset_path = directory.join("datasets").join(TEST_DATASET_ID);
+    assert!(directory.join("catalog/facets.json").exists());
+    assert!(dataset_path.join("attachments.json").exists());
+    assert!(dataset_path.join("export.csv").exists());
+
+    // At second run, the dataset didn't change, so nothing is refreshed.
+    let report = mirror.run().await.unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(report.refreshed.is_empty());
+    assert!(report.exported.is_empty());
+    export_mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_download_attachment() {
+    let mut server = Server::new_async().await;
+    let mock = server
+        .mock("GET", "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits/attachments/fantoir_zip")
+        .match_header("Authorization", "Apikey s3cr3t")
+        .with_body("PK")
+        .create_async()
+        .await;
+
+    let attachment = Attachment {
+        href: format!("{}/catalog/datasets/{}/attachments/fantoir_zip", server.url(), TEST_DATASET_ID),
+        metas: AttachmentProperties::default(),
+    };
+
+    let target_path = std::env::temp_dir()
+        .join(format!("opendatasoft-explore-api-test-download-attachment-{}.zip", std::process::id()));
+    let bytes_written = ExploreApiEndPoint::new(&server.url())
+        .with_api_key("s3cr3t", ApiKeyLocation::Header)
+        .unwrap()
+        .download_attachment(&attachment, &target_path)
+        .await
+        .unwrap();
+
+    let content = std::fs::read(&target_path).unwrap();
+    std::fs::remove_file(&target_path).unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(2, bytes_written);
+    assert_eq!(b"PK".to_vec(), content);
+}
+
+#[tokio::test]
+async fn test_download_attachment_from_another_host() {
+    let server = Server::new_async().await;
+    let mut attachments_server = Server::new_async().await;
+    let mock = attachments_server
+        .mock("GET", "/fantoir.zip")
+        .match_header("Authorization", Matcher::Missing)
+        .match_header("X-Token", Matcher::Missing)
+        .with_body("PK")
+        .create_async()
+        .await;
+
+    let attachment = Attachment {
+        href: format!("{}/fantoir.zip", attachments_server.url()),
+        metas: AttachmentProperties::default(),
+    };
+
+    let target_path = std::env::temp_dir()
+        .join(format!("opendatasoft-explore-api-test-download-attachment-from-another-host-{}.zip", std::process::id()));
+    ExploreApiEndPoint::new(&server.url())
+        .with_api_key("s3cr3t", ApiKeyLocation::Header)
+        .unwrap()
+        .with_header("X-Token", "t0k3n")
+        .unwrap()
+        .download_attachment(&attachment, &target_path)
+        .await
+        .unwrap();
+    std::fs::remove_file(&target_path).unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_get_file_with_retries() {
+    let mut server = Server::new_async().await;
+    let failing_mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/files/1c1a3d1e")
+        .with_status(503)
+        .expect(1)
+        .create_async()
+        .await;
+    let mock = server
+        .mock("GET", "/catalog/datasets/controle_techn/files/1c1a3d1e")
+        .with_body("GIF89a")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let response = ExploreApiEndPoint::new(&server.url())
+        .with_retries(1)
+        .get_file(TEST_DATASET_WITH_RECORDS_ID, "1c1a3d1e")
+        .await
+        .unwrap();
+
+    failing_mock.assert_async().await;
+    mock.assert_async().await;
+    assert_eq!("GIF89a", response.text().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_get_file_without_retries() {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", "/catalog/datasets/controle_techn/files/1c1a3d1e")
+        .with_status(503)
+        .create_async()
+        .await;
+
+    let error = ExploreApiEndPoint::new(&server.url())
+        .get_file(TEST_DATASET_WITH_RECORDS_ID, "1c1a3d1e")
+        .await
+        .unwrap_err();
+
+    assert_eq!(Some(503), error.status().map(|status| status.as_u16()));
+}
@@ -0,0 +1,129 @@
+//! Integration tests for API v2.1 requests
+
+use mockito::{Matcher, Server, ServerGuard};
+use serde_derive::Deserialize;
+use serde_json::json;
+
+use opendatasoft_explore_api::fields::GeoPoint2D;
+use opendatasoft_explore_api::query::RecordsQuery;
+use opendatasoft_explore_api::v21::requests::ExploreApiV21EndPoint;
+
+static TEST_DATASET_ID: &str = "fichier-fantoir-des-voies-et-lieux-dits";
+
+static TEST_DATASET_WITH_RECORDS_ID: &str = "controle_techn";
+static TEST_RECORD_ID: &str = "eb04cba18e872814448a7fda829f3f1918cfae0b";
+
+pub async fn prepare_mock(url: &str, body: &str) -> ServerGuard {
+    let mut server = Server::new_async().await;
+    server
+        .mock("GET", url)
+        .match_query(Matcher::Any)
+        .with_body(body)
+        .create_async()
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn test_get_datasets() {
+    let server = prepare_mock(
+        "/catalog/datasets",
+        include_str!("requests/v2.1/catalog_datasets.json"),
+    ).await;
+
+    let catalog = ExploreApiV21EndPoint::new(&server.url())
+        .get_datasets(&RecordsQuery::new())
+        .await
+        .unwrap();
+
+    assert_eq!(426, catalog.total_count);
+    assert_eq!(3, catalog.results.len());
+    assert_eq!("mef-catalogue-temporaire", catalog.results[0].dataset_id);
+}
+
+#[tokio::test]
+async fn test_get_dataset_records() {
+    let server = prepare_mock(
+        "/catalog/datasets/controle_techn/records",
+        include_str!("requests/v2.1/catalog_datasets_records.json"),
+    ).await;
+
+    let query = RecordsQuery::new().limit(3);
+    let results = ExploreApiV21EndPoint::new(&server.url())
+        .get_dataset_records(TEST_DATASET_WITH_RECORDS_ID, &query)
+        .await
+        .unwrap();
+
+    assert_eq!(222629, results.total_count);
+    assert_eq!(3, results.results.len());
+    assert_eq!(json!("85300"), results.results[0]["code_postal"]);
+}
+
+#[derive(Deserialize)]
+struct TestingCenter {
+    code_postal: String,
+    latitude: GeoPoint2D,
+}
+
+#[tokio::test]
+async fn test_get_dataset_records_as() {
+    let server = prepare_mock(
+        "/catalog/datasets/controle_techn/records",
+        include_str!("requests/v2.1/catalog_datasets_records.json"),
+    ).await;
+
+    let results = ExploreApiV21EndPoint::new(&server.url())
+        .get_dataset_records_as::<TestingCenter>(TEST_DATASET_WITH_RECORDS_ID, &RecordsQuery::new())
+        .await
+        .unwrap();
+
+    assert_eq!(3, results.records.len());
+    assert_eq!("85300", results.records[0].code_postal);
+    assert_eq!(46.85617, results.records[0].latitude.lat);
+}
+
+#[tokio::test]
+async fn test_get_dataset_information() {
+    let server = prepare_mock(
+        "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits",
+        include_str!("requests/v2.1/catalog_dataset_fantoir.json"),
+    ).await;
+
+    let dataset = ExploreApiV21EndPoint::new(&server.url())
+        .get_dataset_information(TEST_DATASET_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(TEST_DATASET_ID, dataset.dataset_id);
+    assert!(!dataset.has_records);
+}
+
+#[tokio::test]
+async fn test_get_dataset_attachments() {
+    let server = prepare_mock(
+        "/catalog/datasets/fichier-fantoir-des-voies-et-lieux-dits/attachments",
+        include_str!("requests/v2.1/catalog_dataset_fantoir_attachments.json"),
+    ).await;
+
+    let attachments = ExploreApiV21EndPoint::new(&server.url())
+        .get_dataset_attachments(TEST_DATASET_ID)
+        .await
+        .unwrap();
+
+    assert!(attachments.attachments[0].href.contains("/api/v2.1/"));
+}
+
+#[tokio::test]
+async fn test_get_dataset_record() {
+    let server = prepare_mock(
+        "/catalog/datasets/controle_techn/records/eb04cba18e872814448a7fda829f3f1918cfae0b",
+        include_str!("requests/v2.1/catalog_dataset_record.json"),
+    ).await;
+
+    let record = ExploreApiV21EndPoint::new(&server.url())
+        .get_dataset_record(TEST_DATASET_WITH_RECORDS_ID, TEST_RECORD_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(json!("85700"), record["code_postal"]);
+}
@@ -0,0 +1,190 @@
+//! # Batched SPARQL queries
+//!
+//! Runs a large number of queries against the same endpoint,
+//! for example one query per department or per commune,
+//! with a bounded number of queries in flight at the same time.
+//!
+//! Each result is tagged with the input used to build the query.
+//! A failed query is reported in its item, and doesn't abort the batch.
+
+use std::collections::HashMap;
+
+use futures_util::{stream, Stream, StreamExt};
+
+use crate::{Client, Error, SparqlResults};
+
+/*   -------------------------------------------------------------
+     Batch item
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// The results of one query of a batch, with the input used to build it
+#[derive(Debug)]
+pub struct BatchItem<T> {
+    pub input: T,
+    pub results: Result<SparqlResults, Error>,
+}
+
+/*   -------------------------------------------------------------
+     Query template
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// A SPARQL query with {{name}} placeholders
+///
+/// Values are inserted as is, so they should already be formatted
+/// as SPARQL terms, e.g. `"75056"` for a literal or `wd:Q90` for an item.
+#[derive(Clone, Debug)]
+pub struct QueryTemplate {
+    pub template: String,
+}
+
+impl QueryTemplate {
+    pub fn new (template: &str) -> Self {
+        Self {
+            template: template.to_string(),
+        }
+    }
+
+    /// Replaces the placeholders by their values, in a single pass over the template.
+    ///
+    /// Values aren't scanned for placeholders, and placeholders without value are kept as is.
+    pub fn render (&self, parameters: &HashMap<String, String>) -> String {
+        let mut query = String::with_capacity(self.template.len());
+        let mut rest = self.template.as_str();
+
+        while let Some(start) = rest.find("{{") {
+            query.push_str(&rest[..start]);
+
+            let placeholder = &rest[start..];
+            let end = match placeholder.find("}}") {
+                Some(end) => end + 2,
+                None => {
+                    rest = placeholder;
+                    break;
+                }
+            };
+
+            match parameters.get(&placeholder[2..end - 2]) {
+                Some(value) => query.push_str(value),
+                None => query.push_str(&placeholder[..end]),
+            }
+
+            rest = &placeholder[end..];
+        }
+
+        query.push_str(rest);
+        query
+    }
+}
+
+/*   -------------------------------------------------------------
+     Batch queries
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+impl Client {
+    /// Runs queries with at most `concurrency` queries in flight.
+    ///
+    /// Results are returned in the same order as the queries.
+    pub async fn query_batch<I, T> (&self, queries: I, concurrency: usize) -> Vec<BatchItem<T>>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.query_batch_stream(queries, |query| query.as_ref().to_string(), concurrency)
+            .collect()
+            .await
+    }
+
+    /// Runs a query template for each parameters set,
+    /// with at most `concurrency` queries in flight.
+    ///
+    /// Results are returned in the same order as the parameters sets.
+    pub async fn query_template_batch<I> (
+        &self,
+        template: &QueryTemplate,
+        parameters: I,
+        concurrency: usize,
+    ) -> Vec<BatchItem<HashMap<String, String>>>
+    where
+        I: IntoIterator<Item = HashMap<String, String>>,
+    {
+        self.query_batch_stream(parameters, |parameters| template.render(parameters), concurrency)
+            .collect()
+            .await
+    }
+
+    /// Builds a query for each input, and returns a stream of the results
+    /// as soon as they are available, in the same order as the inputs,
+    /// with at most `concurrency` queries in flight.
+    pub fn query_batch_stream<'a, I, T, F> (
+        &'a self,
+        inputs: I,
+        build_query: F,
+        concurrency: usize,
+    ) -> impl Stream<Item = BatchItem<T>> + 'a
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: 'a,
+        T: 'a,
+        F: Fn(&T) -> String + 'a,
+    {
+        stream::iter(inputs)
+            .map(move |input| {
+                let query = build_query(&input);
+
+                async move {
+                    let results = self.try_query(&query).await;
+
+                    BatchItem { input, results }
+                }
+            })
+            .buffered(concurrency.max(1))
+    }
+}
+
+/*   -------------------------------------------------------------
+     Tests
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_render_template () {
+        let template = QueryTemplate::new(r#"SELECT ?item WHERE { ?item wdt:P374 {{code_insee}} . }"#);
+
+        let mut parameters = HashMap::new();
+        parameters.insert("code_insee".to_string(), r#""75056""#.to_string());
+
+        assert_eq!(
+            r#"SELECT ?item WHERE { ?item wdt:P374 "75056" . }"#,
+            template.render(&parameters)
+        );
+    }
+
+    #[test]
+    pub fn test_render_template_in_a_single_pass () {
+        let template = QueryTemplate::new("{{a}} {{b}} {{unknown}} {{");
+        let parameters = HashMap::from([
+            ("a".to_string(), "{{b}}".to_string()),
+            ("b".to_string(), "{{a}}".to_string()),
+        ]);
+
+        assert_eq!("{{b}} {{a}} {{unknown}} {{", template.render(&parameters));
+    }
+
+    #[tokio::test]
+    pub async fn test_query_batch_reports_failures () {
+        // Nothing listens on this port, so every query fails.
+        let client = Client::new("http://127.0.0.1:9/sparql");
+        let queries = vec!["ASK { ?s ?p ?o }", "ASK { ?s ?p 1 }"];
+
+        let items = client.query_batch(queries.clone(), 2).await;
+
+        assert_eq!(2, items.len());
+        for (item, query) in items.iter().zip(queries) {
+            assert_eq!(query, item.input);
+            assert!(matches!(item.results, Err(Error::Http(_))));
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! # On-disk cache for SPARQL query results
+//!
+//! Results are stored as received from the endpoint, in the
+//! SPARQL Query Results XML Format, so a cached file can also
+//! be archived as the snapshot used for an import.
+//!
+//! Entries are keyed by a SHA-256 hash of the endpoint URL
+//! and the normalized query text.
+//!
+//! Files are written under a temporary name then renamed,
+//! so concurrent queries never read a partially written entry.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+
+use sha2::{Digest, Sha256};
+use tokio::fs;
+
+/*   -------------------------------------------------------------
+     Query cache
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// Extension of the cached files, as registered for SPARQL XML results
+static CACHE_FILE_EXTENSION: &str = "srx";
+
+/// Counter to give each cache write its own temporary file
+static TEMPORARY_FILES_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// On-disk cache for SPARQL query results
+#[derive(Clone, Debug)]
+pub struct QueryCache {
+    /// The directory where results are stored
+    pub directory: PathBuf,
+
+    /// How long a cached result stays fresh. None means forever.
+    pub ttl: Option<Duration>,
+
+    /// When set, cached results are ignored, but still refreshed
+    /// with the new results from the endpoint.
+    pub force_refresh: bool,
+}
+
+impl QueryCache {
+    pub fn new<P>(directory: P) -> Self
+    where P: AsRef<Path>
+    {
+        Self {
+            directory: directory.as_ref().to_path_buf(),
+            ttl: None,
+            force_refresh: false,
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_force_refresh(mut self, force_refresh: bool) -> Self {
+        self.force_refresh = force_refresh;
+        self
+    }
+
+    /// Gets the path of the file where results for this query are stored
+    pub fn get_path(&self, endpoint: &str, query: &str) -> PathBuf {
+        let filename = format!("{}.{}", compute_cache_key(endpoint, query), CACHE_FILE_EXTENSION);
+
+        self.directory.join(filename)
+    }
+
+    /// Reads cached results for a query, if they exist and are still fresh
+    pub async fn get(&self, endpoint: &str, query: &str) -> Option<String> {
+        if self.force_refresh {
+            return None;
+        }
+
+        let path = self.get_path(endpoint, query);
+        let metadata = fs::metadata(&path).await.ok()?;
+
+        if let Some(ttl) = self.ttl {
+            let age = metadata.modified().ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .unwrap_or(Duration::MAX);
+
+            if age > ttl {
+                return None;
+            }
+        }
+
+        fs::read_to_string(&path).await.ok()
+    }
+
+    /// Stores results for a query
+    pub async fn set(&self, endpoint: &str, query: &str, query_results: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.directory).await?;
+
+        let path = self.get_path(endpoint, query);
+        let temporary_path = path.with_extension(format!(
+            "{}.{}.tmp",
+            process::id(),
+            TEMPORARY_FILES_COUNT.fetch_add(1, Ordering::Relaxed),
+        ));
+
+        fs::write(&temporary_path, query_results).await?;
+
+        if let Err(error) = fs::rename(&temporary_path, &path).await {
+            let _ = fs::remove_file(&temporary_path).await;
+            return Err(error);
+        }
+
+        Ok(())
+    }
+}
+
+/*   -------------------------------------------------------------
+     Cache key
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// Computes the cache key for a query against an endpoint,
+/// as an hexadecimal SHA-256 hash.
+pub fn compute_cache_key(endpoint: &str, query: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(endpoint.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(normalize_query(query).as_bytes());
+
+    format!("{:x}", hasher.finalize())
+}
+
+/// Normalizes a query so leading or trailing whitespace doesn't change the cache key.
+///
+/// Whitespace inside the query is kept as is, as it can be meaningful
+/// in literals, including multi-line \"\"\"...\"\"\" ones.
+pub fn normalize_query(query: &str) -> &str {
+    query.trim()
+}
+
+/*   -------------------------------------------------------------
+     Tests
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ENDPOINT: &str = "https://query.wikidata.org/sparql";
+
+    #[test]
+    pub fn test_normalize_query() {
+        let query = r#"
+SELECT ?item
+WHERE {
+    ?item rdfs:label """Rue de la Paix
+        Paris""" .
+}
+        "#;
+
+        let expected = "SELECT ?item\nWHERE {\n    ?item rdfs:label \"\"\"Rue de la Paix\n        Paris\"\"\" .\n}";
+        assert_eq!(expected, normalize_query(query));
+    }
+
+    #[test]
+    pub fn test_compute_cache_key() {
+        let key = compute_cache_key(ENDPOINT, "ASK { ?s ?p ?o }");
+
+        assert_eq!(64, key.len());
+        assert_eq!(key, compute_cache_key(ENDPOINT, "  ASK { ?s ?p ?o }\n"));
+        assert_ne!(key, compute_cache_key("https://example.org/sparql", "ASK { ?s ?p ?o }"));
+        assert_ne!(key, compute_cache_key(ENDPOINT, "ASK { ?s ?p \"o\" }"));
+
+        // Whitespace inside a literal is meaningful
+        assert_ne!(
+            compute_cache_key(ENDPOINT, "ASK { ?s ?p \"\"\"a\n  b\"\"\" }"),
+            compute_cache_key(ENDPOINT, "ASK { ?s ?p \"\"\"a\nb\"\"\" }"),
+        );
+    }
+
+    #[tokio::test]
+    pub async fn test_cache_roundtrip() {
+        let directory = std::env::temp_dir()
+            .join(format!("sparql-client-test-cache-roundtrip-{}", process::id()));
+        let query = "ASK { ?s ?p ?o }";
+        let cache = QueryCache::new(&directory);
+
+        cache.set(ENDPOINT, query, "<sparql />").await.unwrap();
+        assert_eq!(Some("<sparql />".to_string()), cache.get(ENDPOINT, query).await);
+
+        let cache = cache.with_force_refresh(true);
+        assert_eq!(None, cache.get(ENDPOINT, query).await);
+
+        let cache = QueryCache::new(&directory).with_ttl(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(None, cache.get(ENDPOINT, query).await);
+
+        fs::remove_dir_all(&directory).await.unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+//! # SPARQL 1.1 Graph Store HTTP Protocol client
+//!
+//! Reads, replaces, merges or deletes graphs in an RDF store,
+//! see <https://www.w3.org/TR/sparql11-http-rdf-update/>.
+//!
+//! Graphs are identified indirectly, through the `graph` or `default`
+//! query parameters of the graph store endpoint.
+
+use std::collections::HashMap;
+
+use ds_http_client::Client as HttpClient;
+use ds_http_client::Error as HttpError;
+use oxrdf::{Graph, NamedNode};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Response, StatusCode, Url};
+
+use crate::rdf::{parse_ntriples, serialize_graph, RdfFormat};
+use crate::{get_user_agent, Error};
+
+/*   -------------------------------------------------------------
+     Graph Store client
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// Graph Store HTTP Protocol client
+pub struct GraphStoreClient {
+    /// The graph store endpoint, e.g. http://localhost:7878/store
+    pub endpoint: String,
+    http_client: HttpClient,
+}
+
+impl GraphStoreClient {
+    pub fn new (endpoint: &str) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert("User-Agent", HeaderValue::from_static(get_user_agent()));
+        let http_client = HttpClient::new(Some(headers));
+
+        Self::from_http_client(endpoint, http_client)
+    }
+
+    pub fn from_http_client(endpoint: &str, http_client: HttpClient) -> Self {
+        Self {
+            endpoint: String::from(endpoint),
+            http_client,
+        }
+    }
+
+    /// Gets the content of a graph.
+    ///
+    /// * `graph` - The named graph to read, or None for the default graph
+    ///
+    /// Returns None if the store doesn't contain this graph.
+    pub async fn get_graph (&self, graph: Option<&NamedNode>) -> Result<Option<Graph>, Error> {
+        let mut headers = HashMap::new();
+        headers.insert("Accept".to_string(), RdfFormat::NTriples.media_type().to_string());
+
+        let response = self.http_client
+            .get_with_headers(self.get_graph_url(graph)?, headers).await
+            .map_err(Error::Http)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let document = check_status(response).await?
+            .text().await
+            .map_err(|error| Error::Http(HttpError::Reqwest(error)))?;
+
+        parse_ntriples(&document)
+            .map(Some)
+            .map_err(Error::RdfSyntax)
+    }
+
+    /// Replaces the content of a graph, creating it if needed.
+    ///
+    /// This is the method to use to publish atomically a new version of a graph.
+    pub async fn put_graph (&self, graph: Option<&NamedNode>, content: &Graph, format: RdfFormat) -> Result<(), Error> {
+        let response = self.http_client
+            .put_with_headers(
+                self.get_graph_url(graph)?,
+                get_content_headers(format),
+                serialize_graph(content, format),
+            ).await
+            .map_err(Error::Http)?;
+
+        check_status(response).await.map(|_| ())
+    }
+
+    /// Merges triples into a graph, creating it if needed.
+    pub async fn post_graph (&self, graph: Option<&NamedNode>, content: &Graph, format: RdfFormat) -> Result<(), Error> {
+        let response = self.http_client
+            .post_with_headers(
+                self.get_graph_url(graph)?,
+                get_content_headers(format),
+                serialize_graph(content, format),
+            ).await
+            .map_err(Error::Http)?;
+
+        check_status(response).await.map(|_| ())
+    }
+
+    /// Deletes a graph.
+    pub async fn delete_graph (&self, graph: Option<&NamedNode>) -> Result<(), Error> {
+        let response = self.http_client
+            .delete_with_headers(self.get_graph_url(graph)?, HashMap::new()).await
+            .map_err(Error::Http)?;
+
+        check_status(response).await.map(|_| ())
+    }
+
+    fn get_graph_url (&self, graph: Option<&NamedNode>) -> Result<Url, Error> {
+        let mut url = Url::parse(&self.endpoint)
+            .map_err(Error::Endpoint)?;
+
+        match graph {
+            None => {
+                // The default parameter has no value, so it's appended to the query as is.
+                let query = match url.query() {
+                    Some(query) if !query.is_empty() => format!("{}&default", query),
+                    _ => "default".to_string(),
+                };
+                url.set_query(Some(&query));
+            },
+            Some(graph) => {
+                url.query_pairs_mut().append_pair("graph", graph.as_str());
+            },
+        }
+
+        Ok(url)
+    }
+}
+
+/*   -------------------------------------------------------------
+     Helper methods
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+fn get_content_headers (format: RdfFormat) -> HashMap<String, String> {
+    let mut headers = HashMap::new();
+    headers.insert("Content-Type".to_string(), format.media_type().to_string());
+
+    headers
+}
+
+/// Checks the response status, keeping the body of error responses,
+/// as stores explain there why a request has been rejected.
+async fn check_status (response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(response);
+    }
+
+    let body = response
+        .text().await
+        .map_err(|error| Error::Http(HttpError::Reqwest(error)))?;
+
+    Err(Error::Store { status, body })
+}
+
+/*   -------------------------------------------------------------
+     Tests
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+#[cfg(test)]
+mod tests {
+    use mockito::{Matcher, Server};
+    use oxrdf::{Literal, Triple};
+
+    use super::*;
+
+    static GRAPH_IRI: &str = "https://data.nasqueron.org/graph/fantoir";
+
+    #[test]
+    pub fn test_get_graph_url () {
+        let client = GraphStoreClient::new("http://localhost:7878/store");
+
+        assert_eq!(
+            "http://localhost:7878/store?default",
+            client.get_graph_url(None).unwrap().as_str()
+        );
+
+        let graph = NamedNode::new(GRAPH_IRI).unwrap();
+        assert_eq!(
+            "http://localhost:7878/store?graph=https%3A%2F%2Fdata.nasqueron.org%2Fgraph%2Ffantoir",
+            client.get_graph_url(Some(&graph)).unwrap().as_str()
+        );
+    }
+
+    #[test]
+    pub fn test_get_graph_url_keeps_endpoint_query () {
+        let client = GraphStoreClient::new("http://localhost:8080/rdf4j/statements?repository=fantoir");
+
+        assert_eq!(
+            "http://localhost:8080/rdf4j/statements?repository=fantoir&default",
+            client.get_graph_url(None).unwrap().as_str()
+        );
+
+        let graph = NamedNode::new(GRAPH_IRI).unwrap();
+        assert_eq!(
+            "http://localhost:8080/rdf4j/statements?repository=fantoir&graph=https%3A%2F%2Fdata.nasqueron.org%2Fgraph%2Ffantoir",
+            client.get_graph_url(Some(&graph)).unwrap().as_str()
+        );
+    }
+
+    #[tokio::test]
+    pub async fn test_put_graph () {
+        let mut server = Server::new_async().await;
+        let mock = server
+            .mock("PUT", "/store")
+            .match_query(Matcher::UrlEncoded("graph".into(), GRAPH_IRI.into()))
+            .match_header("Content-Type", "application/n-triples")
+            .match_body("<http://example.org/s> <http://example.org/p> \"o\" .\n")
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let mut content = Graph::new();
+        content.insert(&Triple::new(
+            NamedNode::new("http://example.org/s").unwrap(),
+            NamedNode::new("http://example.org/p").unwrap(),
+            Literal::new_simple_literal("o"),
+        ));
+
+        let graph = NamedNode::new(GRAPH_IRI).unwrap();
+        GraphStoreClient::new(&format!("{}/store", server.url()))
+            .put_graph(Some(&graph), &content, RdfFormat::NTriples)
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    pub async fn test_get_graph () {
+        let mut server = Server::new_async().await;
+        server
+            .mock("GET", "/store?default")
+            .with_body("<http://example.org/s> <http://example.org/p> <http://example.org/o> .\n")
+            .create_async()
+            .await;
+        server
+            .mock("GET", "/store")
+            .match_query(Matcher::UrlEncoded("graph".into(), GRAPH_IRI.into()))
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let client = GraphStoreClient::new(&format!("{}/store", server.url()));
+
+        let graph = client.get_graph(None).await.unwrap().unwrap();
+        assert_eq!(1, graph.len());
+
+        let graph_name = NamedNode::new(GRAPH_IRI).unwrap();
+        assert!(client.get_graph(Some(&graph_name)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    pub async fn test_delete_graph_when_store_fails () {
+        let mut server = Server::new_async().await;
+        server
+            .mock("DELETE", "/store?default")
+            .with_status(500)
+            .with_body("Store is read-only")
+            .create_async()
+            .await;
+
+        let result = GraphStoreClient::new(&format!("{}/store", server.url()))
+            .delete_graph(None)
+            .await;
+
+        match result {
+            Err(Error::Store { status, body }) => {
+                assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status);
+                assert_eq!("Store is read-only", body);
+            }
+            other => panic!("Expected a store error, got {:?}", other),
+        }
+    }
+}
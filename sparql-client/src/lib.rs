@@ -1,15 +1,25 @@
 //! # SPARQL client
 
 use std::collections::HashMap;
-use std::io::BufRead;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, Write};
 
 use ds_http_client::Client as HttpClient;
+use ds_http_client::Error as HttpError;
 use lazy_static::lazy_static;
-use oxrdf::Term;
+use oxrdf::{Term, Variable};
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Url;
 use sparesults::*;
 
+use crate::cache::QueryCache;
+
+pub mod batch;
+pub mod cache;
+pub mod graph_store;
+pub mod rdf;
+
 type SparqlSolution = HashMap<String, Term>;
 
 /*   -------------------------------------------------------------
@@ -20,6 +30,7 @@ type SparqlSolution = HashMap<String, Term>;
 pub struct Client {
     pub endpoint: String,
     http_client: HttpClient,
+    cache: Option<QueryCache>,
 }
 
 impl Client {
@@ -35,21 +46,63 @@ impl Client {
         Self {
             endpoint: String::from(endpoint),
             http_client,
+            cache: None,
         }
     }
 
+    /// Stores query results on disk, and reuse them for the same queries.
+    pub fn with_cache(mut self, cache: QueryCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub async fn query (&self, query: &str) -> SparqlResults {
+        self.try_query(query)
+            .await
+            .expect("Can't query endpoint")
+    }
+
+    /// Queries the endpoint, returning an error instead of panicking
+    /// if the endpoint can't be reached or replies with invalid results.
+    ///
+    /// Cached results which can't be parsed are discarded and fetched again.
+    pub async fn try_query (&self, query: &str) -> Result<SparqlResults, Error> {
+        if let Some(cache) = &self.cache {
+            if let Some(query_results) = cache.get(&self.endpoint, query).await {
+                if let Ok(results) = try_parse_sparql_results(&query_results) {
+                    return Ok(results);
+                }
+            }
+        }
+
+        let query_results = self.fetch_query_results(query).await?;
+        let results = try_parse_sparql_results(&query_results)
+            .map_err(Error::Parse)?;
+
+        // Only results we could parse are cached
+        if let Some(cache) = &self.cache {
+            cache.set(&self.endpoint, query, &query_results).await
+                .map_err(Error::Cache)?;
+        }
+
+        Ok(results)
+    }
+
+    async fn fetch_query_results (&self, query: &str) -> Result<String, Error> {
         let url = Url::parse_with_params(&self.endpoint, &[("query", query)])
-            .expect("Can't parse endpoint as absolute URL.");
+            .map_err(Error::Endpoint)?;
         let headers = self.get_query_headers();
 
-        let query_results = self.http_client
+        self.http_client
             .get_with_headers(url, headers).await
-            .expect("Can't query endpoint")
+            .and_then(|response| {
+                response
+                    .error_for_status()
+                    .map_err(HttpError::Reqwest)
+            })
+            .map_err(Error::Http)?
             .text().await
-            .expect("End-point didn't return a reply.");
-
-        parse_sparql_results(&query_results)
+            .map_err(|error| Error::Http(HttpError::Reqwest(error)))
     }
 
     fn get_query_headers(&self) -> HashMap<String, String> {
@@ -66,9 +119,15 @@ impl Client {
 
 /// Represent results for a SPARQL query
 /// A query can return a collection of solutions or a boolean.
+#[derive(Debug)]
 pub enum SparqlResults {
     /// Results for SELECT queries
-    Solutions(Vec<SparqlSolution>),
+    Solutions {
+        /// The variables of the query, in the order of the results header
+        variables: Vec<Variable>,
+
+        solutions: Vec<SparqlSolution>,
+    },
 
     /// Results for INSERT DATA, UPDATE DATA, etc. queries
     Boolean(bool),
@@ -76,55 +135,98 @@ pub enum SparqlResults {
 
 impl SparqlResults {
     pub fn read<T>(reader: QueryResultsReader<T>) -> Self
+    where
+        T: BufRead
+    {
+        Self::try_read(reader)
+            .expect("Can't read solution")
+    }
+
+    pub fn try_read<T>(reader: QueryResultsReader<T>) -> Result<Self, ParseError>
     where
         T: BufRead
     {
         match reader {
             QueryResultsReader::Solutions(solutions) => {
-                Self::Solutions(parse_sparql_solutions(solutions))
+                let variables = solutions.variables().to_vec();
+
+                Ok(Self::Solutions {
+                    variables,
+                    solutions: parse_sparql_solutions(solutions)?,
+                })
             },
-            QueryResultsReader::Boolean(bool) => Self::Boolean(bool),
+            QueryResultsReader::Boolean(bool) => Ok(Self::Boolean(bool)),
+        }
+    }
+
+    /// Writes the results in one of the standard SPARQL results formats:
+    /// XML, JSON, CSV or TSV.
+    pub fn write<W>(&self, writer: W, format: QueryResultsFormat) -> io::Result<W>
+    where
+        W: Write
+    {
+        let serializer = QueryResultsSerializer::from_format(format);
+
+        match self {
+            SparqlResults::Solutions { variables, solutions } => {
+                let mut solutions_writer = serializer.solutions_writer(writer, variables.clone())?;
+
+                for solution in solutions {
+                    solutions_writer.write(
+                        variables
+                            .iter()
+                            .filter_map(|variable| {
+                                solution
+                                    .get(variable.as_str())
+                                    .map(|term| (variable.as_ref(), term.as_ref()))
+                            })
+                    )?;
+                }
+
+                solutions_writer.finish()
+            },
+            SparqlResults::Boolean(bool) => serializer.write_boolean_result(writer, *bool),
         }
     }
 
     pub fn into_solutions(self) -> Option<Vec<SparqlSolution>> {
         match self {
-            SparqlResults::Solutions(solutions) => Some(solutions),
+            SparqlResults::Solutions { solutions, .. } => Some(solutions),
             SparqlResults::Boolean(_) => None,
         }
     }
 
     pub fn into_bool(self) -> Option<bool> {
         match self {
-            SparqlResults::Solutions(_) => None,
+            SparqlResults::Solutions { .. } => None,
             SparqlResults::Boolean(bool) => Some(bool),
         }
     }
 }
 
 pub fn parse_sparql_results (query_results: &str) -> SparqlResults {
-    let results_reader = get_query_results_xml_reader(query_results.as_bytes());
+    try_parse_sparql_results(query_results)
+        .expect("Can't read SPARQL results")
+}
+
+pub fn try_parse_sparql_results (query_results: &str) -> Result<SparqlResults, ParseError> {
+    let results_reader = get_query_results_xml_reader(query_results.as_bytes())?;
 
-    SparqlResults::read(results_reader)
+    SparqlResults::try_read(results_reader)
 }
 
-fn get_query_results_xml_reader<T>(reader: T) -> QueryResultsReader<T>
+fn get_query_results_xml_reader<T>(reader: T) -> Result<QueryResultsReader<T>, ParseError>
 where T: BufRead
 {
     QueryResultsParser::from_format(QueryResultsFormat::Xml)
         .read_results(reader)
-        .expect("Can't read SPARQL results")
 }
 
-fn parse_sparql_solutions<T> (solutions: SolutionsReader<T>) -> Vec<SparqlSolution>
+fn parse_sparql_solutions<T> (solutions: SolutionsReader<T>) -> Result<Vec<SparqlSolution>, ParseError>
 where T: BufRead
 {
     solutions
-        .map(|solution| {
-            parse_sparql_result(
-                solution.expect("Can't read solution")
-            )
-        })
+        .map(|solution| solution.map(parse_sparql_result))
         .collect()
 }
 
@@ -170,6 +272,64 @@ pub fn is_term_empty(term: &Term) -> bool {
     }
 }
 
+/*   -------------------------------------------------------------
+     SPARQL client error
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// SPARQL client error
+#[derive(Debug)]
+pub enum Error {
+    /// Represents an endpoint which can't be parsed as an absolute URL.
+    Endpoint(url::ParseError),
+
+    /// Represents an error from the HTTP client when querying the endpoint,
+    /// including replies with an HTTP error status code.
+    Http(HttpError),
+
+    /// Represents an error when parsing the SPARQL results returned by the endpoint.
+    Parse(ParseError),
+
+    /// Represents an error status returned by a graph store, with the response body.
+    Store {
+        status: reqwest::StatusCode,
+        body: String,
+    },
+
+    /// Represents an error when parsing a RDF graph returned by a graph store.
+    RdfSyntax(rdf::RdfParseError),
+
+    /// Represents an error when writing query results to the cache.
+    Cache(io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Endpoint(error) => write!(f, "Can't parse endpoint as absolute URL: {}", error),
+            Error::Http(HttpError::Reqwest(error)) => write!(f, "HTTP error: {}", error),
+            Error::Http(HttpError::IO(error)) => write!(f, "IO error: {}", error),
+            Error::Parse(error) => write!(f, "Can't parse SPARQL results: {}", error),
+            Error::Store { status, body } => write!(f, "Graph store replied {}: {}", status, body),
+            Error::RdfSyntax(error) => write!(f, "Can't parse RDF graph: {}", error),
+            Error::Cache(error) => write!(f, "Can't write SPARQL results to cache: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Endpoint(error) => Some(error),
+            Error::Http(HttpError::Reqwest(error)) => Some(error),
+            Error::Http(HttpError::IO(error)) => Some(error),
+            Error::Parse(error) => Some(error),
+            Error::Cache(error) => Some(error),
+            Error::RdfSyntax(error) => Some(error),
+            Error::Store { .. } => None,
+        }
+    }
+}
+
 /*   -------------------------------------------------------------
      User agent
 
@@ -249,4 +409,122 @@ mod tests {
         assert!(actual.is_some());
         assert!(actual.unwrap());
     }
+
+    #[test]
+    pub fn test_write_solution_results () {
+        let solutions_result = r#"
+<?xml version="1.0"?>
+<sparql xmlns="http://www.w3.org/2005/sparql-results#">
+  <head>
+    <variable name="item"/>
+    <variable name="label"/>
+  </head>
+  <results>
+    <result>
+      <binding name="label">
+        <literal>Rue de la Paix</literal>
+      </binding>
+      <binding name="item">
+        <uri>http://www.wikidata.org/entity/Q1630316</uri>
+      </binding>
+    </result>
+  </results>
+</sparql>
+        "#;
+
+        let results = parse_sparql_results(solutions_result);
+
+        let csv = results.write(Vec::new(), QueryResultsFormat::Csv).unwrap();
+        assert_eq!(
+            "item,label\r\nhttp://www.wikidata.org/entity/Q1630316,Rue de la Paix\r\n",
+            String::from_utf8(csv).unwrap()
+        );
+
+        let tsv = results.write(Vec::new(), QueryResultsFormat::Tsv).unwrap();
+        assert_eq!(
+            "?item\t?label\n<http://www.wikidata.org/entity/Q1630316>\t\"Rue de la Paix\"\n",
+            String::from_utf8(tsv).unwrap()
+        );
+
+        // XML and JSON outputs can be read back.
+        for format in [QueryResultsFormat::Xml, QueryResultsFormat::Json] {
+            let buffer = results.write(Vec::new(), format).unwrap();
+            let reader = QueryResultsParser::from_format(format)
+                .read_results(buffer.as_slice())
+                .unwrap();
+
+            let solutions = SparqlResults::read(reader).into_solutions().unwrap();
+            assert_eq!(
+                Some("Rue de la Paix".to_string()),
+                parse_literal(&solutions[0]["label"])
+            );
+        }
+    }
+
+    #[tokio::test]
+    pub async fn test_try_query_does_not_cache_invalid_results () {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", mockito::Matcher::Any)
+            .with_body("<html>Service temporarily unavailable</html>")
+            .create_async()
+            .await;
+
+        let directory = std::env::temp_dir()
+            .join(format!("sparql-client-test-invalid-results-{}", std::process::id()));
+        let cache = QueryCache::new(&directory);
+        let client = Client::new(&format!("{}/sparql", server.url()))
+            .with_cache(cache.clone());
+
+        let query = "ASK { ?s ?p ?o }";
+        assert!(matches!(client.try_query(query).await, Err(Error::Parse(_))));
+        assert_eq!(None, cache.get(&client.endpoint, query).await);
+
+        let _ = tokio::fs::remove_dir_all(&directory).await;
+    }
+
+    #[tokio::test]
+    pub async fn test_try_query_refetches_invalid_cached_results () {
+        let query_results = r#"<?xml version="1.0"?>
+<sparql xmlns="http://www.w3.org/2005/sparql-results#"><head/><boolean>true</boolean></sparql>"#;
+
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", mockito::Matcher::Any)
+            .with_body(query_results)
+            .create_async()
+            .await;
+
+        let directory = std::env::temp_dir()
+            .join(format!("sparql-client-test-invalid-cache-{}", std::process::id()));
+        let cache = QueryCache::new(&directory);
+        let client = Client::new(&format!("{}/sparql", server.url()))
+            .with_cache(cache.clone());
+
+        let query = "ASK { ?s ?p ?o }";
+        cache.set(&client.endpoint, query, "<html>truncated").await.unwrap();
+
+        assert!(matches!(client.try_query(query).await, Ok(SparqlResults::Boolean(true))));
+        assert_eq!(Some(query_results.to_string()), cache.get(&client.endpoint, query).await);
+        mock.assert_async().await;
+
+        tokio::fs::remove_dir_all(&directory).await.unwrap();
+    }
+
+    #[tokio::test]
+    pub async fn test_try_query_with_relative_endpoint () {
+        let client = Client::new("/sparql");
+
+        let error = client.try_query("ASK { ?s ?p ?o }").await.unwrap_err();
+        assert!(matches!(error, Error::Endpoint(_)));
+        assert!(error.to_string().starts_with("Can't parse endpoint as absolute URL"));
+    }
+
+    #[test]
+    pub fn test_write_boolean_results () {
+        let results = SparqlResults::Boolean(true);
+
+        let json = results.write(Vec::new(), QueryResultsFormat::Json).unwrap();
+        assert_eq!("{\"head\":{},\"boolean\":true}", String::from_utf8(json).unwrap());
+    }
 }
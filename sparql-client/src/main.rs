@@ -0,0 +1,54 @@
+use std::io;
+use std::process::exit;
+
+use clap::Parser;
+use sparesults::QueryResultsFormat;
+
+use sparql_client::Client;
+
+#[derive(Debug, Parser)]
+#[command(name = "sparql-client")]
+#[clap(author="Nasqueron project", version, about="Query a SPARQL endpoint and print results", long_about=None)]
+pub struct SparqlClientArgs {
+    /// The SPARQL endpoint URL
+    #[arg(long, short = 'e')]
+    endpoint: String,
+
+    /// The output format: xml, json, csv or tsv
+    #[arg(long, short = 'f', default_value = "csv", value_parser = parse_format)]
+    format: QueryResultsFormat,
+
+    /// The path to the file containing the SPARQL query
+    query_file: String,
+}
+
+fn parse_format(format: &str) -> Result<QueryResultsFormat, String> {
+    QueryResultsFormat::from_extension(format)
+        .ok_or_else(|| format!("Unknown SPARQL results format: {}", format))
+}
+
+#[tokio::main]
+async fn main() {
+    let args = SparqlClientArgs::parse(); // Will exit if argument is missing or --help/--version provided.
+
+    let query = match std::fs::read_to_string(&args.query_file) {
+        Ok(query) => query,
+        Err(error) => {
+            eprintln!("Can't read query file: {}", error);
+            exit(2);
+        }
+    };
+
+    let results = match Client::new(&args.endpoint).try_query(&query).await {
+        Ok(results) => results,
+        Err(error) => {
+            eprintln!("Can't query SPARQL endpoint: {}", error);
+            exit(3);
+        }
+    };
+
+    if let Err(error) = results.write(io::stdout().lock(), args.format) {
+        eprintln!("Can't write results: {}", error);
+        exit(4);
+    }
+}
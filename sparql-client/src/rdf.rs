@@ -0,0 +1,118 @@
+//! # RDF graphs serialization
+//!
+//! Serializes an `oxrdf::Graph` to N-Triples or Turtle,
+//! and parses N-Triples documents into a graph, through the ds-rdf crate.
+
+use ds_rdf::turtle;
+use oxrdf::Graph;
+
+pub use ds_rdf::RdfParseError;
+
+/*   -------------------------------------------------------------
+     RDF formats
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// RDF serialization formats supported to send graphs
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RdfFormat {
+    NTriples,
+    Turtle,
+}
+
+impl RdfFormat {
+    pub fn media_type(self) -> &'static str {
+        match self {
+            RdfFormat::NTriples => "application/n-triples",
+            RdfFormat::Turtle => "text/turtle",
+        }
+    }
+
+    pub fn file_extension(self) -> &'static str {
+        match self {
+            RdfFormat::NTriples => "nt",
+            RdfFormat::Turtle => "ttl",
+        }
+    }
+}
+
+/*   -------------------------------------------------------------
+     Serialization
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+pub fn serialize_graph(graph: &Graph, format: RdfFormat) -> String {
+    match format {
+        RdfFormat::NTriples => graph.to_string(),
+        RdfFormat::Turtle => turtle::serialize(graph),
+    }
+}
+
+/*   -------------------------------------------------------------
+     Parsing
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+/// Parses a N-Triples document into a graph.
+///
+/// Blank nodes labels are scoped to the document: each label
+/// is mapped to a fresh blank node, so graphs can be merged safely.
+/// RDF-star quoted triples are supported.
+///
+/// The document is read by the Turtle parser, as N-Triples is a subset of Turtle.
+pub fn parse_ntriples(document: &str) -> Result<Graph, RdfParseError> {
+    turtle::parse(document, None)
+}
+
+/*   -------------------------------------------------------------
+     Tests
+     - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - - */
+
+#[cfg(test)]
+mod tests {
+    use oxrdf::{Literal, NamedNode, Triple};
+
+    use super::*;
+
+    fn build_graph() -> Graph {
+        let item = NamedNode::new("http://www.wikidata.org/entity/Q1630316").unwrap();
+
+        let mut graph = Graph::new();
+        graph.insert(&Triple::new(
+            item.clone(),
+            NamedNode::new("http://www.w3.org/2000/01/rdf-schema#label").unwrap(),
+            Literal::new_language_tagged_literal("Rue de la \"Paix\"", "fr").unwrap(),
+        ));
+        graph.insert(&Triple::new(
+            item,
+            NamedNode::new("http://www.wikidata.org/prop/direct/P3182").unwrap(),
+            Literal::new_simple_literal("751566969Y"),
+        ));
+
+        graph
+    }
+
+    #[test]
+    pub fn test_ntriples_roundtrip() {
+        let graph = build_graph();
+
+        let document = serialize_graph(&graph, RdfFormat::NTriples);
+        assert_eq!(2, document.lines().count());
+
+        let actual = parse_ntriples(&document).unwrap();
+        assert_eq!(graph, actual);
+    }
+
+    #[test]
+    pub fn test_parse_ntriples_with_syntax_error() {
+        let document = "# A comment\n\n<http://example.org/s> \"p\" <http://example.org/o> .\n";
+
+        let error = parse_ntriples(document).unwrap_err();
+        assert_eq!(Some(3), error.line);
+    }
+
+    #[test]
+    pub fn test_parse_ntriples_with_quoted_triple() {
+        let document = "<< _:b1 <http://example.org/p> _:b2 >> <http://example.org/source> <http://example.org/s> .\n";
+
+        let graph = parse_ntriples(document).unwrap();
+        assert_eq!(1, graph.len());
+    }
+}